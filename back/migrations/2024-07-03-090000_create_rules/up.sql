@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE rules(
+    solution_id INTEGER NOT NULL REFERENCES solutions ON DELETE CASCADE,
+    id INTEGER NOT NULL,
+    constraint_name TEXT NOT NULL,
+    constraint_type TEXT,
+    PRIMARY KEY (solution_id, id)
+);
\ No newline at end of file
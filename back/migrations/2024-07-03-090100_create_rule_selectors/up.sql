@@ -0,0 +1,10 @@
+-- Your SQL goes here
+CREATE TABLE rule_selectors(
+    solution_id INTEGER NOT NULL REFERENCES solutions ON DELETE CASCADE,
+    rule_id INTEGER NOT NULL,
+    rank INTEGER NOT NULL,
+    generator TEXT NOT NULL,
+    filters TEXT,
+    FOREIGN KEY (solution_id, rule_id) REFERENCES rules(solution_id, id) ON DELETE CASCADE ON UPDATE CASCADE,
+    PRIMARY KEY (solution_id, rule_id, rank)
+);
\ No newline at end of file
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+CREATE TABLE constraint_parameters(
+    solution_id INTEGER NOT NULL REFERENCES solutions ON DELETE CASCADE,
+    rule_id INTEGER NOT NULL,
+    rank INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    value TEXT NOT NULL,
+    FOREIGN KEY (solution_id, rule_id) REFERENCES rules(solution_id, id) ON DELETE CASCADE ON UPDATE CASCADE,
+    PRIMARY KEY (solution_id, rule_id, rank)
+);
\ No newline at end of file
@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE part_allowed_days;
\ No newline at end of file
@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE part_allowed_weeks(
+    solution_id INTEGER NOT NULL REFERENCES solutions ON DELETE CASCADE,
+    part_id TEXT NOT NULL,
+    week INTEGER NOT NULL,
+    FOREIGN KEY (solution_id, part_id) REFERENCES parts(solution_id, id) ON DELETE CASCADE ON UPDATE CASCADE,
+    PRIMARY KEY (solution_id, part_id, week)
+);
\ No newline at end of file
@@ -0,0 +1,9 @@
+-- Your SQL goes here
+CREATE TABLE part_allowed_rooms(
+    solution_id INTEGER NOT NULL REFERENCES solutions ON DELETE CASCADE,
+    part_id TEXT NOT NULL,
+    room_id TEXT NOT NULL,
+    FOREIGN KEY (solution_id, part_id) REFERENCES parts(solution_id, id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (solution_id, room_id) REFERENCES rooms(solution_id, id) ON DELETE CASCADE ON UPDATE CASCADE,
+    PRIMARY KEY (solution_id, part_id, room_id)
+);
\ No newline at end of file
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+CREATE TABLE part_allowed_teachers(
+    solution_id INTEGER NOT NULL REFERENCES solutions ON DELETE CASCADE,
+    part_id TEXT NOT NULL,
+    teacher_id TEXT NOT NULL,
+    nr_sessions INTEGER,
+    FOREIGN KEY (solution_id, part_id) REFERENCES parts(solution_id, id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (solution_id, teacher_id) REFERENCES teachers(solution_id, name) ON DELETE CASCADE ON UPDATE CASCADE,
+    PRIMARY KEY (solution_id, part_id, teacher_id)
+);
\ No newline at end of file
@@ -0,0 +1,9 @@
+-- Your SQL goes here
+CREATE TABLE students_courses(
+    solution_id INTEGER NOT NULL REFERENCES solutions ON DELETE CASCADE,
+    student_id TEXT NOT NULL,
+    course_id TEXT NOT NULL,
+    FOREIGN KEY (solution_id, student_id) REFERENCES students ON UPDATE CASCADE ON DELETE CASCADE,
+    FOREIGN KEY (solution_id, course_id) REFERENCES courses ON UPDATE CASCADE ON DELETE CASCADE,
+    PRIMARY KEY (solution_id, student_id, course_id)
+);
\ No newline at end of file
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE classes DROP COLUMN label;
+ALTER TABLE classes DROP COLUMN parent_id;
\ No newline at end of file
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE classes ADD COLUMN parent_id TEXT;
+ALTER TABLE classes ADD COLUMN label TEXT;
\ No newline at end of file
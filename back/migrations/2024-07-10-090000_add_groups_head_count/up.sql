@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE groups ADD COLUMN head_count INTEGER;
\ No newline at end of file
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+UPDATE solutions SET slot_duration_seconds = slot_duration_seconds / 60;
+ALTER TABLE solutions RENAME COLUMN slot_duration_seconds TO slot_duration;
\ No newline at end of file
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE solutions RENAME COLUMN slot_duration TO slot_duration_seconds;
+UPDATE solutions SET slot_duration_seconds = slot_duration_seconds * 60;
\ No newline at end of file
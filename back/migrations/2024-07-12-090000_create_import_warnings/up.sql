@@ -0,0 +1,9 @@
+-- Your SQL goes here
+CREATE TABLE import_warnings(
+    solution_id INTEGER NOT NULL REFERENCES solutions ON DELETE CASCADE,
+    id INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    detail TEXT,
+    PRIMARY KEY (solution_id, id)
+);
\ No newline at end of file
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE import_warnings DROP COLUMN column_number;
+ALTER TABLE import_warnings DROP COLUMN line_number;
\ No newline at end of file
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE import_warnings ADD COLUMN line_number INTEGER;
+ALTER TABLE import_warnings ADD COLUMN column_number INTEGER;
\ No newline at end of file
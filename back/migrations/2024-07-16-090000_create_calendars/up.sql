@@ -0,0 +1,11 @@
+-- Your SQL goes here
+CREATE TABLE calendars(
+    solution_id INTEGER NOT NULL PRIMARY KEY REFERENCES solutions ON DELETE CASCADE,
+    year INTEGER NOT NULL,
+    starting_week INTEGER NOT NULL,
+    weeks_nr INTEGER NOT NULL,
+    weeks_sequence TEXT,
+    days_nr INTEGER NOT NULL,
+    days_sequence TEXT,
+    slots_nr INTEGER NOT NULL
+);
@@ -4,16 +4,32 @@ use actix_web::{web, Scope};
 use diesel::r2d2::{self, ManageConnection, PooledConnection};
 
 pub mod dto;
+pub mod import;
 mod query;
 mod solution;
 
 pub fn get_scope() -> Scope {
     actix_web::web::scope("/solutions")
         .service(solution::controller::post_route)
+        .service(solution::controller::validate_route)
+        .service(solution::controller::post_itc_route)
+        .service(solution::controller::export_route)
         .service(query::controller::get_availables_filters)
         .service(query::controller::get_availables_solutions)
         .service(query::controller::get_solution)
         .service(query::controller::get_sessions)
+        .service(query::controller::get_solution_rules)
+        .service(query::controller::get_solution_part)
+        .service(query::controller::get_solution_tree)
+        .service(query::controller::get_solution_import_report)
+        .service(query::controller::get_solution_ics)
+}
+
+pub fn get_imports_scope() -> Scope {
+    actix_web::web::scope("/imports")
+        .service(import::controller::get_import_jobs)
+        .service(import::controller::get_import_job)
+        .service(import::controller::get_import_job_events)
 }
 
 pub fn do_with_db<F, R, M>(
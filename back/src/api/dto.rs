@@ -1,6 +1,10 @@
-use chrono::NaiveDateTime;
+use std::collections::BTreeMap;
+
+use chrono::{NaiveDate, NaiveDateTime};
 use serde::Serialize;
 
+use crate::db::model::ImportWarning;
+
 #[derive(Serialize, PartialEq, Eq, Hash)]
 pub struct ShortRoomInfo {
     pub id: String,
@@ -24,6 +28,11 @@ pub struct ShortPartInfo {
     pub id: String,
 }
 
+#[derive(Serialize)]
+pub struct ShortClassInfo {
+    pub id: String,
+}
+
 #[derive(Serialize)]
 pub struct ShortSessionInfo {
     pub id: String,
@@ -31,7 +40,219 @@ pub struct ShortSessionInfo {
     pub to: NaiveDateTime,
     pub course: ShortCourseInfo,
     pub part: ShortPartInfo,
+    pub class: ShortClassInfo,
     pub rooms: Vec<ShortRoomInfo>,
     pub groups: Vec<ShortGroupInfo>,
     pub teachers: Vec<ShortTeacherInfo>,
+    pub expected_attendance: i32,
+}
+
+#[derive(Serialize)]
+pub struct SelectorInfo {
+    pub generator: String,
+    pub filters: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ConstraintParameterInfo {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Serialize)]
+pub struct ConstraintInfo {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub constraint_type: Option<String>,
+    pub parameters: Vec<ConstraintParameterInfo>,
+}
+
+#[derive(Serialize)]
+pub struct RuleInfo {
+    pub id: i32,
+    pub selectors: Vec<SelectorInfo>,
+    pub constraint: ConstraintInfo,
+}
+
+#[derive(Serialize)]
+pub struct AllowedSlotsInfo {
+    pub session_length: i32,
+    pub daily_slots: Vec<i32>,
+    pub days: Vec<i32>,
+    pub weeks: Vec<i32>,
+}
+
+#[derive(Serialize)]
+pub struct AllowedTeacherInfo {
+    pub id: String,
+    pub nr_sessions: Option<i32>,
+}
+
+#[derive(Serialize)]
+pub struct PartInfo {
+    pub id: String,
+    pub course_id: String,
+    pub label: Option<String>,
+    pub max_head_count: Option<i32>,
+    pub nr_session: Option<i32>,
+    pub session_rooms: Option<String>,
+    pub session_teachers: Option<i32>,
+    pub allowed_slots: AllowedSlotsInfo,
+    pub allowed_rooms: Vec<ShortRoomInfo>,
+    pub allowed_teachers: Vec<AllowedTeacherInfo>,
+}
+
+#[derive(Serialize)]
+pub struct ClassTreeNode {
+    pub id: String,
+    pub label: Option<String>,
+    pub groups: Vec<ShortGroupInfo>,
+    pub children: Vec<ClassTreeNode>,
+}
+
+#[derive(Serialize)]
+pub struct PartTreeNode {
+    pub id: String,
+    pub label: Option<String>,
+    pub classes: Vec<ClassTreeNode>,
+}
+
+#[derive(Serialize)]
+pub struct CourseTreeNode {
+    pub id: String,
+    pub label: Option<String>,
+    pub parts: Vec<PartTreeNode>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ImportWarningInfo {
+    pub kind: String,
+    pub subject: String,
+    pub detail: Option<String>,
+    pub line: Option<i32>,
+    pub column: Option<i32>,
+}
+
+impl From<ImportWarning> for ImportWarningInfo {
+    fn from(warning: ImportWarning) -> Self {
+        ImportWarningInfo {
+            kind: warning.kind,
+            subject: warning.subject,
+            detail: warning.detail,
+            line: warning.line_number,
+            column: warning.column_number,
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct ImportReportInfo {
+    pub solution_id: i32,
+    pub warnings: Vec<ImportWarningInfo>,
+}
+
+/// What made an import fail, for the clients to tell the errors apart
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportErrorKind {
+    /// The file exceeds one of the limits of the imports, it is refused as a whole
+    LimitExceeded,
+    /// The file is not a well-formed solution
+    InvalidFile,
+    /// A strict import found references to elements that are not in the file
+    DanglingReferences,
+    /// Something went wrong on the server
+    Internal,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ImportErrorInfo {
+    pub kind: ImportErrorKind,
+    pub message: String,
+    pub dangling_references: Vec<ImportWarningInfo>,
+}
+
+#[derive(Serialize)]
+pub struct EntityCountsInfo {
+    pub rooms: usize,
+    pub teachers: usize,
+    pub courses: usize,
+    pub parts: usize,
+    pub classes: usize,
+    pub groups: usize,
+    pub students: usize,
+    pub sessions: usize,
+    pub rules: usize,
+}
+
+#[derive(Serialize)]
+pub struct CalendarWeekInfo {
+    /// The week as numbered by the sessions of the file
+    pub week: u32,
+    pub starts_on: NaiveDate,
+}
+
+#[derive(Serialize)]
+pub struct DetectedCalendarInfo {
+    pub starting_date: NaiveDateTime,
+    pub slot_duration_seconds: u32,
+    pub weeks: Vec<CalendarWeekInfo>,
+}
+
+#[derive(Serialize)]
+pub struct SolutionValidationInfo {
+    /// Whether the file would be imported
+    pub valid: bool,
+    pub counts: EntityCountsInfo,
+    /// None when the file has no calendar
+    pub calendar: Option<DetectedCalendarInfo>,
+    pub warnings: Vec<ImportWarningInfo>,
+    pub errors: Vec<ImportErrorInfo>,
+}
+
+/// The phases follow each other in this order, a job never goes back to a previous one
+#[derive(Serialize, Clone, Copy, PartialEq, PartialOrd, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportPhase {
+    Queued,
+    Parsing,
+    Inserting,
+    Done,
+    Failed,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ImportJobInfo {
+    pub id: String,
+    pub filename: String,
+    pub created_at: NaiveDateTime,
+    pub phase: ImportPhase,
+    /// The table being inserted, while inserting
+    pub table: Option<&'static str>,
+    pub elements_processed: usize,
+    pub elements_per_route: BTreeMap<String, usize>,
+    pub bytes_read: usize,
+    pub bytes_total: usize,
+    pub rows_inserted: usize,
+    pub solution_id: Option<i32>,
+    pub import_report: Option<ImportReportInfo>,
+    pub error: Option<ImportErrorInfo>,
+}
+
+#[derive(Serialize)]
+pub struct ImportProgressInfo {
+    pub phase: ImportPhase,
+    pub bytes_read: usize,
+    pub bytes_total: usize,
+    pub elements_processed: usize,
+    pub elements_per_route: BTreeMap<String, usize>,
+}
+
+#[derive(Serialize)]
+pub struct ImportFlushInfo {
+    /// Number of the batch, starting at 1
+    pub batch: usize,
+    /// Rows inserted by this batch, by table
+    pub tables: BTreeMap<&'static str, usize>,
+    pub rows_inserted: usize,
 }
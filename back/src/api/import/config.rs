@@ -0,0 +1,106 @@
+use std::{env, time::Duration};
+
+use actix_multipart::{form::MultipartFormConfig, MultipartError};
+use actix_web::{error as actix_error, error::PayloadError, Error as ActixError, HttpRequest};
+use log::warn;
+
+use crate::xml_parsing::limits::XmlLimits;
+
+const DEFAULT_BATCH_SIZE: usize = 20000;
+// elements parsed ahead of the inserter, they are small and this keeps the memory flat
+const DEFAULT_CHANNEL_CAPACITY: usize = 1024;
+const DEFAULT_MAX_UPLOAD_BYTES: usize = 64 * 1024 * 1024;
+const DEFAULT_JOBS_TTL_SECONDS: usize = 60 * 60;
+const DEFAULT_MAX_FINISHED_JOBS: usize = 100;
+
+/// How the imports are run, read once from the environment
+#[derive(Clone, Copy)]
+pub struct ImportConfig {
+    /// Rows buffered before they are inserted, `IMPORT_BATCH_SIZE`
+    pub batch_size: usize,
+    /// Elements the parser can read ahead of the inserter, `IMPORT_CHANNEL_CAPACITY`
+    pub channel_capacity: usize,
+    /// Size of the uploaded forms, `IMPORT_MAX_UPLOAD_BYTES`
+    pub max_upload_bytes: usize,
+    /// `IMPORT_MAX_DEPTH`, `IMPORT_MAX_ELEMENT_BYTES` and `IMPORT_MAX_ATTRIBUTES`
+    pub xml_limits: XmlLimits,
+    /// How long a finished job is kept, `IMPORT_JOBS_TTL_SECONDS`
+    pub jobs_ttl: Duration,
+    /// Finished jobs kept at most, `IMPORT_MAX_FINISHED_JOBS`
+    pub max_finished_jobs: usize,
+}
+
+impl Default for ImportConfig {
+    fn default() -> Self {
+        ImportConfig {
+            batch_size: DEFAULT_BATCH_SIZE,
+            channel_capacity: DEFAULT_CHANNEL_CAPACITY,
+            max_upload_bytes: DEFAULT_MAX_UPLOAD_BYTES,
+            xml_limits: XmlLimits::default(),
+            jobs_ttl: Duration::from_secs(DEFAULT_JOBS_TTL_SECONDS as u64),
+            max_finished_jobs: DEFAULT_MAX_FINISHED_JOBS,
+        }
+    }
+}
+
+impl ImportConfig {
+    pub fn from_env() -> Self {
+        let default = ImportConfig::default();
+
+        return ImportConfig {
+            batch_size: positive_var("IMPORT_BATCH_SIZE", default.batch_size),
+            channel_capacity: positive_var("IMPORT_CHANNEL_CAPACITY", default.channel_capacity),
+            max_upload_bytes: positive_var("IMPORT_MAX_UPLOAD_BYTES", default.max_upload_bytes),
+            xml_limits: XmlLimits {
+                max_depth: positive_var("IMPORT_MAX_DEPTH", default.xml_limits.max_depth),
+                max_element_bytes: positive_var(
+                    "IMPORT_MAX_ELEMENT_BYTES",
+                    default.xml_limits.max_element_bytes,
+                ),
+                max_attributes: positive_var(
+                    "IMPORT_MAX_ATTRIBUTES",
+                    default.xml_limits.max_attributes,
+                ),
+            },
+            jobs_ttl: Duration::from_secs(positive_var(
+                "IMPORT_JOBS_TTL_SECONDS",
+                DEFAULT_JOBS_TTL_SECONDS,
+            ) as u64),
+            max_finished_jobs: positive_var("IMPORT_MAX_FINISHED_JOBS", default.max_finished_jobs),
+        };
+    }
+
+    /// Bounds the uploaded files, a file too large is refused with a 413
+    pub fn multipart_config(&self) -> MultipartFormConfig {
+        let max_upload_bytes = self.max_upload_bytes;
+
+        return MultipartFormConfig::default()
+            .total_limit(max_upload_bytes)
+            .error_handler(move |error, _: &HttpRequest| upload_error(error, max_upload_bytes));
+    }
+}
+
+fn upload_error(error: MultipartError, max_upload_bytes: usize) -> ActixError {
+    match error {
+        MultipartError::Payload(PayloadError::Overflow) => actix_error::ErrorPayloadTooLarge(
+            format!("The file is larger than {} bytes", max_upload_bytes),
+        ),
+        _ => error.into(),
+    }
+}
+
+fn positive_var(name: &str, default: usize) -> usize {
+    match env::var(name) {
+        Err(_) => default,
+        Ok(value) => match value.parse::<usize>() {
+            Ok(parsed) if parsed > 0 => parsed,
+            _ => {
+                warn!(
+                    "{} should be a positive number, not {:?}, using {}",
+                    name, value, default
+                );
+                default
+            }
+        },
+    }
+}
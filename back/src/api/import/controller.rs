@@ -0,0 +1,100 @@
+use actix_web::{
+    error::ErrorNotFound,
+    get,
+    http::{header, StatusCode},
+    web,
+    web::Bytes,
+    Error as ActixError, HttpResponse, Responder,
+};
+use futures_util::stream;
+use tokio::sync::broadcast::error::RecvError;
+
+use super::job::{ImportEvent, ImportJobs};
+use crate::api::dto::{ImportErrorKind, ImportJobInfo, ImportPhase};
+
+#[get("")]
+pub async fn get_import_jobs(jobs: web::Data<ImportJobs>) -> impl Responder {
+    HttpResponse::Ok().json(jobs.list())
+}
+
+/// A failed job answers with the status its upload would have had if it had waited for it
+fn job_status(job: &ImportJobInfo) -> StatusCode {
+    match job.error.as_ref().map(|error| error.kind) {
+        Some(ImportErrorKind::LimitExceeded) => StatusCode::PAYLOAD_TOO_LARGE,
+        Some(ImportErrorKind::InvalidFile) => StatusCode::BAD_REQUEST,
+        Some(ImportErrorKind::DanglingReferences) => StatusCode::UNPROCESSABLE_ENTITY,
+        Some(ImportErrorKind::Internal) => StatusCode::INTERNAL_SERVER_ERROR,
+        None => StatusCode::OK,
+    }
+}
+
+/// The state of a job. A failed job answers 413 when the file exceeds a limit, 400 when it is
+/// not a valid solution, 422 when a strict import failed on dangling references and 500 when
+/// the server failed.
+#[get("/{job_id}")]
+pub async fn get_import_job(
+    info: web::Path<String>,
+    jobs: web::Data<ImportJobs>,
+) -> Result<impl Responder, ActixError> {
+    let job_id = info.into_inner();
+
+    match jobs.get(&job_id) {
+        Some(job) => Ok(HttpResponse::build(job_status(&job)).json(job)),
+        None => Err(ErrorNotFound(format!("Import job {} not found", job_id))),
+    }
+}
+
+fn to_sse(event: &ImportEvent) -> Result<Bytes, ActixError> {
+    return Ok(Bytes::from(format!(
+        "event: {}\ndata: {}\n\n",
+        event.name, event.data
+    )));
+}
+
+/// Streams the events of a job, starting with its current state in a `job` event,
+/// the stream ends after the `done` or `failed` event
+#[get("/{job_id}/events")]
+pub async fn get_import_job_events(
+    info: web::Path<String>,
+    jobs: web::Data<ImportJobs>,
+) -> Result<impl Responder, ActixError> {
+    let job_id = info.into_inner();
+
+    let (job, receiver) = match jobs.subscribe(&job_id) {
+        Some(subscription) => subscription,
+        None => return Err(ErrorNotFound(format!("Import job {} not found", job_id))),
+    };
+
+    let finished = matches!(job.phase, ImportPhase::Done | ImportPhase::Failed);
+    let snapshot = ImportEvent::new("job", &job);
+
+    // when the job is already finished its snapshot is the last event
+    let events = stream::unfold(
+        (Some(snapshot), receiver, finished),
+        |(snapshot, mut receiver, finished)| async move {
+            if let Some(event) = snapshot {
+                return Some((to_sse(&event), (None, receiver, finished)));
+            }
+            if finished {
+                return None;
+            }
+
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => {
+                        let last = event.is_last();
+                        return Some((to_sse(&event), (None, receiver, last)));
+                    }
+                    // missed events are summed up by the following ones
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        },
+    );
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .streaming(events))
+}
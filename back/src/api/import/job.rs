@@ -0,0 +1,377 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use actix_web::web;
+use chrono::Utc;
+use log::error;
+use serde::Serialize;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::api::{
+    dto::{
+        ImportErrorInfo, ImportFlushInfo, ImportJobInfo, ImportPhase, ImportProgressInfo,
+        ImportReportInfo,
+    },
+    solution::progress::ImportProgress,
+};
+
+// events a slow subscriber can lag behind before missing some
+const EVENTS_CAPACITY: usize = 1024;
+// a progress event is sent every time this fraction of the file is read
+const PROGRESS_STEPS: usize = 100;
+
+/// An event of a job, with its data already serialized
+#[derive(Clone)]
+pub struct ImportEvent {
+    pub name: &'static str,
+    pub data: String,
+}
+
+impl ImportEvent {
+    pub fn new<T: Serialize>(name: &'static str, data: &T) -> Self {
+        ImportEvent {
+            name: name,
+            data: serde_json::to_string(data).unwrap_or_else(|e| {
+                error!("Could not serialize the {} event: {}", name, e);
+                String::from("{}")
+            }),
+        }
+    }
+
+    /// No event follows the end of the job
+    pub fn is_last(&self) -> bool {
+        return self.name == "done" || self.name == "failed";
+    }
+}
+
+struct JobEntry {
+    info: ImportJobInfo,
+    events: broadcast::Sender<ImportEvent>,
+    last_progress_offset: usize,
+    batches: usize,
+    batch_tables: BTreeMap<&'static str, usize>,
+    finished_at: Option<Instant>,
+}
+
+impl JobEntry {
+    fn send<T: Serialize>(&self, name: &'static str, data: &T) {
+        // nobody is listening most of the time, no need to serialize then
+        if self.events.receiver_count() > 0 {
+            // only fails if the last subscriber left in between
+            let _ = self.events.send(ImportEvent::new(name, data));
+        }
+    }
+
+    /// Moves the job to `phase` if it is a later one, the elements parsed after a batch was
+    /// inserted don't bring it back to parsing
+    fn advance(&mut self, phase: ImportPhase) -> bool {
+        if phase <= self.info.phase {
+            return false;
+        }
+
+        self.info.phase = phase;
+        return true;
+    }
+
+    fn send_progress(&mut self) {
+        self.last_progress_offset = self.info.bytes_read;
+        self.send(
+            "progress",
+            &ImportProgressInfo {
+                phase: self.info.phase,
+                bytes_read: self.info.bytes_read,
+                bytes_total: self.info.bytes_total,
+                elements_processed: self.info.elements_processed,
+                elements_per_route: self.info.elements_per_route.clone(),
+            },
+        );
+    }
+}
+
+/// The imports running in the background, and the finished ones, shared by all the workers.
+///
+/// A finished job is kept for `ttl`, and only the `max_finished` most recent ones are. The jobs
+/// live in memory, they are lost when the server restarts.
+pub struct ImportJobs {
+    jobs: Mutex<HashMap<String, JobEntry>>,
+    ttl: Duration,
+    max_finished: usize,
+}
+
+/// Removes the finished jobs older than `ttl`, then the oldest ones above `max_finished`
+fn evict(jobs: &mut HashMap<String, JobEntry>, ttl: Duration, max_finished: usize) {
+    jobs.retain(|_, entry| {
+        entry
+            .finished_at
+            .is_none_or(|finished_at| finished_at.elapsed() < ttl)
+    });
+
+    let mut finished: Vec<(Instant, String)> = jobs
+        .iter()
+        .filter_map(|(id, entry)| entry.finished_at.map(|at| (at, id.clone())))
+        .collect();
+    if finished.len() > max_finished {
+        finished.sort();
+        finished
+            .iter()
+            .take(finished.len() - max_finished)
+            .for_each(|(_, id)| {
+                jobs.remove(id);
+            });
+    }
+}
+
+impl ImportJobs {
+    pub fn new(ttl: Duration, max_finished: usize) -> Self {
+        ImportJobs {
+            jobs: Mutex::new(HashMap::new()),
+            ttl: ttl,
+            max_finished: max_finished,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, JobEntry>> {
+        let mut jobs = self.jobs.lock().unwrap();
+        evict(&mut jobs, self.ttl, self.max_finished);
+
+        return jobs;
+    }
+
+    pub fn create(&self, filename: &str, bytes_total: usize) -> ImportJobInfo {
+        let job = ImportJobInfo {
+            id: Uuid::new_v4().to_string(),
+            filename: filename.to_string(),
+            created_at: Utc::now().naive_utc(),
+            phase: ImportPhase::Queued,
+            table: None,
+            elements_processed: 0,
+            elements_per_route: BTreeMap::new(),
+            bytes_read: 0,
+            bytes_total: bytes_total,
+            rows_inserted: 0,
+            solution_id: None,
+            import_report: None,
+            error: None,
+        };
+        let (events, _) = broadcast::channel(EVENTS_CAPACITY);
+
+        self.lock().insert(
+            job.id.clone(),
+            JobEntry {
+                info: job.clone(),
+                events: events,
+                last_progress_offset: 0,
+                batches: 0,
+                batch_tables: BTreeMap::new(),
+                finished_at: None,
+            },
+        );
+
+        return job;
+    }
+
+    pub fn get(&self, job_id: &str) -> Option<ImportJobInfo> {
+        self.lock().get(job_id).map(|entry| entry.info.clone())
+    }
+
+    /// The current state of a job, and a receiver of the events that follow it
+    pub fn subscribe(
+        &self,
+        job_id: &str,
+    ) -> Option<(ImportJobInfo, broadcast::Receiver<ImportEvent>)> {
+        self.lock()
+            .get(job_id)
+            .map(|entry| (entry.info.clone(), entry.events.subscribe()))
+    }
+
+    /// Every job, the most recent first
+    pub fn list(&self) -> Vec<ImportJobInfo> {
+        let mut jobs: Vec<ImportJobInfo> = self
+            .lock()
+            .values()
+            .map(|entry| entry.info.clone())
+            .collect();
+        jobs.sort_by_key(|job| Reverse(job.created_at));
+
+        return jobs;
+    }
+
+    fn update<F>(&self, job_id: &str, f: F)
+    where
+        F: FnOnce(&mut JobEntry),
+    {
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(job_id) {
+            f(job);
+        }
+    }
+
+    pub fn succeed(&self, job_id: &str, solution_id: i32, import_report: ImportReportInfo) {
+        self.update(job_id, |entry| {
+            entry.info.phase = ImportPhase::Done;
+            entry.info.table = None;
+            entry.info.bytes_read = entry.info.bytes_total;
+            entry.info.solution_id = Some(solution_id);
+            entry.info.import_report = Some(import_report);
+            entry.finished_at = Some(Instant::now());
+            entry.send("done", &entry.info);
+        });
+    }
+
+    pub fn fail(&self, job_id: &str, error: ImportErrorInfo) {
+        self.update(job_id, |entry| {
+            entry.info.phase = ImportPhase::Failed;
+            entry.info.table = None;
+            entry.info.error = Some(error);
+            entry.finished_at = Some(Instant::now());
+            entry.send("failed", &entry.info);
+        });
+    }
+}
+
+/// Reports the progress of an import into its job
+pub struct JobProgress {
+    jobs: web::Data<ImportJobs>,
+    job_id: String,
+}
+
+impl JobProgress {
+    pub fn new(jobs: web::Data<ImportJobs>, job_id: String) -> Self {
+        JobProgress {
+            jobs: jobs,
+            job_id: job_id,
+        }
+    }
+}
+
+impl ImportProgress for JobProgress {
+    /// A progress event is sent when the job starts parsing, then every step of the file
+    fn on_element(&self, route: &str, offset: usize) {
+        self.jobs.update(&self.job_id, |entry| {
+            let advanced = entry.advance(ImportPhase::Parsing);
+            entry.info.elements_processed += 1;
+            entry.info.bytes_read = offset;
+            match entry.info.elements_per_route.get_mut(route) {
+                Some(count) => *count += 1,
+                None => {
+                    entry.info.elements_per_route.insert(route.to_string(), 1);
+                }
+            }
+
+            let step = (entry.info.bytes_total / PROGRESS_STEPS).max(1);
+            if advanced || offset >= entry.last_progress_offset + step {
+                entry.send_progress();
+            }
+        });
+    }
+
+    fn on_table_insert(&self, table: &'static str) {
+        self.jobs.update(&self.job_id, |entry| {
+            entry.info.table = Some(table);
+            if entry.advance(ImportPhase::Inserting) {
+                entry.send_progress();
+            }
+        });
+    }
+
+    fn on_rows_inserted(&self, table: &'static str, rows: usize) {
+        self.jobs.update(&self.job_id, |entry| {
+            entry.info.rows_inserted += rows;
+            *entry.batch_tables.entry(table).or_default() += rows;
+        });
+    }
+
+    fn on_flush(&self) {
+        self.jobs.update(&self.job_id, |entry| {
+            if entry.batch_tables.is_empty() {
+                return;
+            }
+
+            entry.batches += 1;
+            let flush = ImportFlushInfo {
+                batch: entry.batches,
+                tables: std::mem::take(&mut entry.batch_tables),
+                rows_inserted: entry.info.rows_inserted,
+            };
+            entry.send("flush", &flush);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use actix_web::web;
+
+    use super::{ImportJobs, JobProgress};
+    use crate::api::{
+        dto::{ImportErrorInfo, ImportErrorKind, ImportPhase},
+        solution::progress::ImportProgress,
+    };
+
+    fn failure() -> ImportErrorInfo {
+        ImportErrorInfo {
+            kind: ImportErrorKind::Internal,
+            message: String::from("failed"),
+            dangling_references: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn should_keep_only_the_most_recent_finished_jobs() {
+        let jobs = ImportJobs::new(Duration::from_secs(3600), 2);
+
+        let finished: Vec<String> = (0..3)
+            .map(|_| {
+                let job = jobs.create("solution.xml", 0);
+                jobs.fail(&job.id, failure());
+                job.id
+            })
+            .collect();
+        let running = jobs.create("solution.xml", 0);
+
+        assert!(jobs.get(&finished[0]).is_none());
+        assert!(jobs.get(&finished[1]).is_some());
+        assert!(jobs.get(&finished[2]).is_some());
+        assert!(jobs.get(&running.id).is_some());
+    }
+
+    #[test]
+    fn should_forget_the_finished_jobs_after_their_ttl() {
+        let jobs = ImportJobs::new(Duration::ZERO, 100);
+
+        let finished = jobs.create("solution.xml", 0);
+        jobs.fail(&finished.id, failure());
+        let running = jobs.create("solution.xml", 0);
+
+        assert!(jobs.get(&finished.id).is_none());
+        assert!(jobs.get(&running.id).is_some());
+        assert_eq!(jobs.list().len(), 1);
+    }
+
+    #[test]
+    fn should_only_move_the_phase_forward() {
+        let jobs = web::Data::new(ImportJobs::new(Duration::from_secs(3600), 100));
+        let job = jobs.create("solution.xml", 1000);
+        let (_, mut events) = jobs.subscribe(&job.id).unwrap();
+        let progress = JobProgress::new(jobs.clone(), job.id.clone());
+
+        progress.on_element("timetabling/rooms/room", 1);
+        progress.on_element("timetabling/rooms/room", 2);
+        progress.on_table_insert("rooms");
+        progress.on_element("timetabling/rooms/room", 3);
+
+        let phases: Vec<String> = std::iter::from_fn(|| events.try_recv().ok())
+            .map(|event| event.data)
+            .collect();
+        assert_eq!(phases.len(), 2);
+        assert!(phases[0].contains("\"phase\":\"parsing\""));
+        assert!(phases[1].contains("\"phase\":\"inserting\""));
+        assert_eq!(jobs.get(&job.id).unwrap().phase, ImportPhase::Inserting);
+    }
+}
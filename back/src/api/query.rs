@@ -1,2 +1,3 @@
 pub mod controller;
+mod ics;
 mod service;
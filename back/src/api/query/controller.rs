@@ -9,12 +9,19 @@ use diesel::{
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    api::do_with_db,
+    api::{
+        do_with_db,
+        solution::{calendar_handler::CalendarHandler, export::solution_calendar},
+    },
     db::{model::Solution, schema},
     DbPool,
 };
 
-use super::service::{get_filter_list, get_sessions_with_filters};
+use super::ics::sessions_to_ics;
+use super::service::{
+    get_course_tree, get_filter_list, get_import_report, get_part, get_rules,
+    get_sessions_with_filters, SessionFilters,
+};
 
 #[derive(Serialize)]
 pub struct FilterList {
@@ -23,6 +30,7 @@ pub struct FilterList {
     pub teachers: Vec<String>,
     pub rooms: Vec<String>,
     pub groups: Vec<String>,
+    pub students: Vec<String>,
 }
 
 #[get("/{solution_id}/filters")]
@@ -79,6 +87,89 @@ pub async fn get_solution(
     }
 }
 
+#[get("/{solution_id}/rules")]
+pub async fn get_solution_rules(
+    info: web::Path<i32>,
+    pool: web::Data<DbPool>,
+) -> Result<impl Responder, ActixError> {
+    let request_solution_id = info.into_inner();
+
+    let result = do_with_db(pool, move |conn| get_rules(conn, request_solution_id)).await?;
+
+    match result {
+        Ok(rules) => Ok(HttpResponse::Ok().json(rules)),
+        Err(DieselError::NotFound) => Err(ErrorNotFound(format!(
+            "Solution {} not found",
+            request_solution_id
+        ))),
+        Err(err) => Err(ErrorInternalServerError(err)),
+    }
+}
+
+#[get("/{solution_id}/parts/{part_id}")]
+pub async fn get_solution_part(
+    info: web::Path<(i32, String)>,
+    pool: web::Data<DbPool>,
+) -> Result<impl Responder, ActixError> {
+    let (request_solution_id, request_part_id) = info.into_inner();
+    let part_id = request_part_id.clone();
+
+    let result = do_with_db(pool, move |conn| {
+        get_part(conn, request_solution_id, &part_id)
+    })
+    .await?;
+
+    match result {
+        Ok(part) => Ok(HttpResponse::Ok().json(part)),
+        Err(DieselError::NotFound) => Err(ErrorNotFound(format!(
+            "Part {} not found in solution {}",
+            request_part_id, request_solution_id
+        ))),
+        Err(err) => Err(ErrorInternalServerError(err)),
+    }
+}
+
+#[get("/{solution_id}/tree")]
+pub async fn get_solution_tree(
+    info: web::Path<i32>,
+    pool: web::Data<DbPool>,
+) -> Result<impl Responder, ActixError> {
+    let request_solution_id = info.into_inner();
+
+    let result = do_with_db(pool, move |conn| get_course_tree(conn, request_solution_id)).await?;
+
+    match result {
+        Ok(tree) => Ok(HttpResponse::Ok().json(tree)),
+        Err(DieselError::NotFound) => Err(ErrorNotFound(format!(
+            "Solution {} not found",
+            request_solution_id
+        ))),
+        Err(err) => Err(ErrorInternalServerError(err)),
+    }
+}
+
+#[get("/{solution_id}/import-report")]
+pub async fn get_solution_import_report(
+    info: web::Path<i32>,
+    pool: web::Data<DbPool>,
+) -> Result<impl Responder, ActixError> {
+    let request_solution_id = info.into_inner();
+
+    let result = do_with_db(pool, move |conn| {
+        get_import_report(conn, request_solution_id)
+    })
+    .await?;
+
+    match result {
+        Ok(report) => Ok(HttpResponse::Ok().json(report)),
+        Err(DieselError::NotFound) => Err(ErrorNotFound(format!(
+            "Solution {} not found",
+            request_solution_id
+        ))),
+        Err(err) => Err(ErrorInternalServerError(err)),
+    }
+}
+
 #[derive(Deserialize)]
 struct ReadInstanceBody {
     pub from: Option<String>,
@@ -88,6 +179,8 @@ struct ReadInstanceBody {
     pub teachers: Vec<String>,
     pub rooms: Vec<String>,
     pub groups: Vec<String>,
+    #[serde(default)]
+    pub students: Vec<String>,
 }
 
 #[post("/{solution_id}/query")]
@@ -127,13 +220,16 @@ pub async fn get_sessions(
         get_sessions_with_filters(
             conn,
             request_solution_id,
-            parsed_from,
-            parsed_to,
-            body.courses,
-            body.parts,
-            body.teachers,
-            body.rooms,
-            body.groups,
+            SessionFilters {
+                from: parsed_from,
+                to: parsed_to,
+                courses: body.courses,
+                parts: body.parts,
+                teachers: body.teachers,
+                rooms: body.rooms,
+                groups: body.groups,
+                students: body.students,
+            },
         )
     })
     .await?;
@@ -143,3 +239,85 @@ pub async fn get_sessions(
         Err(err) => Err(ErrorBadRequest(err)),
     }
 }
+
+/// Comma separated ids, as calendar clients only take a plain URL
+#[derive(Deserialize)]
+struct IcsQuery {
+    pub teachers: Option<String>,
+    pub rooms: Option<String>,
+    pub groups: Option<String>,
+    pub students: Option<String>,
+    /// Writes the weekly series as recurring events
+    #[serde(default)]
+    pub recurrences: bool,
+}
+
+fn split_ids(ids: Option<String>) -> Vec<String> {
+    ids.map(|ids| {
+        ids.split(',')
+            .filter(|id| !id.is_empty())
+            .map(String::from)
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+#[get("/{solution_id}/ics")]
+pub async fn get_solution_ics(
+    query: web::Query<IcsQuery>,
+    info: web::Path<i32>,
+    pool: web::Data<DbPool>,
+) -> Result<impl Responder, ActixError> {
+    let request_solution_id = info.into_inner();
+    let query = query.into_inner();
+
+    let result: Result<String, DieselError> = do_with_db(pool, move |conn| {
+        let solution = schema::solutions::table
+            .filter(schema::solutions::id.eq(request_solution_id))
+            .get_result::<Solution>(conn)?;
+
+        let sessions = get_sessions_with_filters(
+            conn,
+            request_solution_id,
+            SessionFilters {
+                teachers: split_ids(query.teachers),
+                rooms: split_ids(query.rooms),
+                groups: split_ids(query.groups),
+                students: split_ids(query.students),
+                ..Default::default()
+            },
+        )?;
+
+        let calendar = match query.recurrences {
+            true => {
+                let mut calendar = CalendarHandler::new();
+                // its warnings were given by the import
+                calendar.register_xml_calendar(&solution_calendar(conn, &solution)?);
+                Some(calendar)
+            }
+            false => None,
+        };
+
+        return Ok(sessions_to_ics(
+            solution
+                .filename
+                .strip_suffix(".xml")
+                .unwrap_or(&solution.filename),
+            solution.created_at,
+            sessions,
+            calendar.as_ref(),
+        ));
+    })
+    .await?;
+
+    match result {
+        Ok(ics) => Ok(HttpResponse::Ok()
+            .content_type("text/calendar; charset=utf-8")
+            .body(ics)),
+        Err(DieselError::NotFound) => Err(ErrorNotFound(format!(
+            "Solution {} not found",
+            request_solution_id
+        ))),
+        Err(err) => Err(ErrorInternalServerError(err)),
+    }
+}
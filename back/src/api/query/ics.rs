@@ -0,0 +1,403 @@
+use std::collections::HashMap;
+
+use chrono::{Datelike, Duration, NaiveDateTime};
+
+use crate::api::{dto::ShortSessionInfo, solution::calendar_handler::CalendarHandler};
+
+const DATE_TIME_FORMAT: &str = "%Y%m%dT%H%M%S";
+/// Octets of a content line, without its line break
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Content lines of an iCalendar object, folded and ended as RFC 5545 asks
+struct IcsWriter {
+    ics: String,
+}
+
+impl IcsWriter {
+    fn line(&mut self, name: &str, value: &str) {
+        let line = format!("{}:{}", name, value);
+        let mut octets = 0;
+
+        for c in line.chars() {
+            // the space starting a folded line counts in its octets
+            if octets + c.len_utf8() > MAX_LINE_OCTETS {
+                self.ics.push_str("\r\n ");
+                octets = 1;
+            }
+            self.ics.push(c);
+            octets += c.len_utf8();
+        }
+        self.ics.push_str("\r\n");
+    }
+
+    fn text(&mut self, name: &str, text: &str) {
+        self.line(name, &escape_text(text));
+    }
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn join_ids<'a>(ids: impl Iterator<Item = &'a String>) -> String {
+    let mut ids: Vec<&String> = ids.collect();
+    ids.sort();
+
+    return ids
+        .into_iter()
+        .map(String::as_str)
+        .collect::<Vec<&str>>()
+        .join(", ");
+}
+
+/// What an event says of a session, the sessions of a series only differ by their dates
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct EventContent {
+    summary: String,
+    location: Option<String>,
+    description: Option<String>,
+}
+
+struct Event {
+    uid: String,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    class: String,
+    content: EventContent,
+}
+
+impl From<ShortSessionInfo> for Event {
+    fn from(session: ShortSessionInfo) -> Self {
+        let mut description = Vec::new();
+        if !session.teachers.is_empty() {
+            description.push(format!(
+                "Teachers: {}",
+                join_ids(session.teachers.iter().map(|teacher| &teacher.id))
+            ));
+        }
+        if !session.groups.is_empty() {
+            description.push(format!(
+                "Groups: {}",
+                join_ids(session.groups.iter().map(|group| &group.id))
+            ));
+        }
+
+        Event {
+            uid: format!("{}@cellion", session.id),
+            from: session.from,
+            to: session.to,
+            class: session.class.id,
+            content: EventContent {
+                summary: format!("{} - {}", session.course.id, session.part.id),
+                location: (!session.rooms.is_empty())
+                    .then(|| join_ids(session.rooms.iter().map(|room| &room.id))),
+                description: (!description.is_empty()).then(|| description.join("\n")),
+            },
+        }
+    }
+}
+
+/// Groups the events given at the same time of the same weekday, in the same rooms and by the
+/// same teachers, over consecutive weeks of the calendar. A series is written as its first event
+/// repeated each week until the last one, the weeks the calendar skips are excluded.
+fn weekly_series(events: Vec<Event>, calendar: &CalendarHandler) -> Vec<Vec<Event>> {
+    let mut candidates: HashMap<_, Vec<(Option<u32>, Event)>> = HashMap::new();
+    events.into_iter().for_each(|event| {
+        // an event out of the calendar can't be repeated with the others
+        let week = calendar.session_slot(event.from).ok().map(|slot| slot.week);
+        let key = (
+            event.class.clone(),
+            event.from.weekday(),
+            event.from.time(),
+            event.to - event.from,
+            event.content.clone(),
+        );
+        candidates.entry(key).or_default().push((week, event));
+    });
+
+    let mut series: Vec<Vec<Event>> = Vec::new();
+    for mut events in candidates.into_values() {
+        events.sort_by(|a, b| a.1.from.cmp(&b.1.from).then_with(|| a.1.uid.cmp(&b.1.uid)));
+
+        let mut previous_week: Option<u32> = None;
+        for (week, event) in events {
+            let follows = match (previous_week, week) {
+                (Some(previous), Some(week)) => week == previous + 1,
+                _ => false,
+            };
+            previous_week = week;
+
+            match follows {
+                true => series.last_mut().unwrap().push(event),
+                false => series.push(vec![event]),
+            }
+        }
+    }
+
+    return series;
+}
+
+/// The sessions as the events of a calendar. The dates are written in floating time, as they are
+/// stored: the events stay at the same hour wherever the calendar is read. `stamp` is the UTC
+/// date of the data, events written twice are the same. With a calendar, the weekly series are
+/// written as recurring events.
+pub fn sessions_to_ics(
+    name: &str,
+    stamp: NaiveDateTime,
+    sessions: Vec<ShortSessionInfo>,
+    calendar: Option<&CalendarHandler>,
+) -> String {
+    let mut writer = IcsWriter { ics: String::new() };
+    let stamp = format!("{}Z", stamp.format(DATE_TIME_FORMAT));
+    let events = sessions.into_iter().map(Event::from).collect();
+
+    let mut series = match calendar {
+        Some(calendar) => weekly_series(events, calendar),
+        None => events.into_iter().map(|event| vec![event]).collect(),
+    };
+    series.sort_by(|a, b| {
+        a[0].from
+            .cmp(&b[0].from)
+            .then_with(|| a[0].uid.cmp(&b[0].uid))
+    });
+
+    writer.line("BEGIN", "VCALENDAR");
+    writer.line("VERSION", "2.0");
+    writer.line("PRODID", "-//Cellion//Solutions//FR");
+    writer.line("CALSCALE", "GREGORIAN");
+    writer.text("X-WR-CALNAME", name);
+
+    for events in series {
+        let first = &events[0];
+
+        writer.line("BEGIN", "VEVENT");
+        writer.line("UID", &first.uid);
+        writer.line("DTSTAMP", &stamp);
+        writer.line("DTSTART", &first.from.format(DATE_TIME_FORMAT).to_string());
+        writer.line("DTEND", &first.to.format(DATE_TIME_FORMAT).to_string());
+
+        if let Some(last) = events.last().filter(|_| events.len() > 1) {
+            writer.line(
+                "RRULE",
+                &format!("FREQ=WEEKLY;UNTIL={}", last.from.format(DATE_TIME_FORMAT)),
+            );
+
+            let excluded: Vec<String> = (0..)
+                .map(|week| first.from + Duration::weeks(week))
+                .take_while(|date| *date < last.from)
+                .filter(|date| !events.iter().any(|event| event.from == *date))
+                .map(|date| date.format(DATE_TIME_FORMAT).to_string())
+                .collect();
+            if !excluded.is_empty() {
+                writer.line("EXDATE", &excluded.join(","));
+            }
+        }
+
+        writer.text("SUMMARY", &first.content.summary);
+        if let Some(location) = &first.content.location {
+            writer.text("LOCATION", location);
+        }
+        if let Some(description) = &first.content.description {
+            writer.text("DESCRIPTION", description);
+        }
+
+        writer.line("END", "VEVENT");
+    }
+
+    writer.line("END", "VCALENDAR");
+
+    return writer.ics;
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, NaiveDate, NaiveDateTime};
+
+    use super::{sessions_to_ics, IcsWriter, DATE_TIME_FORMAT};
+    use crate::api::{
+        dto::{
+            ShortClassInfo, ShortCourseInfo, ShortGroupInfo, ShortPartInfo, ShortRoomInfo,
+            ShortSessionInfo, ShortTeacherInfo,
+        },
+        solution::{
+            calendar_handler::CalendarHandler,
+            xml_types::{XmlCalendar, XmlCalendarMetaData},
+        },
+    };
+
+    fn session(id: &str, class: &str, from: NaiveDateTime, room: &str) -> ShortSessionInfo {
+        ShortSessionInfo {
+            id: String::from(id),
+            from: from,
+            to: from + Duration::minutes(90),
+            course: ShortCourseInfo {
+                id: String::from("Algo"),
+            },
+            part: ShortPartInfo {
+                id: String::from("Algo-CM"),
+            },
+            class: ShortClassInfo {
+                id: String::from(class),
+            },
+            rooms: vec![ShortRoomInfo {
+                id: String::from(room),
+            }],
+            groups: Vec::new(),
+            teachers: vec![ShortTeacherInfo {
+                id: String::from("Dupont"),
+            }],
+            expected_attendance: 0,
+        }
+    }
+
+    /// The occurrences of the events with their rooms, the recurrences expanded
+    fn occurrences(ics: &str) -> Vec<(String, String)> {
+        let mut occurrences: Vec<(String, String)> = ics
+            .split("BEGIN:VEVENT\r\n")
+            .skip(1)
+            .flat_map(|event| {
+                let property = |name: &str| {
+                    event
+                        .split("\r\n")
+                        .find_map(|line| line.strip_prefix(&format!("{}:", name)))
+                        .map(String::from)
+                };
+                let parse =
+                    |date: &str| NaiveDateTime::parse_from_str(date, DATE_TIME_FORMAT).unwrap();
+                let start = parse(&property("DTSTART").unwrap());
+                let until = property("RRULE")
+                    .map(|rule| parse(rule.strip_prefix("FREQ=WEEKLY;UNTIL=").unwrap()))
+                    .unwrap_or(start);
+                let excluded: Vec<NaiveDateTime> = property("EXDATE")
+                    .map(|dates| dates.split(',').map(parse).collect())
+                    .unwrap_or_default();
+                let location = property("LOCATION").unwrap();
+
+                (0..)
+                    .map(move |week| start + Duration::weeks(week))
+                    .take_while(move |date| *date <= until)
+                    .filter(move |date| !excluded.contains(date))
+                    .map(move |date| (date.format(DATE_TIME_FORMAT).to_string(), location.clone()))
+            })
+            .collect();
+        occurrences.sort();
+
+        return occurrences;
+    }
+
+    #[test]
+    fn should_fold_the_long_lines_between_characters() {
+        let mut writer = IcsWriter { ics: String::new() };
+
+        writer.line("DESCRIPTION", &"é".repeat(40));
+
+        let lines: Vec<&str> = writer.ics.trim_end_matches("\r\n").split("\r\n").collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|line| line.len() <= 75));
+        assert!(lines[1].starts_with(' '));
+        assert_eq!(
+            lines.concat().replacen(" ", "", 1),
+            format!("DESCRIPTION:{}", "é".repeat(40))
+        );
+    }
+
+    #[test]
+    fn should_write_a_session_as_an_event() {
+        let from = NaiveDate::from_ymd_opt(2023, 9, 4)
+            .unwrap()
+            .and_hms_opt(8, 0, 0)
+            .unwrap();
+        let session = ShortSessionInfo {
+            id: String::from("1b4e28ba-2fa1-11d2-883f-0016d3cca427"),
+            from: from,
+            to: from + chrono::Duration::minutes(90),
+            course: ShortCourseInfo {
+                id: String::from("Algo"),
+            },
+            part: ShortPartInfo {
+                id: String::from("Algo-CM"),
+            },
+            rooms: vec![ShortRoomInfo {
+                id: String::from("Amphi A"),
+            }],
+            groups: vec![
+                ShortGroupInfo {
+                    id: String::from("L1,G2"),
+                },
+                ShortGroupInfo {
+                    id: String::from("L1,G1"),
+                },
+            ],
+            class: ShortClassInfo {
+                id: String::from("Algo-CM-1"),
+            },
+            teachers: vec![ShortTeacherInfo {
+                id: String::from("Dupont"),
+            }],
+            expected_attendance: 0,
+        };
+
+        let ics = sessions_to_ics("solution", from, vec![session], None);
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VEVENT\r\nEND:VCALENDAR\r\n"));
+        assert!(ics.contains("UID:1b4e28ba-2fa1-11d2-883f-0016d3cca427@cellion\r\n"));
+        assert!(ics.contains("DTSTAMP:20230904T080000Z\r\n"));
+        assert!(ics.contains("DTSTART:20230904T080000\r\nDTEND:20230904T093000\r\n"));
+        assert!(ics.contains("SUMMARY:Algo - Algo-CM\r\nLOCATION:Amphi A\r\n"));
+        assert!(ics.contains("DESCRIPTION:Teachers: Dupont\\nGroups: L1\\,G1\\, L1\\,G2\r\n"));
+    }
+
+    #[test]
+    fn should_write_the_weekly_series_as_recurring_events() {
+        let mut calendar = CalendarHandler::new();
+        // the third week of september is skipped
+        calendar.register_xml_calendar(&XmlCalendar {
+            starting_week: 36,
+            year: 2023,
+            weeks: XmlCalendarMetaData {
+                nr: 4,
+                sequence: Some(String::from("1,2,4,5")),
+            },
+            days: XmlCalendarMetaData {
+                nr: 7,
+                sequence: None,
+            },
+            slots: XmlCalendarMetaData {
+                nr: 1440,
+                sequence: None,
+            },
+        });
+        let date = |day: u32| {
+            NaiveDate::from_ymd_opt(2023, 9, day)
+                .unwrap()
+                .and_hms_opt(8, 0, 0)
+                .unwrap()
+        };
+        let sessions = || {
+            vec![
+                session("1", "Algo-CM-1", date(4), "Amphi A"),
+                session("2", "Algo-CM-1", date(11), "Amphi A"),
+                session("3", "Algo-CM-1", date(25), "Amphi A"),
+                // another room ends the series
+                session("4", "Algo-CM-1", date(4) + Duration::weeks(4), "Amphi B"),
+                session("5", "Algo-CM-2", date(5), "Amphi A"),
+            ]
+        };
+
+        let expanded = sessions_to_ics("solution", date(4), sessions(), None);
+        let series = sessions_to_ics("solution", date(4), sessions(), Some(&calendar));
+
+        assert_eq!(series.matches("BEGIN:VEVENT").count(), 3);
+        assert!(series.contains(
+            "UID:1@cellion\r\nDTSTAMP:20230904T080000Z\r\nDTSTART:20230904T080000\r\n\
+             DTEND:20230904T093000\r\nRRULE:FREQ=WEEKLY;UNTIL=20230925T080000\r\n\
+             EXDATE:20230918T080000\r\n"
+        ));
+        assert_eq!(occurrences(&series), occurrences(&expanded));
+        assert_eq!(occurrences(&expanded).len(), 5);
+    }
+}
@@ -2,16 +2,24 @@ use std::collections::{HashMap, HashSet};
 
 use chrono::NaiveDateTime;
 use diesel::{
-    result::Error as DieselError, BoolExpressionMethods, ExpressionMethods, JoinOnDsl, QueryDsl,
-    RunQueryDsl, SqliteConnection,
+    result::Error as DieselError, BoolExpressionMethods, ExpressionMethods, JoinOnDsl,
+    NullableExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper, SqliteConnection,
 };
 
 use crate::{
-    api::dto::{
-        ShortCourseInfo, ShortGroupInfo, ShortPartInfo, ShortRoomInfo, ShortSessionInfo,
-        ShortTeacherInfo,
+    api::{
+        dto::{
+            AllowedSlotsInfo, AllowedTeacherInfo, ClassTreeNode, ConstraintInfo,
+            ConstraintParameterInfo, CourseTreeNode, ImportReportInfo, ImportWarningInfo, PartInfo,
+            PartTreeNode, RuleInfo, SelectorInfo, ShortClassInfo, ShortCourseInfo, ShortGroupInfo,
+            ShortPartInfo, ShortRoomInfo, ShortSessionInfo, ShortTeacherInfo,
+        },
+        solution::calendar_handler::slots_to_duration,
+    },
+    db::{
+        model::{Class, ConstraintParameter, Course, ImportWarning, Part, Rule, RuleSelector},
+        schema,
     },
-    db::schema,
 };
 
 use super::controller::FilterList;
@@ -22,55 +30,79 @@ struct ShortSessionInfoMap {
     pub to: NaiveDateTime,
     pub course: ShortCourseInfo,
     pub part: ShortPartInfo,
+    pub class: ShortClassInfo,
     pub rooms: HashSet<ShortRoomInfo>,
     pub groups: HashSet<ShortGroupInfo>,
     pub teachers: HashSet<ShortTeacherInfo>,
+    pub expected_attendance: i32,
 }
 
-impl From<(String, NaiveDateTime, String, String, i32)> for ShortSessionInfoMap {
-    fn from(value: (String, NaiveDateTime, String, String, i32)) -> Self {
+impl From<(String, NaiveDateTime, String, String, String, i32, i32)> for ShortSessionInfoMap {
+    fn from(value: (String, NaiveDateTime, String, String, String, i32, i32)) -> Self {
         ShortSessionInfoMap {
             id: value.0,
             from: value.1,
             to: value
                 .1
-                .checked_add_signed(chrono::Duration::minutes(value.4 as i64))
+                .checked_add_signed(slots_to_duration(value.5, value.6))
                 .unwrap(),
             course: ShortCourseInfo { id: value.2 },
             part: ShortPartInfo { id: value.3 },
+            class: ShortClassInfo { id: value.4 },
             rooms: HashSet::new(),
             groups: HashSet::new(),
             teachers: HashSet::new(),
+            expected_attendance: 0,
         }
     }
 }
 
-impl Into<ShortSessionInfo> for ShortSessionInfoMap {
-    fn into(self) -> ShortSessionInfo {
+impl From<ShortSessionInfoMap> for ShortSessionInfo {
+    fn from(val: ShortSessionInfoMap) -> Self {
         ShortSessionInfo {
-            id: self.id,
-            from: self.from,
-            to: self.to,
-            course: self.course,
-            part: self.part,
-            rooms: Vec::from_iter(self.rooms.into_iter()),
-            groups: Vec::from_iter(self.groups.into_iter()),
-            teachers: Vec::from_iter(self.teachers.into_iter()),
+            id: val.id,
+            from: val.from,
+            to: val.to,
+            course: val.course,
+            part: val.part,
+            class: val.class,
+            rooms: Vec::from_iter(val.rooms),
+            groups: Vec::from_iter(val.groups),
+            teachers: Vec::from_iter(val.teachers),
+            expected_attendance: val.expected_attendance,
         }
     }
 }
 
+/// What the sessions are selected on, an empty list doesn't filter
+#[derive(Default)]
+pub struct SessionFilters {
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+    pub courses: Vec<String>,
+    pub parts: Vec<String>,
+    pub teachers: Vec<String>,
+    pub rooms: Vec<String>,
+    pub groups: Vec<String>,
+    pub students: Vec<String>,
+}
+
 pub fn get_sessions_with_filters(
     conn: &mut SqliteConnection,
     query_solution_id: i32,
-    from: Option<NaiveDateTime>,
-    to: Option<NaiveDateTime>,
-    courses_id: Vec<String>,
-    parts_id: Vec<String>,
-    teachers_id: Vec<String>,
-    rooms_id: Vec<String>,
-    groups_id: Vec<String>,
+    filters: SessionFilters,
 ) -> Result<Vec<ShortSessionInfo>, DieselError> {
+    let SessionFilters {
+        from,
+        to,
+        courses: courses_id,
+        parts: parts_id,
+        teachers: teachers_id,
+        rooms: rooms_id,
+        groups: groups_id,
+        students: students_id,
+    } = filters;
+
     let mut query = schema::solutions::table
         .filter(schema::solutions::id.eq(query_solution_id))
         .inner_join(schema::sessions::table)
@@ -89,30 +121,30 @@ pub fn get_sessions_with_filters(
                 .eq(schema::courses::id)
                 .and(schema::parts::solution_id.eq(schema::courses::solution_id))),
         )
-        .inner_join(
+        .left_join(
             schema::sessions_rooms::table
                 .on(schema::sessions_rooms::session_id.eq(schema::sessions::id)),
         )
-        .inner_join(
+        .left_join(
             schema::rooms::table.on(schema::sessions_rooms::room_id
                 .eq(schema::rooms::id)
                 .and(schema::sessions_rooms::solution_id.eq(schema::rooms::solution_id))),
         )
-        .inner_join(
+        .left_join(
             schema::sessions_teachers::table
                 .on(schema::sessions_teachers::session_id.eq(schema::sessions::id)),
         )
-        .inner_join(
+        .left_join(
             schema::teachers::table.on(schema::sessions_teachers::teacher_id
                 .eq(schema::teachers::name)
                 .and(schema::sessions_teachers::solution_id.eq(schema::teachers::solution_id))),
         )
-        .inner_join(
+        .left_join(
             schema::classes_groups::table.on(schema::classes_groups::class_id
                 .eq(schema::classes::id)
                 .and(schema::classes::solution_id.eq(schema::classes_groups::solution_id))),
         )
-        .inner_join(
+        .left_join(
             schema::groups::table.on(schema::classes_groups::group_id
                 .eq(schema::groups::id)
                 .and(schema::classes_groups::solution_id.eq(schema::groups::solution_id))),
@@ -138,6 +170,16 @@ pub fn get_sessions_with_filters(
     if !groups_id.is_empty() {
         query = query.filter(schema::groups::id.eq_any(&groups_id));
     }
+    if !students_id.is_empty() {
+        query = query.filter(
+            schema::groups::id.eq_any(
+                schema::students_groups::table
+                    .filter(schema::students_groups::solution_id.eq(query_solution_id))
+                    .filter(schema::students_groups::student_id.eq_any(&students_id))
+                    .select(schema::students_groups::group_id),
+            ),
+        );
+    }
     let mut sessions_map: HashMap<i32, ShortSessionInfoMap> = HashMap::new();
 
     query
@@ -147,10 +189,12 @@ pub fn get_sessions_with_filters(
             schema::sessions::starting_date,
             schema::courses::id,
             schema::parts::id,
+            schema::classes::id,
             schema::parts::session_length,
-            schema::rooms::id,
-            schema::groups::id,
-            schema::teachers::name,
+            schema::solutions::slot_duration_seconds,
+            schema::rooms::id.nullable(),
+            schema::groups::id.nullable(),
+            schema::teachers::name.nullable(),
         ))
         .load::<(
             i32,
@@ -158,25 +202,38 @@ pub fn get_sessions_with_filters(
             NaiveDateTime,
             String,
             String,
-            i32,
-            String,
-            String,
             String,
+            i32,
+            i32,
+            Option<String>,
+            Option<String>,
+            Option<String>,
         )>(conn)?
         .into_iter()
         .for_each(|sess| {
             let entry = sessions_map
                 .entry(sess.0)
                 .or_insert(ShortSessionInfoMap::from((
-                    sess.1, sess.2, sess.3, sess.4, sess.5,
+                    sess.1, sess.2, sess.3, sess.4, sess.5, sess.6, sess.7,
                 )));
 
-            entry.rooms.insert(ShortRoomInfo { id: sess.6 });
-            entry.groups.insert(ShortGroupInfo { id: sess.7 });
-            entry.teachers.insert(ShortTeacherInfo { id: sess.8 });
+            // a session can miss its rooms, its groups or its teachers
+            sess.8
+                .map(|id| entry.rooms.insert(ShortRoomInfo { id: id }));
+            sess.9
+                .map(|id| entry.groups.insert(ShortGroupInfo { id: id }));
+            sess.10
+                .map(|id| entry.teachers.insert(ShortTeacherInfo { id: id }));
         });
 
-    let filters_collection = [&teachers_id, &courses_id, &parts_id, &rooms_id, &groups_id];
+    let filters_collection = [
+        &teachers_id,
+        &courses_id,
+        &parts_id,
+        &rooms_id,
+        &groups_id,
+        &students_id,
+    ];
 
     if filters_collection
         .iter()
@@ -218,7 +275,7 @@ pub fn get_sessions_with_filters(
                         .insert(ShortRoomInfo { id: tuple.1 });
                 });
         }
-        if !groups_id.is_empty() {
+        if !groups_id.is_empty() || !students_id.is_empty() {
             schema::sessions::table
                 .filter(schema::sessions::id.eq_any(&session_ids))
                 .inner_join(
@@ -244,6 +301,23 @@ pub fn get_sessions_with_filters(
         }
     }
 
+    // the expected attendance of a session is the sum of the head counts of its groups
+    let head_counts: HashMap<String, i32> = schema::groups::table
+        .filter(schema::groups::solution_id.eq(query_solution_id))
+        .select((schema::groups::id, schema::groups::head_count))
+        .load::<(String, Option<i32>)>(conn)?
+        .into_iter()
+        .filter_map(|(id, head_count)| head_count.map(|count| (id, count)))
+        .collect();
+
+    sessions_map.values_mut().for_each(|session| {
+        session.expected_attendance = session
+            .groups
+            .iter()
+            .filter_map(|group| head_counts.get(&group.id))
+            .sum();
+    });
+
     return Ok(sessions_map
         .into_values()
         .map(ShortSessionInfoMap::into)
@@ -279,11 +353,367 @@ pub fn get_filter_list(
         .select(schema::groups::id)
         .get_results::<String>(conn)?;
 
+    let students = schema::students::table
+        .filter(schema::students::solution_id.eq(request_solution_id))
+        .select(schema::students::id)
+        .get_results::<String>(conn)?;
+
     Ok(FilterList {
         courses: courses,
         parts: parts,
         teachers: teachers,
         rooms: rooms,
         groups: groups,
+        students: students,
+    })
+}
+
+/// Fails with NotFound when the solution doesn't exist, a solution without rows is not an
+/// unknown solution
+fn check_solution_exists(
+    conn: &mut SqliteConnection,
+    request_solution_id: i32,
+) -> Result<(), DieselError> {
+    schema::solutions::table
+        .filter(schema::solutions::id.eq(request_solution_id))
+        .select(schema::solutions::id)
+        .get_result::<i32>(conn)?;
+
+    return Ok(());
+}
+
+pub fn get_rules(
+    conn: &mut SqliteConnection,
+    request_solution_id: i32,
+) -> Result<Vec<RuleInfo>, DieselError> {
+    check_solution_exists(conn, request_solution_id)?;
+
+    let rules = schema::rules::table
+        .filter(schema::rules::solution_id.eq(request_solution_id))
+        .order(schema::rules::id)
+        .load::<Rule>(conn)?;
+
+    let mut selectors_map: HashMap<i32, Vec<SelectorInfo>> = HashMap::new();
+    schema::rule_selectors::table
+        .filter(schema::rule_selectors::solution_id.eq(request_solution_id))
+        .order((
+            schema::rule_selectors::rule_id,
+            schema::rule_selectors::rank,
+        ))
+        .load::<RuleSelector>(conn)?
+        .into_iter()
+        .for_each(|selector| {
+            selectors_map
+                .entry(selector.rule_id)
+                .or_default()
+                .push(SelectorInfo {
+                    generator: selector.generator,
+                    filters: selector.filters,
+                })
+        });
+
+    let mut parameters_map: HashMap<i32, Vec<ConstraintParameterInfo>> = HashMap::new();
+    schema::constraint_parameters::table
+        .filter(schema::constraint_parameters::solution_id.eq(request_solution_id))
+        .order((
+            schema::constraint_parameters::rule_id,
+            schema::constraint_parameters::rank,
+        ))
+        .load::<ConstraintParameter>(conn)?
+        .into_iter()
+        .for_each(|parameter| {
+            parameters_map
+                .entry(parameter.rule_id)
+                .or_default()
+                .push(ConstraintParameterInfo {
+                    name: parameter.name,
+                    value: parameter.value,
+                })
+        });
+
+    return Ok(rules
+        .into_iter()
+        .map(|rule| RuleInfo {
+            id: rule.id,
+            selectors: selectors_map.remove(&rule.id).unwrap_or_default(),
+            constraint: ConstraintInfo {
+                name: rule.constraint_name,
+                constraint_type: rule.constraint_type,
+                parameters: parameters_map.remove(&rule.id).unwrap_or_default(),
+            },
+        })
+        .collect());
+}
+
+pub fn get_part(
+    conn: &mut SqliteConnection,
+    request_solution_id: i32,
+    request_part_id: &str,
+) -> Result<PartInfo, DieselError> {
+    let part = schema::parts::table
+        .filter(schema::parts::solution_id.eq(request_solution_id))
+        .filter(schema::parts::id.eq(request_part_id))
+        .get_result::<Part>(conn)?;
+
+    let daily_slots = schema::part_allowed_daily_slots::table
+        .filter(schema::part_allowed_daily_slots::solution_id.eq(request_solution_id))
+        .filter(schema::part_allowed_daily_slots::part_id.eq(request_part_id))
+        .select(schema::part_allowed_daily_slots::daily_slot)
+        .order(schema::part_allowed_daily_slots::daily_slot)
+        .get_results::<i32>(conn)?;
+
+    let days = schema::part_allowed_days::table
+        .filter(schema::part_allowed_days::solution_id.eq(request_solution_id))
+        .filter(schema::part_allowed_days::part_id.eq(request_part_id))
+        .select(schema::part_allowed_days::day)
+        .order(schema::part_allowed_days::day)
+        .get_results::<i32>(conn)?;
+
+    let weeks = schema::part_allowed_weeks::table
+        .filter(schema::part_allowed_weeks::solution_id.eq(request_solution_id))
+        .filter(schema::part_allowed_weeks::part_id.eq(request_part_id))
+        .select(schema::part_allowed_weeks::week)
+        .order(schema::part_allowed_weeks::week)
+        .get_results::<i32>(conn)?;
+
+    let allowed_rooms = schema::part_allowed_rooms::table
+        .filter(schema::part_allowed_rooms::solution_id.eq(request_solution_id))
+        .filter(schema::part_allowed_rooms::part_id.eq(request_part_id))
+        .select(schema::part_allowed_rooms::room_id)
+        .get_results::<String>(conn)?
+        .into_iter()
+        .map(|room_id| ShortRoomInfo { id: room_id })
+        .collect();
+
+    let allowed_teachers = schema::part_allowed_teachers::table
+        .filter(schema::part_allowed_teachers::solution_id.eq(request_solution_id))
+        .filter(schema::part_allowed_teachers::part_id.eq(request_part_id))
+        .select((
+            schema::part_allowed_teachers::teacher_id,
+            schema::part_allowed_teachers::nr_sessions,
+        ))
+        .get_results::<(String, Option<i32>)>(conn)?
+        .into_iter()
+        .map(|(teacher_id, nr_sessions)| AllowedTeacherInfo {
+            id: teacher_id,
+            nr_sessions: nr_sessions,
+        })
+        .collect();
+
+    Ok(PartInfo {
+        id: part.id,
+        course_id: part.course_id,
+        label: part.label,
+        max_head_count: part.max_head_count,
+        nr_session: part.nr_session,
+        session_rooms: part.session_rooms,
+        session_teachers: part.session_teachers,
+        allowed_slots: AllowedSlotsInfo {
+            session_length: part.session_length,
+            daily_slots: daily_slots,
+            days: days,
+            weeks: weeks,
+        },
+        allowed_rooms: allowed_rooms,
+        allowed_teachers: allowed_teachers,
     })
 }
+
+pub fn get_course_tree(
+    conn: &mut SqliteConnection,
+    request_solution_id: i32,
+) -> Result<Vec<CourseTreeNode>, DieselError> {
+    check_solution_exists(conn, request_solution_id)?;
+
+    let courses = schema::courses::table
+        .filter(schema::courses::solution_id.eq(request_solution_id))
+        .order(schema::courses::id)
+        .select(Course::as_select())
+        .load::<Course>(conn)?;
+
+    let mut parts_map: HashMap<String, Vec<Part>> = HashMap::new();
+    schema::parts::table
+        .filter(schema::parts::solution_id.eq(request_solution_id))
+        .order(schema::parts::id)
+        .load::<Part>(conn)?
+        .into_iter()
+        .for_each(|part| {
+            parts_map
+                .entry(part.course_id.clone())
+                .or_default()
+                .push(part)
+        });
+
+    let classes = schema::classes::table
+        .filter(schema::classes::solution_id.eq(request_solution_id))
+        .order(schema::classes::id)
+        .load::<Class>(conn)?;
+
+    let mut groups_map: HashMap<String, Vec<String>> = HashMap::new();
+    schema::classes_groups::table
+        .filter(schema::classes_groups::solution_id.eq(request_solution_id))
+        .select((
+            schema::classes_groups::class_id,
+            schema::classes_groups::group_id,
+        ))
+        .load::<(String, String)>(conn)?
+        .into_iter()
+        .for_each(|(class_id, group_id)| groups_map.entry(class_id).or_default().push(group_id));
+
+    let mut part_classes_map = build_part_classes(&classes, &groups_map);
+
+    return Ok(courses
+        .into_iter()
+        .map(|course| CourseTreeNode {
+            parts: parts_map
+                .remove(&course.id)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|part| PartTreeNode {
+                    classes: part_classes_map
+                        .remove(part.id.as_str())
+                        .unwrap_or_default(),
+                    id: part.id,
+                    label: part.label,
+                })
+                .collect(),
+            id: course.id,
+            label: course.name,
+        })
+        .collect());
+}
+
+/// The class nodes of each part. Each class is a node once: under its parent, or at the top of
+/// its part when its parent is unknown. The classes left are in a cycle of parents, the first one
+/// met starts the cycle.
+fn build_part_classes<'a>(
+    classes: &'a [Class],
+    groups_map: &HashMap<String, Vec<String>>,
+) -> HashMap<&'a str, Vec<ClassTreeNode>> {
+    let class_ids: HashSet<&str> = classes.iter().map(|class| class.id.as_str()).collect();
+    let mut children_map: HashMap<&str, Vec<&Class>> = HashMap::new();
+    for class in classes {
+        if let Some(parent_id) = class.parent_id.as_deref() {
+            children_map.entry(parent_id).or_default().push(class);
+        }
+    }
+
+    let roots = classes.iter().filter(|class| {
+        class
+            .parent_id
+            .as_deref()
+            .is_none_or(|parent_id| !class_ids.contains(parent_id))
+    });
+    let mut placed = HashSet::new();
+    let mut part_classes_map: HashMap<&'a str, Vec<ClassTreeNode>> = HashMap::new();
+    for class in roots.chain(classes.iter()) {
+        if !placed.contains(class.id.as_str()) {
+            let node = build_class_node(class, &children_map, groups_map, &mut placed);
+            part_classes_map
+                .entry(class.part_id.as_str())
+                .or_default()
+                .push(node);
+        }
+    }
+
+    return part_classes_map;
+}
+
+/// Builds the node of a class and, recursively, the nodes of the classes having it as parent.
+/// `placed` holds the classes already in the tree, so that a cyclic hierarchy in the file can't
+/// loop forever and no class is given twice.
+fn build_class_node<'a>(
+    class: &'a Class,
+    children_map: &HashMap<&str, Vec<&'a Class>>,
+    groups_map: &HashMap<String, Vec<String>>,
+    placed: &mut HashSet<&'a str>,
+) -> ClassTreeNode {
+    placed.insert(class.id.as_str());
+
+    let mut children = Vec::new();
+    for child in children_map.get(class.id.as_str()).into_iter().flatten() {
+        if !placed.contains(child.id.as_str()) {
+            children.push(build_class_node(child, children_map, groups_map, placed));
+        }
+    }
+
+    ClassTreeNode {
+        id: class.id.clone(),
+        label: class.label.clone(),
+        groups: groups_map
+            .get(&class.id)
+            .map(|groups| {
+                groups
+                    .iter()
+                    .map(|group_id| ShortGroupInfo {
+                        id: group_id.clone(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+        children: children,
+    }
+}
+
+pub fn get_import_report(
+    conn: &mut SqliteConnection,
+    request_solution_id: i32,
+) -> Result<ImportReportInfo, DieselError> {
+    check_solution_exists(conn, request_solution_id)?;
+
+    let warnings = schema::import_warnings::table
+        .filter(schema::import_warnings::solution_id.eq(request_solution_id))
+        .order(schema::import_warnings::id)
+        .load::<ImportWarning>(conn)?;
+
+    return Ok(ImportReportInfo {
+        solution_id: request_solution_id,
+        warnings: warnings.into_iter().map(ImportWarningInfo::from).collect(),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::build_part_classes;
+    use crate::{api::dto::ClassTreeNode, db::model::Class};
+
+    fn class(id: &str, part_id: &str, parent_id: Option<&str>) -> Class {
+        Class {
+            solution_id: 1,
+            id: id.to_string(),
+            part_id: part_id.to_string(),
+            parent_id: parent_id.map(String::from),
+            label: None,
+        }
+    }
+
+    fn ids(nodes: &[ClassTreeNode]) -> Vec<String> {
+        nodes
+            .iter()
+            .map(|node| match node.children.is_empty() {
+                true => node.id.clone(),
+                false => format!("{}({})", node.id, ids(&node.children).join(",")),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_give_each_class_once() {
+        let classes = vec![
+            class("cm", "cm", None),
+            class("td", "td", Some("cm")),
+            class("tp", "tp", Some("td")),
+            class("orphan", "tp", Some("unknown")),
+            class("loop-a", "td", Some("loop-b")),
+            class("loop-b", "td", Some("loop-a")),
+        ];
+
+        let parts = build_part_classes(&classes, &HashMap::new());
+
+        assert_eq!(ids(&parts["cm"]), vec!["cm(td(tp))"]);
+        assert_eq!(ids(&parts["td"]), vec!["loop-a(loop-b)"]);
+        assert_eq!(ids(&parts["tp"]), vec!["orphan"]);
+    }
+}
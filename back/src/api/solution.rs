@@ -1,5 +1,14 @@
 mod buffer_handler;
-mod calendar_handler;
+pub mod calendar_handler;
+mod checker;
 pub mod controller;
+pub mod export;
+mod import_report;
+mod itc;
+mod itc_types;
+mod pipeline;
+pub mod progress;
 mod service;
-mod xml_types;
+mod sink;
+mod table_buffer;
+pub mod xml_types;
@@ -1,252 +1,271 @@
-use std::ops::Deref;
+use std::{collections::HashMap, sync::Arc};
 
-use diesel::{QueryResult, RunQueryDsl, SqliteConnection};
-use log::{error, warn};
+use diesel::{ExpressionMethods, QueryDsl, QueryResult, RunQueryDsl, SqliteConnection};
+use log::warn;
 
+use super::{progress::ImportProgress, table_buffer::TableBuffers};
 use crate::db::{
     model::{
-        Class, ClassGroupOwn, ClassRoomOwn, ClassTeacherOwn, Course, Part, Room, Session,
-        SolutionGroupOwn, Student, StudentGroupOwn, Teacher,
+        Class, ClassGroupOwn, ClassRoomOwn, ClassTeacherOwn, ConstraintParameter, Course,
+        ImportWarning, Part, PartAllowedDailySlot, PartAllowedDay, PartAllowedRoom,
+        PartAllowedTeacher, PartAllowedWeek, Room, Rule, RuleSelector, Session, SessionRoomOwn,
+        SessionTeacherOwn, SolutionGroupOwn, Student, StudentCourseOwn, StudentGroupOwn, Teacher,
     },
     schema,
 };
 
-fn use_query<F, R>(base_query: &str, buff: &mut Vec<String>, rows_to_insert: &mut i32, f: F) -> R
-where
-    F: FnOnce(String) -> R,
-{
-    let query_nb = buff.len();
-
-    let header = format!("INSERT OR IGNORE INTO {} VALUES ", base_query);
-
-    let capacity_needed = base_query.len()
-        + buff.deref().iter().map(String::len).sum::<usize>()
-        + header.len()
-        + (2 * query_nb);
+/// Link between a session, known by its class and rank until it is inserted, and a room or a teacher
+pub struct SessionLink {
+    pub class_id: String,
+    pub rank: i32,
+    pub ref_id: String,
+}
 
-    let mut query = String::with_capacity(capacity_needed);
+/// Row of `sessions_teachers` before its session is inserted
+pub struct SessionTeacherLink(pub SessionLink);
 
-    query.push_str(header.as_str());
+/// Row of `sessions_rooms` before its session is inserted
+pub struct SessionRoomLink(pub SessionLink);
 
-    for i in 0..query_nb {
-        let buff_element = buff.pop();
+/// Ids of the inserted sessions, by class and rank
+pub struct SessionIds {
+    solution_id: i32,
+    ids: HashMap<(String, i32), i32>,
+    last_session_id: i32,
+}
 
-        match buff_element {
-            Some(elem) => {
-                *rows_to_insert -= 1;
-                query.push('(');
-                query.push_str(elem.as_str());
-                query.push(')');
-                if i != query_nb - 1 {
-                    query.push(',');
-                }
-            }
-            None => break,
+impl SessionIds {
+    fn new(solution_id: i32) -> Self {
+        SessionIds {
+            solution_id: solution_id,
+            ids: HashMap::new(),
+            last_session_id: 0,
         }
     }
 
-    // debug!("query : {}", query);
-
-    let ret = f(query);
-
-    buff.clear();
+    /// Fetches the ids of the sessions inserted since the last call
+    fn load(&mut self, conn: &mut SqliteConnection) -> QueryResult<()> {
+        schema::sessions::table
+            .filter(schema::sessions::solution_id.eq(self.solution_id))
+            .filter(schema::sessions::id.gt(self.last_session_id))
+            .select((
+                schema::sessions::id,
+                schema::sessions::class_id,
+                schema::sessions::rank,
+            ))
+            .load::<(i32, String, i32)>(conn)?
+            .into_iter()
+            .for_each(|(id, class_id, rank)| {
+                self.last_session_id = self.last_session_id.max(id);
+                self.ids.insert((class_id, rank), id);
+            });
+
+        return Ok(());
+    }
 
-    return ret;
+    /// Keeps the links whose session has been inserted, along with the session id
+    fn resolve<'a>(
+        &'a self,
+        links: impl Iterator<Item = &'a SessionLink> + 'a,
+    ) -> impl Iterator<Item = (i32, &'a SessionLink)> + 'a {
+        links.filter_map(
+            |link| match self.ids.get(&(link.class_id.clone(), link.rank)) {
+                Some(session_id) => Some((*session_id, link)),
+                None => {
+                    warn!(
+                        "The session {} of class {} was not inserted, ignoring its link to {}",
+                        link.rank, link.class_id, link.ref_id
+                    );
+                    None
+                }
+            },
+        )
+    }
 }
 
-fn use_buffer<T, F, R>(buff: &mut Vec<T>, rows_to_insert: &mut i32, f: F) -> R
-where
-    F: FnOnce(&Vec<T>) -> R,
-{
-    let ret = f(&buff);
-
-    *rows_to_insert -= buff.len() as i32;
-    buff.clear();
+/// Inserts the rows given to the buffer of a table
+macro_rules! insert_or_ignore {
+    ($table:ident) => {
+        |conn, _, rows| {
+            diesel::insert_or_ignore_into(schema::$table::table)
+                .values(rows)
+                .execute(conn)
+        }
+    };
+}
 
-    return ret;
+/// Inserts the rows given to the buffer of a table, failing on duplicates
+macro_rules! insert {
+    ($table:ident) => {
+        |conn, _, rows| {
+            diesel::insert_into(schema::$table::table)
+                .values(rows)
+                .execute(conn)
+        }
+    };
 }
 
+/// Buffers the rows of an import, a table is inserted when its buffer is full and every
+/// remaining row at the end.
+///
+/// A new table takes one `register` line, with the tables its rows reference.
 pub struct BufferHandler {
-    pub rows_to_insert: i32,
-
-    pub rooms_to_insert: Vec<Room>,
-    pub teachers_to_insert: Vec<Teacher>,
-    pub classes_to_insert: Vec<Class>,
-    pub courses_to_insert: Vec<Course>,
-    pub parts_to_insert: Vec<Part>,
-    pub students_to_insert: Vec<Student>,
-    pub solution_groups_to_insert: Vec<SolutionGroupOwn>,
-    pub students_groups_to_insert: Vec<StudentGroupOwn>,
-    pub sessions_to_insert: Vec<Session>,
-    pub sessions_teachers_to_insert_queries: Vec<String>,
-    pub sessions_rooms_to_insert_queries: Vec<String>,
-    pub classes_groups_to_insert: Vec<ClassGroupOwn>,
-    pub classes_teachers_to_insert: Vec<ClassTeacherOwn>,
-    pub classes_rooms_to_insert: Vec<ClassRoomOwn>,
+    progress: Arc<dyn ImportProgress>,
+    session_ids: SessionIds,
+    buffers: TableBuffers<SessionIds>,
 }
 
 impl BufferHandler {
-    pub fn new() -> Self {
+    pub fn new(solution_id: i32, batch_size: usize, progress: Arc<dyn ImportProgress>) -> Self {
+        let buffers = TableBuffers::<SessionIds>::new(batch_size)
+            .register::<Room>("rooms", &[], insert_or_ignore!(rooms))
+            .register::<Teacher>("teachers", &[], insert_or_ignore!(teachers))
+            .register::<Student>("students", &[], insert_or_ignore!(students))
+            .register::<Course>("courses", &[], insert_or_ignore!(courses))
+            .register::<Part>("parts", &["courses"], insert!(parts))
+            .register::<Class>("classes", &["parts"], insert_or_ignore!(classes))
+            .register::<PartAllowedDailySlot>(
+                "part_allowed_daily_slots",
+                &["parts"],
+                insert_or_ignore!(part_allowed_daily_slots),
+            )
+            .register::<PartAllowedDay>(
+                "part_allowed_days",
+                &["parts"],
+                insert_or_ignore!(part_allowed_days),
+            )
+            .register::<PartAllowedWeek>(
+                "part_allowed_weeks",
+                &["parts"],
+                insert_or_ignore!(part_allowed_weeks),
+            )
+            .register::<PartAllowedRoom>(
+                "part_allowed_rooms",
+                &["parts", "rooms"],
+                insert_or_ignore!(part_allowed_rooms),
+            )
+            .register::<PartAllowedTeacher>(
+                "part_allowed_teachers",
+                &["parts", "teachers"],
+                insert_or_ignore!(part_allowed_teachers),
+            )
+            .register::<SolutionGroupOwn>("groups", &[], insert_or_ignore!(groups))
+            .register::<StudentGroupOwn>(
+                "students_groups",
+                &["students", "groups"],
+                insert_or_ignore!(students_groups),
+            )
+            .register::<StudentCourseOwn>(
+                "students_courses",
+                &["students", "courses"],
+                insert_or_ignore!(students_courses),
+            )
+            .register::<Session>("sessions", &["classes"], insert_or_ignore!(sessions))
+            .register::<SessionTeacherLink>(
+                "sessions_teachers",
+                &["sessions", "teachers"],
+                |conn, session_ids, links| {
+                    // the links need the ids given to the sessions by the database
+                    session_ids.load(conn)?;
+                    let rows: Vec<SessionTeacherOwn> = session_ids
+                        .resolve(links.iter().map(|link| &link.0))
+                        .map(|(session_id, link)| SessionTeacherOwn {
+                            session_id: session_id,
+                            solution_id: session_ids.solution_id,
+                            teacher_id: link.ref_id.clone(),
+                        })
+                        .collect();
+
+                    diesel::insert_or_ignore_into(schema::sessions_teachers::table)
+                        .values(&rows)
+                        .execute(conn)
+                },
+            )
+            .register::<SessionRoomLink>(
+                "sessions_rooms",
+                &["sessions", "rooms"],
+                |conn, session_ids, links| {
+                    session_ids.load(conn)?;
+                    let rows: Vec<SessionRoomOwn> = session_ids
+                        .resolve(links.iter().map(|link| &link.0))
+                        .map(|(session_id, link)| SessionRoomOwn {
+                            session_id: session_id,
+                            room_id: link.ref_id.clone(),
+                            solution_id: session_ids.solution_id,
+                        })
+                        .collect();
+
+                    diesel::insert_or_ignore_into(schema::sessions_rooms::table)
+                        .values(&rows)
+                        .execute(conn)
+                },
+            )
+            .register::<ClassGroupOwn>(
+                "classes_groups",
+                &["classes", "groups"],
+                insert_or_ignore!(classes_groups),
+            )
+            .register::<ClassTeacherOwn>(
+                "classes_teachers",
+                &["classes", "teachers"],
+                insert_or_ignore!(classes_teachers),
+            )
+            .register::<ClassRoomOwn>(
+                "classes_rooms",
+                &["classes", "rooms"],
+                insert_or_ignore!(classes_rooms),
+            )
+            .register::<Rule>("rules", &[], insert_or_ignore!(rules))
+            .register::<RuleSelector>(
+                "rule_selectors",
+                &["rules"],
+                insert_or_ignore!(rule_selectors),
+            )
+            .register::<ConstraintParameter>(
+                "constraint_parameters",
+                &["rules"],
+                insert!(constraint_parameters),
+            )
+            .register::<ImportWarning>("import_warnings", &[], insert!(import_warnings))
+            .build();
+
         BufferHandler {
-            rows_to_insert: 0,
-            rooms_to_insert: Vec::new(),
-            teachers_to_insert: Vec::new(),
-            classes_to_insert: Vec::new(),
-            courses_to_insert: Vec::new(),
-            parts_to_insert: Vec::new(),
-            students_to_insert: Vec::new(),
-            solution_groups_to_insert: Vec::new(),
-            students_groups_to_insert: Vec::new(),
-            sessions_to_insert: Vec::new(),
-            sessions_teachers_to_insert_queries: Vec::new(),
-            sessions_rooms_to_insert_queries: Vec::new(),
-            classes_groups_to_insert: Vec::new(),
-            classes_teachers_to_insert: Vec::new(),
-            classes_rooms_to_insert: Vec::new(),
+            progress: progress,
+            session_ids: SessionIds::new(solution_id),
+            buffers: buffers,
         }
     }
 
-    pub fn on_add_callback(&mut self, conn: &mut SqliteConnection) {
-        self.rows_to_insert += 1;
-
-        if self.rows_to_insert > 20000 {
-            if let Err(e) = self.insert_all_into_db(conn) {
-                error!("Error while dumping the buffer : {e}");
-            }
+    /// Buffers the rows of their table, which is inserted if it is full
+    pub fn extend<T: 'static, I: IntoIterator<Item = T>>(
+        &mut self,
+        conn: &mut SqliteConnection,
+        rows: I,
+    ) -> QueryResult<()> {
+        if let Some(table) = self.buffers.extend(rows) {
+            self.buffers
+                .flush_table(table, conn, &mut self.session_ids, &*self.progress)?;
         }
-    }
 
-    // I would like to refactor this, but Diesel typing makes it really really hard....
-    // Storing the pair (vector, table) into a vector and iterating on it would be amazing
-    pub fn insert_all_into_db(&mut self, conn: &mut SqliteConnection) -> QueryResult<usize> {
-        let mut nb_inserted: usize = 0;
-
-        nb_inserted += use_buffer(&mut self.rooms_to_insert, &mut self.rows_to_insert, |b| {
-            diesel::insert_or_ignore_into(schema::rooms::table)
-                .values(b)
-                .execute(conn)
-        })?;
-
-        nb_inserted += use_buffer(
-            &mut self.teachers_to_insert,
-            &mut self.rows_to_insert,
-            |b| {
-                diesel::insert_or_ignore_into(schema::teachers::table)
-                    .values(b)
-                    .execute(conn)
-            },
-        )?;
+        return Ok(());
+    }
 
-        nb_inserted += use_buffer(&mut self.classes_to_insert, &mut self.rows_to_insert, |b| {
-            diesel::insert_or_ignore_into(schema::classes::table)
-                .values(b)
-                .execute(conn)
-        })?;
+    pub fn push<T: 'static>(&mut self, conn: &mut SqliteConnection, row: T) -> QueryResult<()> {
+        self.extend(conn, std::iter::once(row))
+    }
 
-        nb_inserted += use_buffer(&mut self.courses_to_insert, &mut self.rows_to_insert, |b| {
-            diesel::insert_or_ignore_into(schema::courses::table)
-                .values(b)
-                .execute(conn)
-        })?;
+    pub fn insert_all_into_db(&mut self, conn: &mut SqliteConnection) -> QueryResult<usize> {
+        let nb_inserted = self
+            .buffers
+            .flush_all(conn, &mut self.session_ids, &*self.progress)?;
 
-        nb_inserted += use_buffer(&mut self.parts_to_insert, &mut self.rows_to_insert, |b| {
-            diesel::insert_into(schema::parts::table)
-                .values(b)
-                .execute(conn)
-        })?;
-
-        nb_inserted += use_buffer(
-            &mut self.students_to_insert,
-            &mut self.rows_to_insert,
-            |b| {
-                diesel::insert_or_ignore_into(schema::students::table)
-                    .values(b)
-                    .execute(conn)
-            },
-        )?;
-
-        nb_inserted += use_buffer(
-            &mut self.solution_groups_to_insert,
-            &mut self.rows_to_insert,
-            |b| {
-                diesel::insert_or_ignore_into(schema::groups::table)
-                    .values(b)
-                    .execute(conn)
-            },
-        )?;
-
-        nb_inserted += use_buffer(
-            &mut self.students_groups_to_insert,
-            &mut self.rows_to_insert,
-            |b| {
-                diesel::insert_or_ignore_into(schema::students_groups::table)
-                    .values(b)
-                    .execute(conn)
-            },
-        )?;
-
-        nb_inserted += use_buffer(
-            &mut self.sessions_to_insert,
-            &mut self.rows_to_insert,
-            |b| {
-                diesel::insert_or_ignore_into(schema::sessions::table)
-                    .values(b)
-                    .execute(conn)
-            },
-        )?;
-
-        if self.sessions_teachers_to_insert_queries.len() > 0 {
-            nb_inserted += use_query(
-                "sessions_teachers(session_id, teacher_id, solution_id)",
-                &mut self.sessions_teachers_to_insert_queries,
-                &mut self.rows_to_insert,
-                |q| diesel::sql_query(q).execute(conn),
-            )?;
-        } else {
+        if self.buffers.inserted("sessions_teachers") == 0 {
             warn!("No Sessions teachers inserted, are sessions parsed well ?")
         }
-
-        if self.sessions_rooms_to_insert_queries.len() > 0 {
-            nb_inserted += use_query(
-                "sessions_rooms(session_id, room_id, solution_id)",
-                &mut self.sessions_rooms_to_insert_queries,
-                &mut self.rows_to_insert,
-                |q| diesel::sql_query(q).execute(conn),
-            )?;
-        } else {
+        if self.buffers.inserted("sessions_rooms") == 0 {
             warn!("No Sessions rooms inserted, are sessions parsed well ?")
         }
 
-        nb_inserted += use_buffer(
-            &mut self.classes_groups_to_insert,
-            &mut self.rows_to_insert,
-            |b| {
-                diesel::insert_or_ignore_into(schema::classes_groups::table)
-                    .values(b)
-                    .execute(conn)
-            },
-        )?;
-
-        nb_inserted += use_buffer(
-            &mut self.classes_teachers_to_insert,
-            &mut self.rows_to_insert,
-            |b| {
-                diesel::insert_or_ignore_into(schema::classes_teachers::table)
-                    .values(b)
-                    .execute(conn)
-            },
-        )?;
-
-        nb_inserted += use_buffer(
-            &mut self.classes_rooms_to_insert,
-            &mut self.rows_to_insert,
-            |b| {
-                diesel::insert_or_ignore_into(schema::classes_rooms::table)
-                    .values(b)
-                    .execute(conn)
-            },
-        )?;
-
         return Ok(nb_inserted);
     }
 }
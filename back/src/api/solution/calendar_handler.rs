@@ -1,14 +1,100 @@
-use std::{str::FromStr, usize};
+use std::{fmt, str::FromStr};
 
 use chrono::{Datelike, Days, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
-use log::warn;
 
-use super::xml_types::XmlCalendar;
+use super::xml_types::{XmlCalendar, XmlSessionStartingSlot};
+
+#[derive(Debug, PartialEq)]
+pub enum SessionDateError {
+    UnknownWeek(u32),
+    UnknownDay(u32),
+    UnknownDailySlot(u16),
+    OutOfRange,
+}
+
+impl fmt::Display for SessionDateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionDateError::UnknownWeek(week) => {
+                write!(f, "week {} is not in the calendar", week)
+            }
+            SessionDateError::UnknownDay(day) => write!(f, "day {} is not in the calendar", day),
+            SessionDateError::UnknownDailySlot(slot) => {
+                write!(f, "daily slot {} is not in the calendar", slot)
+            }
+            SessionDateError::OutOfRange => write!(f, "the date is out of range"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CalendarWarning {
+    UnevenSlots {
+        slots_nr: i32,
+        rounded_duration: u32,
+    },
+    InvalidSlotsNr {
+        slots_nr: i32,
+        kept_duration: u32,
+    },
+    InvalidStartingDate,
+    InvalidWeekSequence,
+    InvalidDaySequence,
+}
+
+impl fmt::Display for CalendarWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CalendarWarning::UnevenSlots {
+                slots_nr,
+                rounded_duration,
+            } => write!(
+                f,
+                "{} slots don't divide a day evenly, slots are rounded to {} seconds",
+                slots_nr, rounded_duration
+            ),
+            CalendarWarning::InvalidSlotsNr {
+                slots_nr,
+                kept_duration,
+            } => write!(
+                f,
+                "invalid number of slots ({}), keeping slots of {} seconds",
+                slots_nr, kept_duration
+            ),
+            CalendarWarning::InvalidStartingDate => {
+                write!(
+                    f,
+                    "the starting date described by the calendar is not valid"
+                )
+            }
+            CalendarWarning::InvalidWeekSequence => write!(f, "the week sequence seems invalid"),
+            CalendarWarning::InvalidDaySequence => write!(f, "the day sequence seems invalid"),
+        }
+    }
+}
+
+const SECONDS_IN_A_DAY: u32 = 60 * 60 * 24;
+// without a calendar in the file, the week has every day and the calendar lasts a year
+const DEFAULT_DAYS_NR: u32 = 7;
+const DEFAULT_WEEKS_NR: u32 = 53;
+
+/// The largest values the domains of a part can take in the calendar
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DomainLimits {
+    pub daily_slot: u32,
+    pub day: u32,
+    pub week: u32,
+}
 
 pub struct CalendarHandler {
     pub starting_date: NaiveDateTime,
-    pub slot_duration: u16,
+    pub slot_duration_seconds: u32,
     sequence_week_association_table: Option<Vec<u32>>,
+    /// Offset (in days) from the first day of the week, for each session day index
+    day_offset_table: Option<Vec<u32>>,
+    weeks_nr: Option<u32>,
+    days_nr: Option<u32>,
+    slots_nr: u32,
 }
 
 impl CalendarHandler {
@@ -19,20 +105,45 @@ impl CalendarHandler {
                 .unwrap()
                 .naive_local(),
 
-            slot_duration: 1,
+            slot_duration_seconds: 60,
             sequence_week_association_table: None,
+            day_offset_table: None,
+            weeks_nr: None,
+            days_nr: None,
+            slots_nr: SECONDS_IN_A_DAY / 60,
         }
     }
 
-    pub fn register_xml_calendar(&mut self, xml_calendar: &XmlCalendar) {
-        // Minutes in a day divided by the number of slots in the day
-        self.slot_duration = ((60 * 24) / xml_calendar.slots.nr) as u16;
+    /// Registers the calendar of the file, returns what had to be ignored or approximated
+    pub fn register_xml_calendar(&mut self, xml_calendar: &XmlCalendar) -> Vec<CalendarWarning> {
+        let mut warnings = Vec::new();
+
+        match slot_duration_from_slots_nr(xml_calendar.slots.nr) {
+            Ok(duration) => {
+                self.slot_duration_seconds = duration;
+                self.slots_nr = xml_calendar.slots.nr as u32;
+            }
+            Err(SlotsNrError::Uneven(duration)) => {
+                warnings.push(CalendarWarning::UnevenSlots {
+                    slots_nr: xml_calendar.slots.nr,
+                    rounded_duration: duration,
+                });
+                self.slot_duration_seconds = duration;
+                self.slots_nr = xml_calendar.slots.nr as u32;
+            }
+            Err(SlotsNrError::Invalid) => warnings.push(CalendarWarning::InvalidSlotsNr {
+                slots_nr: xml_calendar.slots.nr,
+                kept_duration: self.slot_duration_seconds,
+            }),
+        }
+        self.weeks_nr = Some(xml_calendar.weeks.nr as u32);
+        self.days_nr = Some(xml_calendar.days.nr as u32);
 
         let extracted_date = extract_starting_date(xml_calendar.year, xml_calendar.starting_week);
 
         match extracted_date {
             Some(extracted) => self.starting_date = extracted,
-            None => warn!("The starting date described by calendar is not valid !"),
+            None => warnings.push(CalendarWarning::InvalidStartingDate),
         }
 
         if let Some(week_seq) = xml_calendar.weeks.sequence.as_ref() {
@@ -40,17 +151,75 @@ impl CalendarHandler {
                 week_seq.as_str(),
                 xml_calendar.weeks.nr as usize,
             )
-            .inspect_err(|_| warn!("The sequence week format seem invalid"))
+            .inspect_err(|_| warnings.push(CalendarWarning::InvalidWeekSequence))
             .ok();
         }
+
+        if let Some(day_seq) = xml_calendar.days.sequence.as_ref() {
+            match expand_str_seq(day_seq, 7).map(|weekdays| create_day_offset_table(&weekdays)) {
+                Ok(Some((first_day_offset, table))) => {
+                    // the week starts on the first day of the sequence, not always on monday
+                    self.starting_date += chrono::Duration::days(first_day_offset as i64);
+                    self.day_offset_table = Some(table);
+                }
+                _ => warnings.push(CalendarWarning::InvalidDaySequence),
+            }
+        }
+
+        return warnings;
+    }
+
+    /// Daily slots are counted from 0, days and weeks from 1
+    pub fn domain_limits(&self) -> DomainLimits {
+        DomainLimits {
+            daily_slot: self.slots_nr.saturating_sub(1),
+            day: self.days_nr.unwrap_or(DEFAULT_DAYS_NR),
+            week: self.weeks_nr.unwrap_or(DEFAULT_WEEKS_NR),
+        }
+    }
+
+    fn get_delta_week(&self, week: u32) -> Result<u32, SessionDateError> {
+        let mapped_week = match self.sequence_week_association_table.as_ref() {
+            Some(table) => week
+                .checked_sub(1)
+                .and_then(|index| table.get(index as usize).copied()),
+            None => Some(week).filter(|w| self.weeks_nr.is_none_or(|nr| *w <= nr)),
+        };
+
+        mapped_week
+            .and_then(|w| w.checked_sub(1))
+            .ok_or(SessionDateError::UnknownWeek(week))
     }
 
-    fn get_delta_week(&self, week: u32) -> u32 {
-        self.sequence_week_association_table
-            .as_ref()
-            .and_then(|table| table.get((week - 1) as usize).copied())
-            .unwrap_or(week)
-            - 1
+    fn get_delta_day(&self, day: u32) -> Result<u32, SessionDateError> {
+        let delta_day = match self.day_offset_table.as_ref() {
+            Some(table) => day
+                .checked_sub(1)
+                .and_then(|index| table.get(index as usize).copied()),
+            None => day
+                .checked_sub(1)
+                .filter(|_| self.days_nr.is_none_or(|nr| day <= nr)),
+        };
+
+        delta_day.ok_or(SessionDateError::UnknownDay(day))
+    }
+
+    /// The first day of each week of the file, weeks are numbered from 1 as in the sessions
+    pub fn week_starts(&self) -> Vec<(u32, NaiveDate)> {
+        let weeks_nr = match self.sequence_week_association_table.as_ref() {
+            Some(table) => table.len() as u32,
+            None => self.weeks_nr.unwrap_or(0),
+        };
+
+        return (1..=weeks_nr)
+            .filter_map(|week| {
+                let delta_week = self.get_delta_week(week).ok()?;
+                self.starting_date
+                    .date()
+                    .checked_add_days(Days::new(delta_week as u64 * 7))
+                    .map(|date| (week, date))
+            })
+            .collect();
     }
 
     pub fn extract_session_date(
@@ -58,20 +227,116 @@ impl CalendarHandler {
         daily_slot: u16,
         session_week: u32,
         session_day: u32,
-    ) -> Option<NaiveDateTime> {
-        let mut extracted_date = self
+    ) -> Result<NaiveDateTime, SessionDateError> {
+        if daily_slot as u32 >= self.slots_nr {
+            return Err(SessionDateError::UnknownDailySlot(daily_slot));
+        }
+
+        let delta_week = self.get_delta_week(session_week)?;
+        let delta_day = self.get_delta_day(session_day)?;
+
+        return self
             .starting_date
-            .checked_add_days(Days::new((self.get_delta_week(session_week) * 7) as u64))?;
+            .checked_add_days(Days::new(delta_week as u64 * 7 + delta_day as u64))
+            .and_then(|date| {
+                date.checked_add_signed(slots_to_duration(
+                    daily_slot as i32,
+                    self.slot_duration_seconds as i32,
+                ))
+            })
+            .ok_or(SessionDateError::OutOfRange);
+    }
+
+    /// The inverse of `extract_session_date`, the slot of the calendar a session starts at
+    pub fn session_slot(
+        &self,
+        date: NaiveDateTime,
+    ) -> Result<XmlSessionStartingSlot, SessionDateError> {
+        let elapsed = date.signed_duration_since(self.starting_date);
+        if elapsed < chrono::Duration::zero() {
+            return Err(SessionDateError::OutOfRange);
+        }
+
+        let elapsed_days = elapsed.num_days();
+        let seconds_in_day = (elapsed - chrono::Duration::days(elapsed_days)).num_seconds();
+        let daily_slot = u16::try_from(seconds_in_day / self.slot_duration_seconds as i64)
+            .map_err(|_| SessionDateError::OutOfRange)?;
+        let delta_week = (elapsed_days / 7) as u32;
+        let delta_day = (elapsed_days % 7) as u32;
+
+        let week = match self.sequence_week_association_table.as_ref() {
+            Some(table) => table
+                .iter()
+                .position(|w| *w == delta_week + 1)
+                .map(|index| index as u32 + 1),
+            None => Some(delta_week + 1).filter(|w| self.weeks_nr.is_none_or(|nr| *w <= nr)),
+        };
+        let day = match self.day_offset_table.as_ref() {
+            Some(table) => table
+                .iter()
+                .position(|d| *d == delta_day)
+                .map(|index| index as u32 + 1),
+            None => Some(delta_day + 1).filter(|d| self.days_nr.is_none_or(|nr| *d <= nr)),
+        };
+
+        return match (week, day) {
+            (Some(week), Some(day)) => Ok(XmlSessionStartingSlot {
+                daily_slot: daily_slot,
+                day: day,
+                week: week,
+            }),
+            _ => Err(SessionDateError::OutOfRange),
+        };
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SlotsNrError {
+    /// The day can't be split in that many slots
+    Invalid,
+    /// The slots don't divide the day evenly, holds the rounded slot duration
+    Uneven(u32),
+}
 
-        extracted_date =
-            extracted_date.checked_add_signed(chrono::Duration::days((session_day - 1) as i64))?;
+/// Computes the duration (in seconds) of a slot from the number of slots in a day
+pub fn slot_duration_from_slots_nr(slots_nr: i32) -> Result<u32, SlotsNrError> {
+    if slots_nr <= 0 || slots_nr as u32 > SECONDS_IN_A_DAY {
+        return Err(SlotsNrError::Invalid);
+    }
+
+    let slots_nr = slots_nr as u32;
+
+    return match SECONDS_IN_A_DAY % slots_nr {
+        0 => Ok(SECONDS_IN_A_DAY / slots_nr),
+        _ => Err(SlotsNrError::Uneven(
+            (SECONDS_IN_A_DAY + slots_nr / 2) / slots_nr,
+        )),
+    };
+}
 
-        extracted_date = extracted_date.checked_add_signed(chrono::Duration::minutes(
-            (daily_slot * self.slot_duration) as i64,
-        ))?;
+/// Session lengths and daily slots are stored in slots, this is the only place where they
+/// become a duration
+pub fn slots_to_duration(slots: i32, slot_duration_seconds: i32) -> chrono::Duration {
+    chrono::Duration::seconds(slots as i64 * slot_duration_seconds as i64)
+}
 
-        return Some(extracted_date);
+/// Turns a sequence of weekdays (sunday = 0, monday = 1, ...) into the offset of the first
+/// weekday from monday, and the offset of every weekday from the first one.
+/// A weekday that comes before the first one belongs to the next calendar week.
+fn create_day_offset_table(weekdays: &[u32]) -> Option<(u32, Vec<u32>)> {
+    if weekdays.is_empty() || weekdays.iter().any(|d| *d > 6) {
+        return None;
     }
+
+    let offset_from_monday = |weekday: u32| (weekday + 6) % 7;
+    let first_day_offset = offset_from_monday(weekdays[0]);
+
+    let table = weekdays
+        .iter()
+        .map(|d| (offset_from_monday(*d) + 7 - first_day_offset) % 7)
+        .collect();
+
+    return Some((first_day_offset, table));
 }
 
 /// Extracts the calendar starting date from the starting year and starting week
@@ -81,7 +346,7 @@ pub fn extract_starting_date(
 ) -> Option<NaiveDateTime> {
     let starting_date = NaiveDate::from_isoywd_opt(
         calendar_starting_year,
-        calendar_starting_week as u32,
+        calendar_starting_week,
         chrono::Weekday::Mon,
     )?;
 
@@ -162,11 +427,12 @@ fn create_sequence_association_table(
     return result;
 }
 
+/// Reads the week sequence of a calendar. Its ranges exclude their end as the solver writes
+/// them: `<weeks nr="24" sequence="1,2,4,5,7-27" />` gives 24 weeks, the last one is 26.
 pub fn parse_str_seq_to_association_table(seq: &str, seq_size: usize) -> Result<Vec<u32>, ()> {
     let mut parsing_error = false;
 
     let parsed_seq = parse_str_to_sequence::<u32>(seq)
-        .into_iter()
         .inspect(|r| {
             if r.is_err() {
                 parsing_error = true;
@@ -183,14 +449,75 @@ pub fn parse_str_seq_to_association_table(seq: &str, seq_size: usize) -> Result<
     };
 }
 
+/// Expands a sequence such as \"1-3,5\" into every value it describes.
+/// Unlike the week sequence of the calendar, ranges are inclusive here: `<days>1-5</days>`
+/// is Monday to Friday.
+///
+/// A sequence going above `max` is invalid, so that a range is never expanded past it.
+pub fn expand_str_seq(seq: &str, max: u32) -> Result<Vec<u32>, ()> {
+    let mut result = Vec::new();
+
+    for item in parse_str_to_sequence::<u32>(seq.trim()) {
+        match item? {
+            Sequence::Elem(e) if e <= max => result.push(e),
+            Sequence::Range(r) if r.end <= max => result.extend(r.start..=r.end),
+            _ => return Err(()),
+        }
+    }
+
+    return Ok(result);
+}
+
+/// Writes sorted values as a sequence read back by `expand_str_seq`, [1, 2, 3, 5] gives \"1-3,5\"
+pub fn compress_seq(values: &[u32]) -> String {
+    let mut items: Vec<String> = Vec::new();
+    let mut index = 0;
+
+    while index < values.len() {
+        let start = values[index];
+        let mut end = start;
+        while index + 1 < values.len() && values[index + 1] == end + 1 {
+            index += 1;
+            end = values[index];
+        }
+
+        items.push(match start == end {
+            true => start.to_string(),
+            false => format!("{}-{}", start, end),
+        });
+        index += 1;
+    }
+
+    return items.join(",");
+}
+
+/// Positions (from 1) of the ones of a bitstring, as the weeks and days of ITC 2019:
+/// \"0101\" is the second and the fourth
+pub fn bitstring_positions(bits: &str) -> Result<Vec<u32>, ()> {
+    let mut positions = Vec::new();
+
+    for (index, bit) in bits.trim().chars().enumerate() {
+        match bit {
+            '1' => positions.push(index as u32 + 1),
+            '0' => {}
+            _ => return Err(()),
+        }
+    }
+
+    return Ok(positions);
+}
+
 #[cfg(test)]
 mod tests {
-    use chrono::{TimeZone, Utc};
+    use chrono::{NaiveDate, TimeZone, Utc};
 
     use crate::api::solution::calendar_handler::{
-        create_sequence_association_table, extract_starting_date,
-        parse_str_seq_to_association_table, parse_str_to_sequence, CalendarHandler,
+        bitstring_positions, compress_seq, create_day_offset_table,
+        create_sequence_association_table, expand_str_seq, extract_starting_date,
+        parse_str_seq_to_association_table, parse_str_to_sequence, slot_duration_from_slots_nr,
+        CalendarHandler,
         Sequence::{self, Elem, Range},
+        SessionDateError, SlotsNrError,
     };
 
     #[test]
@@ -213,11 +540,10 @@ mod tests {
 
         assert_eq!(
             calendar.extract_session_date(480, 1, 1),
-            Some(
-                Utc.with_ymd_and_hms(2023, 9, 4, 8, 0, 0)
-                    .unwrap()
-                    .naive_local()
-            )
+            Ok(Utc
+                .with_ymd_and_hms(2023, 9, 4, 8, 0, 0)
+                .unwrap()
+                .naive_local())
         );
     }
 
@@ -230,11 +556,10 @@ mod tests {
             .naive_local();
         assert_eq!(
             calendar.extract_session_date(480, 2, 1,),
-            Some(
-                Utc.with_ymd_and_hms(2023, 9, 11, 8, 0, 0)
-                    .unwrap()
-                    .naive_local()
-            )
+            Ok(Utc
+                .with_ymd_and_hms(2023, 9, 11, 8, 0, 0)
+                .unwrap()
+                .naive_local())
         );
     }
 
@@ -248,11 +573,30 @@ mod tests {
 
         assert_eq!(
             calendar.extract_session_date(480, 2, 3),
-            Some(
-                Utc.with_ymd_and_hms(2023, 9, 13, 8, 0, 0)
-                    .unwrap()
-                    .naive_local()
-            )
+            Ok(Utc
+                .with_ymd_and_hms(2023, 9, 13, 8, 0, 0)
+                .unwrap()
+                .naive_local())
+        );
+    }
+
+    #[test]
+    fn should_give_the_start_of_each_week() {
+        let mut calendar = CalendarHandler::new();
+        calendar.starting_date = Utc
+            .with_ymd_and_hms(2023, 9, 4, 0, 0, 0)
+            .unwrap()
+            .naive_local();
+        assert_eq!(calendar.week_starts(), vec![]);
+
+        calendar.sequence_week_association_table = Some(vec![1, 2, 4]);
+        assert_eq!(
+            calendar.week_starts(),
+            vec![
+                (1, NaiveDate::from_ymd_opt(2023, 9, 4).unwrap()),
+                (2, NaiveDate::from_ymd_opt(2023, 9, 11).unwrap()),
+                (3, NaiveDate::from_ymd_opt(2023, 9, 25).unwrap()),
+            ]
         );
     }
 
@@ -267,11 +611,10 @@ mod tests {
 
         assert_eq!(
             calendar.extract_session_date(480, 3, 1),
-            Some(
-                Utc.with_ymd_and_hms(2023, 9, 25, 8, 0, 0)
-                    .unwrap()
-                    .naive_local()
-            )
+            Ok(Utc
+                .with_ymd_and_hms(2023, 9, 25, 8, 0, 0)
+                .unwrap()
+                .naive_local())
         );
     }
 
@@ -343,11 +686,179 @@ mod tests {
 
         assert_eq!(
             calendar.extract_session_date(480, 3, 1,),
-            Some(
-                Utc.with_ymd_and_hms(2023, 9, 25, 8, 0, 0)
-                    .unwrap()
-                    .naive_local()
-            )
+            Ok(Utc
+                .with_ymd_and_hms(2023, 9, 25, 8, 0, 0)
+                .unwrap()
+                .naive_local())
+        );
+    }
+
+    #[test]
+    fn should_expand_sequence_with_inclusive_ranges() {
+        assert_eq!(expand_str_seq("1-2,4-5", 5), Ok(vec![1, 2, 4, 5]));
+        assert_eq!(expand_str_seq("480,570,660", 1439), Ok(vec![480, 570, 660]));
+    }
+
+    #[test]
+    fn should_not_expand_invalid_sequence() {
+        assert!(expand_str_seq("1-a", 5).is_err());
+    }
+
+    #[test]
+    fn should_not_expand_sequence_past_its_max() {
+        assert!(expand_str_seq("1-4000000000", 24).is_err());
+        assert!(expand_str_seq("1,25", 24).is_err());
+        assert_eq!(expand_str_seq("20-24", 24), Ok(vec![20, 21, 22, 23, 24]));
+    }
+
+    #[test]
+    fn should_compress_what_expands_back() {
+        assert_eq!(compress_seq(&[1, 2, 3, 5, 7, 8]), "1-3,5,7-8");
+        assert_eq!(expand_str_seq("1-3,5,7-8", 8), Ok(vec![1, 2, 3, 5, 7, 8]));
+        assert_eq!(compress_seq(&[]), "");
+    }
+
+    #[test]
+    fn should_read_the_positions_of_a_bitstring() {
+        assert_eq!(bitstring_positions("0101100"), Ok(vec![2, 4, 5]));
+        assert_eq!(bitstring_positions("0000"), Ok(vec![]));
+        assert!(bitstring_positions("01a1").is_err());
+    }
+
+    #[test]
+    fn should_create_day_offset_table_from_monday() {
+        assert_eq!(
+            create_day_offset_table(&[1, 3, 5]),
+            Some((0, vec![0, 2, 4]))
+        );
+    }
+
+    #[test]
+    fn should_create_day_offset_table_wrapping_on_next_week() {
+        // saturday, sunday, monday
+        assert_eq!(
+            create_day_offset_table(&[6, 0, 1]),
+            Some((5, vec![0, 1, 2]))
+        );
+    }
+
+    #[test]
+    fn should_not_create_day_offset_table_with_invalid_weekday() {
+        assert_eq!(create_day_offset_table(&[1, 7]), None);
+    }
+
+    #[test]
+    fn should_extract_date_with_day_sequence() {
+        let mut calendar = CalendarHandler::new();
+        calendar.starting_date = Utc
+            .with_ymd_and_hms(2023, 9, 4, 0, 0, 0)
+            .unwrap()
+            .naive_local();
+        calendar.day_offset_table = create_day_offset_table(&[1, 3, 5]).map(|(_, t)| t);
+
+        // third day of the sequence is friday
+        assert_eq!(
+            calendar.extract_session_date(480, 1, 3),
+            Ok(Utc
+                .with_ymd_and_hms(2023, 9, 8, 8, 0, 0)
+                .unwrap()
+                .naive_local())
+        );
+        assert_eq!(
+            calendar.extract_session_date(480, 1, 4),
+            Err(SessionDateError::UnknownDay(4))
+        );
+    }
+
+    #[test]
+    fn should_give_back_the_slot_of_a_date() {
+        let mut calendar = CalendarHandler::new();
+        calendar.starting_date = Utc
+            .with_ymd_and_hms(2023, 9, 4, 0, 0, 0)
+            .unwrap()
+            .naive_local();
+        calendar.sequence_week_association_table =
+            parse_str_seq_to_association_table("1,2,4,5,7-27", 24).ok();
+        calendar.day_offset_table = create_day_offset_table(&[1, 3, 5]).map(|(_, t)| t);
+
+        for (daily_slot, week, day) in [(480, 1, 1), (0, 3, 2), (1439, 24, 3)] {
+            let date = calendar
+                .extract_session_date(daily_slot, week, day)
+                .unwrap();
+            let slot = calendar.session_slot(date).unwrap();
+
+            assert_eq!(
+                (slot.daily_slot, slot.week, slot.day),
+                (daily_slot, week, day)
+            );
+        }
+
+        // the third calendar week is not in the sequence, and tuesday is not a day of it
+        let gap_week = Utc.with_ymd_and_hms(2023, 9, 18, 8, 0, 0).unwrap();
+        let tuesday = Utc.with_ymd_and_hms(2023, 9, 5, 8, 0, 0).unwrap();
+        assert!(calendar.session_slot(gap_week.naive_local()).is_err());
+        assert!(calendar.session_slot(tuesday.naive_local()).is_err());
+    }
+
+    #[test]
+    fn should_not_extract_date_outside_of_calendar() {
+        let mut calendar = CalendarHandler::new();
+        calendar.sequence_week_association_table = Some(vec![1, 2, 4]);
+
+        assert_eq!(
+            calendar.extract_session_date(480, 4, 1),
+            Err(SessionDateError::UnknownWeek(4))
+        );
+        assert_eq!(
+            calendar.extract_session_date(480, 0, 1),
+            Err(SessionDateError::UnknownWeek(0))
+        );
+        assert_eq!(
+            calendar.extract_session_date(480, 1, 0),
+            Err(SessionDateError::UnknownDay(0))
+        );
+        assert_eq!(
+            calendar.extract_session_date(1440, 1, 1),
+            Err(SessionDateError::UnknownDailySlot(1440))
+        );
+    }
+
+    #[test]
+    fn should_compute_slot_duration() {
+        assert_eq!(slot_duration_from_slots_nr(1440), Ok(60));
+        assert_eq!(slot_duration_from_slots_nr(288), Ok(300));
+        assert_eq!(slot_duration_from_slots_nr(86400), Ok(1));
+    }
+
+    #[test]
+    fn should_not_compute_slot_duration_of_uneven_or_invalid_slots() {
+        assert_eq!(
+            slot_duration_from_slots_nr(1000),
+            Err(SlotsNrError::Uneven(86))
+        );
+        assert_eq!(slot_duration_from_slots_nr(0), Err(SlotsNrError::Invalid));
+        assert_eq!(
+            slot_duration_from_slots_nr(86401),
+            Err(SlotsNrError::Invalid)
+        );
+    }
+
+    #[test]
+    fn should_extract_date_with_five_minutes_slots() {
+        let mut calendar = CalendarHandler::new();
+        calendar.starting_date = Utc
+            .with_ymd_and_hms(2023, 9, 4, 0, 0, 0)
+            .unwrap()
+            .naive_local();
+        calendar.slot_duration_seconds = 300;
+        calendar.slots_nr = 288;
+
+        assert_eq!(
+            calendar.extract_session_date(96, 1, 1),
+            Ok(Utc
+                .with_ymd_and_hms(2023, 9, 4, 8, 0, 0)
+                .unwrap()
+                .naive_local())
         );
     }
 }
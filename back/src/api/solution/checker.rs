@@ -0,0 +1,345 @@
+use chrono::NaiveDateTime;
+use log::warn;
+
+use crate::{db::model::ImportWarning, xml_parsing::reader::LineIndex};
+
+use super::{
+    calendar_handler::{expand_str_seq, CalendarHandler, DomainLimits, SessionDateError},
+    import_report::{Entity, ImportReport, SourceWarning, WarningKind},
+    sink::SolutionModel,
+    xml_types::{
+        XmlAllowedSlots, XmlCalendar, XmlCourse, XmlRoom, XmlSession, XmlSolutionClass,
+        XmlSolutionGroup, XmlStudent, XmlTeacher,
+    },
+};
+
+#[derive(Debug)]
+enum SessionError {
+    NoStartingSlot,
+    InvalidDate(SessionDateError),
+}
+
+/// Checks the elements of a solution in reading order: the calendar, the dates of the sessions,
+/// the duplicate ids and the references between the elements. Only what it accepts is imported.
+pub struct SolutionChecker {
+    report: ImportReport,
+    calendar: CalendarHandler,
+}
+
+impl SolutionChecker {
+    pub fn new() -> Self {
+        SolutionChecker {
+            report: ImportReport::new(),
+            calendar: CalendarHandler::new(),
+        }
+    }
+
+    /// The calendar used to date the sessions, the default one until the file gives its own
+    pub fn calendar(&self) -> &CalendarHandler {
+        &self.calendar
+    }
+
+    /// Following warnings and references are located at this offset of the file
+    pub fn set_offset(&mut self, offset: usize) {
+        self.report.set_offset(offset);
+    }
+
+    pub fn check_calendar(&mut self, calendar: &XmlCalendar) {
+        self.calendar
+            .register_xml_calendar(calendar)
+            .into_iter()
+            .for_each(|w| {
+                warn!("Calendar : {}", w);
+                self.report.warn(
+                    WarningKind::InvalidCalendar,
+                    String::from("calendar"),
+                    Some(w.to_string()),
+                );
+            });
+    }
+
+    pub fn check_teacher(&mut self, teacher: &XmlTeacher) -> bool {
+        self.report.declare(Entity::Teacher, &teacher.id)
+    }
+
+    pub fn check_room(&mut self, room: &XmlRoom) -> bool {
+        self.report.declare(Entity::Room, &room.id)
+    }
+
+    pub fn check_student(&mut self, student: &XmlStudent) -> bool {
+        if !self.report.declare(Entity::Student, &student.id) {
+            return false;
+        }
+
+        if let Some(courses) = &student.courses {
+            let referenced_by = format!("student {}", student.id);
+            courses.courses.iter().for_each(|c| {
+                self.report
+                    .reference(Entity::Course, &c.ref_id, &referenced_by)
+            });
+        }
+
+        return true;
+    }
+
+    pub fn check_solution_group(&mut self, group: &XmlSolutionGroup) -> bool {
+        if !self.report.declare(Entity::Group, &group.id) {
+            return false;
+        }
+
+        let referenced_by = format!("group {}", group.id);
+        if let Some(classes) = &group.classes {
+            classes.classes.iter().for_each(|c| {
+                self.report
+                    .reference(Entity::Class, &c.ref_id, &referenced_by)
+            });
+        }
+        if let Some(students) = &group.students {
+            students.students.iter().for_each(|s| {
+                self.report
+                    .reference(Entity::Student, &s.ref_id, &referenced_by)
+            });
+        }
+
+        return true;
+    }
+
+    pub fn check_solution_class(&mut self, class: &XmlSolutionClass) {
+        let referenced_by = format!("the solution of class {}", class.ref_id);
+        self.report
+            .reference(Entity::Class, &class.ref_id, "the solution");
+
+        if let Some(groups) = &class.groups {
+            groups.groups_id.iter().for_each(|g| {
+                self.report
+                    .reference(Entity::Group, &g.ref_id, &referenced_by)
+            });
+        }
+        if let Some(teachers) = &class.teachers {
+            teachers.teachers_id.iter().for_each(|t| {
+                self.report
+                    .reference(Entity::Teacher, &t.ref_id, &referenced_by)
+            });
+        }
+        if let Some(rooms) = &class.rooms {
+            rooms.rooms_id.iter().for_each(|r| {
+                self.report
+                    .reference(Entity::Room, &r.ref_id, &referenced_by)
+            });
+        }
+    }
+
+    /// The date of the session, sessions that can't be placed in the calendar are reported
+    pub fn check_session(&mut self, session: &XmlSession) -> Option<NaiveDateTime> {
+        let session_id = format!("{} of class {}", session.rank, session.class);
+
+        let date = match self.session_date(session) {
+            Ok(date) => date,
+            Err(e) => {
+                warn!("Error while adding the session {} : {:?}", session_id, e);
+                let (kind, detail) = match e {
+                    SessionError::NoStartingSlot => (WarningKind::SessionWithoutDate, None),
+                    SessionError::InvalidDate(date_error) => (
+                        WarningKind::InvalidSessionDate,
+                        Some(date_error.to_string()),
+                    ),
+                };
+                self.report
+                    .warn(kind, format!("{} {}", Entity::Session, session_id), detail);
+                return None;
+            }
+        };
+
+        if !self.report.declare(Entity::Session, &session_id) {
+            return None;
+        }
+
+        let referenced_by = format!("{} {}", Entity::Session, session_id);
+        self.report
+            .reference(Entity::Class, &session.class, &referenced_by);
+        if let Some(teachers) = &session.teachers {
+            teachers.teachers_id.iter().for_each(|t| {
+                self.report
+                    .reference(Entity::Teacher, &t.ref_id, &referenced_by)
+            });
+        }
+        if let Some(rooms) = &session.rooms {
+            rooms.rooms_id.iter().for_each(|r| {
+                self.report
+                    .reference(Entity::Room, &r.ref_id, &referenced_by)
+            });
+        }
+
+        return Some(date);
+    }
+
+    fn session_date(&self, session: &XmlSession) -> Result<NaiveDateTime, SessionError> {
+        let starting_slot = session
+            .starting_slot
+            .as_ref()
+            .ok_or(SessionError::NoStartingSlot)?;
+
+        return self
+            .calendar
+            .extract_session_date(
+                starting_slot.daily_slot,
+                starting_slot.week,
+                starting_slot.day,
+            )
+            .map_err(SessionError::InvalidDate);
+    }
+
+    /// Removes the parts and the classes of the course whose id was already declared
+    pub fn check_course(&mut self, course: &mut XmlCourse) -> bool {
+        if !self.report.declare(Entity::Course, &course.id) {
+            return false;
+        }
+
+        let limits = self.calendar.domain_limits();
+        let report = &mut self.report;
+        course.parts.retain_mut(|part| {
+            if !report.declare(Entity::Part, &part.id) {
+                return false;
+            }
+
+            // the part is kept, without its domains
+            if let Some(detail) = allowed_slots_error(&part.allowed_slots, limits) {
+                warn!(
+                    "The allowed slots of the part {} are invalid : {}",
+                    part.id, detail
+                );
+                report.warn(
+                    WarningKind::InvalidAllowedSlots,
+                    format!("{} {}", Entity::Part, part.id),
+                    Some(detail),
+                );
+            }
+
+            part.classes.class.retain(|c| {
+                if !report.declare(Entity::Class, &c.id) {
+                    return false;
+                }
+                if let Some(parent) = &c.parent {
+                    report.reference(Entity::Class, parent, &format!("class {}", c.id));
+                }
+                return true;
+            });
+
+            let referenced_by = format!("part {}", part.id);
+            if let Some(rooms) = &part.allowed_rooms {
+                rooms
+                    .rooms
+                    .iter()
+                    .for_each(|r| report.reference(Entity::Room, &r.ref_id, &referenced_by));
+            }
+            if let Some(teachers) = &part.allowed_teachers {
+                teachers
+                    .teachers
+                    .iter()
+                    .for_each(|t| report.reference(Entity::Teacher, &t.ref_id, &referenced_by));
+            }
+
+            return true;
+        });
+
+        return true;
+    }
+
+    /// Reports what the reading of the source could not import as it is
+    pub fn add_source_warning(&mut self, warning: SourceWarning) {
+        self.report
+            .warn(warning.kind, warning.subject, warning.detail);
+    }
+
+    /// Checks a solution read in memory, in the order of the sections of a file of the
+    /// application so that the warnings come as from its import
+    pub fn check_model(&mut self, model: &mut SolutionModel) {
+        if let Some(calendar) = &model.calendar {
+            self.set_offset(calendar.offset);
+            self.check_calendar(calendar);
+        }
+        for room in &model.rooms {
+            self.set_offset(room.offset);
+            self.check_room(room);
+        }
+        for teacher in &model.teachers {
+            self.set_offset(teacher.offset);
+            self.check_teacher(teacher);
+        }
+        for course in &mut model.courses {
+            self.set_offset(course.offset);
+            self.check_course(course);
+        }
+        for student in &model.students {
+            self.set_offset(student.offset);
+            self.check_student(student);
+        }
+        for group in &model.solution_groups {
+            self.set_offset(group.offset);
+            self.check_solution_group(group);
+        }
+        for class in &model.solution_classes {
+            self.set_offset(class.offset);
+            self.check_solution_class(class);
+        }
+        for session in &model.sessions {
+            self.set_offset(session.offset);
+            self.check_session(session);
+        }
+        for warning in &model.warnings {
+            self.set_offset(warning.offset);
+            self.add_source_warning(warning.element.clone());
+        }
+    }
+
+    /// Checks the references, to be called once the whole file has been read
+    pub fn close(&mut self, solution_id: i32, line_index: &LineIndex) -> Vec<ImportWarning> {
+        self.report.close(solution_id, line_index)
+    }
+}
+
+/// Tells which domain of the allowed slots is not a sequence of values of the calendar
+fn allowed_slots_error(slots: &XmlAllowedSlots, limits: DomainLimits) -> Option<String> {
+    return [
+        ("daily slots", &slots.daily_slots, limits.daily_slot),
+        ("days", &slots.days, limits.day),
+        ("weeks", &slots.weeks, limits.week),
+    ]
+    .into_iter()
+    .find(|(_, domain, max)| expand_str_seq(domain, *max).is_err())
+    .map(|(name, domain, max)| {
+        format!(
+            "the {} \"{}\" are not a sequence of values up to {}",
+            name, domain, max
+        )
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::allowed_slots_error;
+    use crate::api::solution::{calendar_handler::DomainLimits, xml_types::XmlAllowedSlots};
+
+    #[test]
+    fn should_tell_the_domain_going_past_the_calendar() {
+        let limits = DomainLimits {
+            daily_slot: 1439,
+            day: 5,
+            week: 24,
+        };
+        let slots = |weeks: &str| XmlAllowedSlots {
+            session_lenght: 80,
+            daily_slots: String::from("480,570"),
+            days: String::from("1-5"),
+            weeks: String::from(weeks),
+        };
+
+        assert_eq!(allowed_slots_error(&slots("1-24"), limits), None);
+        assert_eq!(
+            allowed_slots_error(&slots("1-4000000000"), limits),
+            Some(String::from(
+                "the weeks \"1-4000000000\" are not a sequence of values up to 24"
+            ))
+        );
+    }
+}
@@ -1,26 +1,47 @@
-use std::{fs::File, io::BufReader, path::Path, str};
+use std::{
+    fs::File,
+    io::{self, BufReader, Read},
+    path::Path,
+    str,
+    sync::Arc,
+};
 
 use actix_multipart::form::{tempfile::TempFile, MultipartForm};
-use actix_web::{error as actix_error, post, web, Error as ActixError, HttpResponse, Responder};
+use actix_web::{
+    error as actix_error, get, http::header, post, web, Error as ActixError, HttpResponse,
+    Responder,
+};
 use chrono::Utc;
-use diesel::{Connection, ExpressionMethods};
-use log::{debug, warn};
-use quick_xml::events::Event;
-use serde::Serialize;
+use diesel::{Connection, ExpressionMethods, SqliteConnection};
+use log::debug;
+use serde::Deserialize;
 
 use crate::{
-    api::solution::service::SolutionInserter,
-    db::schema,
-    xml_parsing::reader::{
-        self, EventHandlingError, Router, XmlParser, XmlRouting, XmlRoutingError,
+    api::{
+        do_with_db,
+        dto::{
+            CalendarWeekInfo, DetectedCalendarInfo, EntityCountsInfo, ImportErrorInfo,
+            ImportErrorKind, ImportJobInfo, ImportReportInfo, ImportWarningInfo,
+            SolutionValidationInfo,
+        },
+        import::{
+            config::ImportConfig,
+            job::{ImportJobs, JobProgress},
+        },
+        solution::service::SolutionInserter,
     },
+    db::{model::ImportWarning, schema},
+    xml_parsing::reader::{self, LineIndex, XmlRoutingError},
     DbPool,
 };
 
-use super::xml_types::{
-    XmlCalendar, XmlCourse, XmlRoom, XmlSession, XmlSolutionClass, XmlSolutionGroup, XmlStudent,
-    XmlTeacher,
-};
+use super::checker::SolutionChecker;
+use super::export;
+use super::import_report::WarningKind;
+use super::itc::{self, ItcFiles};
+use super::pipeline::{self, ExtractFileError, ParsingError};
+use super::progress::ImportProgress;
+use super::sink::{SinkError, SolutionModel, SolutionSink, SolutionStats, Tee};
 
 #[derive(MultipartForm)]
 struct SolutionUpload {
@@ -28,18 +49,183 @@ struct SolutionUpload {
     file: TempFile,
 }
 
-#[derive(Serialize)]
-struct UploadResult {
-    pub id: i32,
-    pub row_inserted: usize,
+#[derive(MultipartForm)]
+struct ItcUpload {
+    problem: TempFile,
+    solution: Option<TempFile>,
 }
 
-#[post("")]
-pub async fn post_route(
-    payload: MultipartForm<SolutionUpload>,
-    pool: web::Data<DbPool>,
-) -> Result<impl Responder, ActixError> {
-    match payload.file.content_type.as_ref() {
+#[derive(Deserialize, Default, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum ImportMode {
+    /// Imports what it can and reports the rest
+    #[default]
+    Lenient,
+    /// Rejects the solution if an element references something that is not in the file
+    Strict,
+}
+
+#[derive(Deserialize)]
+struct UploadOptions {
+    #[serde(default)]
+    mode: ImportMode,
+}
+
+/// An ITC 2019 instance does not date its weeks, the first one is the `starting_week` of `year`
+/// (its first week by default). The year is required so that an instance always gives the same
+/// dates.
+#[derive(Deserialize)]
+struct ItcUploadOptions {
+    #[serde(default)]
+    mode: ImportMode,
+    year: i32,
+    starting_week: Option<u32>,
+}
+
+/// The files an import reads
+enum ImportSource<'a> {
+    Solution(&'a Path),
+    Itc(ItcFiles<'a>),
+}
+
+impl ImportSource<'_> {
+    fn extract<S: SolutionSink + ?Sized>(
+        &self,
+        config: &ImportConfig,
+        sink: &mut S,
+    ) -> Result<(), ExtractFileError> {
+        match self {
+            ImportSource::Solution(path) => pipeline::extract_file(path, config, sink),
+            ImportSource::Itc(files) => itc::extract_itc_files(files, config, sink),
+        }
+    }
+
+    /// The lines of an ITC solution are counted after the ones of its problem, as its offsets
+    fn line_index(&self) -> io::Result<LineIndex> {
+        match self {
+            ImportSource::Solution(path) => LineIndex::from_file(path),
+            ImportSource::Itc(files) => match files.solution {
+                Some(solution) => LineIndex::from_reader(
+                    BufReader::new(File::open(files.problem)?)
+                        .chain(BufReader::new(File::open(solution)?)),
+                ),
+                None => LineIndex::from_file(files.problem),
+            },
+        }
+    }
+}
+
+struct ImportResult {
+    pub solution_id: i32,
+    pub warnings: Vec<ImportWarning>,
+}
+
+enum ImportError {
+    DbError(diesel::result::Error),
+    ExtractFileError(ExtractFileError),
+    FileReadingError(io::Error),
+    DanglingReferences(Vec<ImportWarning>),
+}
+
+impl From<diesel::result::Error> for ImportError {
+    fn from(value: diesel::result::Error) -> Self {
+        ImportError::DbError(value)
+    }
+}
+
+impl From<ImportError> for ImportErrorInfo {
+    fn from(value: ImportError) -> Self {
+        let (kind, message) = match value {
+            ImportError::DanglingReferences(references) => {
+                return ImportErrorInfo {
+                    kind: ImportErrorKind::DanglingReferences,
+                    message: format!(
+                        "{} references point to elements that are not in the file, nothing was imported",
+                        references.len()
+                    ),
+                    dangling_references: references
+                        .into_iter()
+                        .map(ImportWarningInfo::from)
+                        .collect(),
+                }
+            }
+            ImportError::DbError(dbe) => (
+                ImportErrorKind::Internal,
+                format!("Error while interacting with the database : {:?}", dbe),
+            ),
+            ImportError::FileReadingError(fe) => (
+                ImportErrorKind::Internal,
+                format!("Error while reading the file : {:?}", fe),
+            ),
+            ImportError::ExtractFileError(ExtractFileError::FileOpeningError(fe)) => (
+                ImportErrorKind::Internal,
+                format!("Error while opening the file : {:?}", fe),
+            ),
+            ImportError::ExtractFileError(ExtractFileError::ParserPanicked) => (
+                ImportErrorKind::Internal,
+                String::from("The parser of the file crashed"),
+            ),
+            ImportError::ExtractFileError(ExtractFileError::SinkError(SinkError::DbError(dbe))) => (
+                ImportErrorKind::Internal,
+                format!("Error while inserting the solution : {:?}", dbe),
+            ),
+            ImportError::ExtractFileError(ExtractFileError::InvalidRoute(re)) => (
+                ImportErrorKind::Internal,
+                format!("Error while building the router : {}", re),
+            ),
+            ImportError::ExtractFileError(ExtractFileError::RoutingError(re)) => match re {
+                XmlRoutingError::RoutingError(e) => (
+                    ImportErrorKind::InvalidFile,
+                    format!("Error while parsing the file : {:?}", e),
+                ),
+                XmlRoutingError::LimitExceeded(le)
+                | XmlRoutingError::HandlingError(ParsingError::HandlingError(
+                    reader::EventHandlingError::LimitExceeded(le),
+                )) => (
+                    ImportErrorKind::LimitExceeded,
+                    format!("The file was refused, {}", le),
+                ),
+                XmlRoutingError::HandlingError(ParsingError::HandlingError(
+                    reader::EventHandlingError::DeserializationError(
+                        reader::DeserializationError::DeError(de),
+                    ),
+                )) => (
+                    ImportErrorKind::InvalidFile,
+                    format!("Error while deserializing a tag: {}", de),
+                ),
+                XmlRoutingError::DecodingError { offset, error }
+                | XmlRoutingError::HandlingError(ParsingError::HandlingError(
+                    reader::EventHandlingError::DecodingError { offset, error },
+                )) => (
+                    ImportErrorKind::InvalidFile,
+                    format!(
+                        "The file is not in the encoding it declares, at byte {} : {:?}",
+                        offset, error
+                    ),
+                ),
+                XmlRoutingError::HandlingError(ParsingError::HandlingError(
+                    reader::EventHandlingError::UnsupportedEventType,
+                )) => (
+                    ImportErrorKind::Internal,
+                    "Error while trying to deserialize an Event....the code is buggy :(".to_string(),
+                ),
+                XmlRoutingError::HandlingError(ParsingError::InserterStopped) => (
+                    ImportErrorKind::Internal,
+                    String::from("The insertion stopped before the end of the file"),
+                ),
+            },
+        };
+
+        return ImportErrorInfo {
+            kind: kind,
+            message: message,
+            dangling_references: Vec::new(),
+        };
+    }
+}
+
+fn check_xml_file(file: &TempFile) -> Result<(), ActixError> {
+    match file.content_type.as_ref() {
         Some(ct) => {
             if ct.subtype() != mime::XML {
                 return Result::Err(actix_error::ErrorBadRequest(format!(
@@ -50,207 +236,338 @@ pub async fn post_route(
         None => return Result::Err(actix_error::ErrorBadRequest("No content")),
     };
 
-    enum BlockError {
-        DbError(diesel::result::Error),
-        ExtractFileError(ExtractFileError),
-    }
-    impl From<diesel::result::Error> for BlockError {
-        fn from(value: diesel::result::Error) -> Self {
-            BlockError::DbError(value)
-        }
-    }
+    return Ok(());
+}
 
-    web::block(move || -> Result<UploadResult, BlockError> {
-        let mut conn = pool.get().expect("couldn't get db connection from pool");
-
-        return conn.transaction(|trans_conn| {
-            let mut solution_inserter = SolutionInserter::new(
-                trans_conn,
-                &(
-                    schema::solutions::filename.eq(payload
-                        .file
-                        .file_name
-                        .as_deref()
-                        .unwrap_or("UNKNOWN")),
-                    schema::solutions::created_at.eq(&Utc::now().naive_utc()),
-                ),
-            )
-            .map_err(|e| BlockError::DbError(e))?;
+/// Starts the import in the background, its progress is given by `GET /imports/{job_id}`,
+/// which answers with the status of the error once the import failed
+#[post("")]
+pub async fn post_route(
+    payload: MultipartForm<SolutionUpload>,
+    options: web::Query<UploadOptions>,
+    pool: web::Data<DbPool>,
+    jobs: web::Data<ImportJobs>,
+    config: web::Data<ImportConfig>,
+) -> Result<impl Responder, ActixError> {
+    check_xml_file(&payload.file)?;
 
-            debug!("Solution inserted");
+    let filename = payload
+        .file
+        .file_name
+        .clone()
+        .unwrap_or(String::from("UNKNOWN"));
+    let bytes_total = payload.file.size;
+    let mode = options.mode;
+    let config = **config;
 
-            extract_file(payload.file.file.path(), &mut solution_inserter)
-                .map_err(|e| BlockError::ExtractFileError(e))?;
+    let job = spawn_import(
+        pool,
+        jobs,
+        filename.clone(),
+        bytes_total,
+        move |conn, progress| {
+            let source = ImportSource::Solution(payload.file.file.path());
+            import_solution(conn, &source, &filename, mode, config, progress)
+        },
+    );
 
-            debug!("File extracted ");
+    return Ok(HttpResponse::Accepted().json(job));
+}
 
-            let db_result = solution_inserter
-                .insert_all_into_db()
-                .map(|inserted| UploadResult {
-                    id: solution_inserter.solution_id(),
-                    row_inserted: inserted,
-                })
-                .map_err(|e| BlockError::DbError(e))?;
+/// Starts the import of an ITC 2019 problem, with a solution of it if there is one. The progress
+/// and the warnings count the bytes and the lines of the solution after the ones of the problem.
+#[post("/itc2019")]
+pub async fn post_itc_route(
+    payload: MultipartForm<ItcUpload>,
+    options: web::Query<ItcUploadOptions>,
+    pool: web::Data<DbPool>,
+    jobs: web::Data<ImportJobs>,
+    config: web::Data<ImportConfig>,
+) -> Result<impl Responder, ActixError> {
+    check_xml_file(&payload.problem)?;
+    payload.solution.as_ref().map(check_xml_file).transpose()?;
 
-            return Ok(db_result);
-        });
-    })
-    .await?
-    .map(|result| HttpResponse::Ok().json(result))
-    .map_err(|e| match e {
-        BlockError::DbError(dbe) => actix_error::ErrorFailedDependency(format!(
-            "Error while interacting with the database : {:?}",
-            dbe
-        )),
-        BlockError::ExtractFileError(ExtractFileError::FileOpeningError(fe)) => {
-            actix_error::ErrorInternalServerError(format!(
-                "Error while opening the file : {:?}",
-                fe
-            ))
-        }
-        BlockError::ExtractFileError(ExtractFileError::RoutingError(re)) => match re {
-            XmlRoutingError::RoutingError(e) => actix_error::ErrorInternalServerError(format!(
-                "Error while parsing the file : {:?}",
-                e
-            )),
-            XmlRoutingError::HandlingError(reader::EventHandlingError::DeserializationError(
-                de,
-            )) => {
-                actix_error::ErrorBadRequest(format!("Error while deserializing a tag: {:?}", de))
-            }
-            XmlRoutingError::HandlingError(reader::EventHandlingError::UnsupportedEventType) => {
-                actix_error::ErrorInternalServerError(format!(
-                    "Error while trying to deserialize an Event....the code is buggy :("
-                ))
-            }
+    let filename = payload
+        .solution
+        .as_ref()
+        .unwrap_or(&payload.problem)
+        .file_name
+        .clone()
+        .unwrap_or(String::from("UNKNOWN"));
+    let bytes_total = payload.problem.size
+        + payload
+            .solution
+            .as_ref()
+            .map_or(0, |solution| solution.size);
+    let mode = options.mode;
+    let year = options.year;
+    let starting_week = options.starting_week.unwrap_or(1);
+    let config = **config;
+
+    let job = spawn_import(
+        pool,
+        jobs,
+        filename.clone(),
+        bytes_total,
+        move |conn, progress| {
+            let source = ImportSource::Itc(ItcFiles {
+                problem: payload.problem.file.path(),
+                solution: payload
+                    .solution
+                    .as_ref()
+                    .map(|solution| solution.file.path()),
+                year: year,
+                starting_week: starting_week,
+            });
+            import_solution(conn, &source, &filename, mode, config, progress)
         },
-    })
-}
+    );
 
-enum ExtractFileError {
-    RoutingError(XmlRoutingError<EventHandlingError>),
-    FileOpeningError(quick_xml::Error),
+    return Ok(HttpResponse::Accepted().json(job));
 }
 
-fn extract_file(
-    file: &Path,
-    solution_inserter: &mut SolutionInserter,
-) -> Result<(), ExtractFileError> {
-    let mut router: Router<BufReader<File>, EventHandlingError, SolutionInserter> = vec![
-        XmlRouting {
-            route: vec!["timetabling", "teachers", "teacher"],
-            handler: Box::from(
-                |event: Event,
-                 parser: &mut XmlParser<BufReader<File>>,
-                 context: &mut SolutionInserter| {
-                    return parser.handle_event(event, |teacher: XmlTeacher| {
-                        context.add_teacher(teacher);
-                    });
-                },
-            ),
-        },
-        XmlRouting {
-            route: vec!["timetabling", "students", "student"],
-            handler: Box::from(
-                |event: Event,
-                 parser: &mut XmlParser<BufReader<File>>,
-                 context: &mut SolutionInserter| {
-                    return parser.handle_event(event, |student: XmlStudent| {
-                        context.add_student(student);
-                    });
-                },
-            ),
-        },
-        XmlRouting {
-            route: vec!["timetabling", "courses", "course"],
-            handler: Box::from(
-                |event: Event,
-                 parser: &mut XmlParser<BufReader<File>>,
-                 context: &mut SolutionInserter| {
-                    return parser.handle_event(event, |course: XmlCourse| {
-                        context.add_course(course);
-                    });
-                },
-            ),
-        },
-        XmlRouting {
-            route: vec!["timetabling", "rooms", "room"],
-            handler: Box::from(
-                |event: Event,
-                 parser: &mut XmlParser<BufReader<File>>,
-                 context: &mut SolutionInserter| {
-                    return parser.handle_event(event, |room: XmlRoom| {
-                        // debug!("Adding room : {:?}", room);
-                        context.add_room(room);
-                    });
-                },
-            ),
-        },
-        XmlRouting {
-            route: vec!["timetabling", "solution", "sessions", "session"],
-            handler: Box::from(
-                |event: Event,
-                 parser: &mut XmlParser<BufReader<File>>,
-                 context: &mut SolutionInserter| {
-                    return parser.handle_event(event, |session: XmlSession| {
-                        // debug!("Inserting a session {:?}", session);
-                        context
-                            .add_session(session)
-                            .inspect_err(|e| {
-                                warn!("Error while adding the session : {:?}", e);
-                            })
-                            .ok();
-                    });
-                },
-            ),
-        },
-        XmlRouting {
-            route: vec!["timetabling", "solution", "groups", "group"],
-            handler: Box::from(
-                |event: Event,
-                 parser: &mut XmlParser<BufReader<File>>,
-                 context: &mut SolutionInserter| {
-                    return parser.handle_event(event, |group: XmlSolutionGroup| {
-                        context.add_solution_group(group);
-                    });
+/// Runs the import on a blocking thread and ends its job with the result
+fn spawn_import<F>(
+    pool: web::Data<DbPool>,
+    jobs: web::Data<ImportJobs>,
+    filename: String,
+    bytes_total: usize,
+    import: F,
+) -> ImportJobInfo
+where
+    F: FnOnce(&mut SqliteConnection, Arc<dyn ImportProgress>) -> Result<ImportResult, ImportError>
+        + Send
+        + 'static,
+{
+    let job = jobs.create(&filename, bytes_total);
+    let job_id = job.id.clone();
+    let progress = Arc::new(JobProgress::new(jobs.clone(), job_id.clone()));
+
+    actix_web::rt::spawn(async move {
+        let result = web::block(move || {
+            let mut conn = pool.get().expect("couldn't get db connection from pool");
+
+            import(&mut conn, progress)
+        })
+        .await;
+
+        match result {
+            Ok(Ok(imported)) => jobs.succeed(
+                &job_id,
+                imported.solution_id,
+                ImportReportInfo {
+                    solution_id: imported.solution_id,
+                    warnings: imported
+                        .warnings
+                        .into_iter()
+                        .map(ImportWarningInfo::from)
+                        .collect(),
                 },
             ),
-        },
-        XmlRouting {
-            route: vec!["timetabling", "solution", "classes", "class"],
-            handler: Box::from(
-                |event: Event,
-                 parser: &mut XmlParser<BufReader<File>>,
-                 context: &mut SolutionInserter| {
-                    return parser.handle_event(event, |class: XmlSolutionClass| {
-                        context.add_solution_class(class);
-                    });
+            Ok(Err(e)) => jobs.fail(&job_id, ImportErrorInfo::from(e)),
+            Err(e) => jobs.fail(
+                &job_id,
+                ImportErrorInfo {
+                    kind: ImportErrorKind::Internal,
+                    message: format!("The import was interrupted : {:?}", e),
+                    dangling_references: Vec::new(),
                 },
             ),
-        },
-        XmlRouting {
-            route: vec!["timetabling", "calendar"],
-            handler: Box::from(
-                |event: Event,
-                 parser: &mut XmlParser<BufReader<File>>,
-                 context: &mut SolutionInserter| {
-                    return parser.handle_event(event, |calendar: XmlCalendar| {
-                        context
-                            .add_calendar(calendar)
-                            .inspect_err(|e| warn!("Error while adding the calendar: {:?}", e))
-                            .ok();
-                    });
-                },
+        }
+    });
+
+    return job;
+}
+
+fn import_solution(
+    conn: &mut SqliteConnection,
+    source: &ImportSource,
+    filename: &str,
+    mode: ImportMode,
+    config: ImportConfig,
+    progress: Arc<dyn ImportProgress>,
+) -> Result<ImportResult, ImportError> {
+    return conn.transaction(|trans_conn| {
+        let mut solution_inserter = SolutionInserter::new(
+            trans_conn,
+            &(
+                schema::solutions::filename.eq(filename),
+                schema::solutions::created_at.eq(&Utc::now().naive_utc()),
+                // slots of one minute until the calendar says otherwise
+                schema::solutions::slot_duration_seconds.eq(60),
             ),
-        },
-    ];
+            config.batch_size,
+            progress,
+        )
+        .map_err(ImportError::DbError)?;
 
-    let mut parser =
-        XmlParser::from_file(file).map_err(|e| ExtractFileError::FileOpeningError(e))?;
+        debug!("Solution inserted");
 
-    parser
-        .walk_buffer(&mut router, solution_inserter)
-        .map_err(|e| ExtractFileError::RoutingError(e))?;
+        source
+            .extract(&config, &mut solution_inserter)
+            .map_err(ImportError::ExtractFileError)?;
 
-    return Ok(());
+        debug!("File extracted ");
+
+        let line_index = source.line_index().map_err(ImportError::FileReadingError)?;
+
+        let warnings = solution_inserter.close_report(&line_index)?;
+
+        if mode == ImportMode::Strict {
+            let dangling_references = dangling_references(&warnings);
+
+            // rolls the transaction back
+            if !dangling_references.is_empty() {
+                return Err(ImportError::DanglingReferences(dangling_references));
+            }
+        }
+
+        solution_inserter
+            .insert_all_into_db()
+            .map_err(ImportError::DbError)?;
+
+        return Ok(ImportResult {
+            solution_id: solution_inserter.solution_id(),
+            warnings: warnings,
+        });
+    });
+}
+
+/// The solution as a file of the application, importing it gives back the same solution
+#[get("/{solution_id}/export.xml")]
+pub async fn export_route(
+    info: web::Path<i32>,
+    pool: web::Data<DbPool>,
+) -> Result<impl Responder, ActixError> {
+    let solution_id = info.into_inner();
+
+    let result = do_with_db(pool, move |conn| {
+        export::export_solution(conn, solution_id)
+            .map(|document| (format!("{}.xml", document.name), export::to_xml(&document)))
+    })
+    .await?;
+
+    match result {
+        Ok((filename, Ok(xml))) => Ok(HttpResponse::Ok()
+            .content_type("application/xml")
+            .insert_header(header::ContentDisposition::attachment(filename))
+            .body(xml)),
+        Ok((_, Err(err))) => Err(actix_error::ErrorInternalServerError(err)),
+        Err(export::ExportError::DbError(diesel::result::Error::NotFound)) => Err(
+            actix_error::ErrorNotFound(format!("Solution {} not found", solution_id)),
+        ),
+        Err(export::ExportError::DbError(err)) => Err(actix_error::ErrorInternalServerError(err)),
+        Err(export::ExportError::UnplacedSessions(sessions)) => {
+            Err(actix_error::ErrorUnprocessableEntity(format!(
+                "{} sessions are not in the calendar of the solution : {}",
+                sessions.len(),
+                sessions
+                    .iter()
+                    .map(|(rank, class_id)| format!("session {} of class {}", rank, class_id))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )))
+        }
+    }
+}
+
+/// Reads the file and checks it as its import would, without touching the database
+#[post("/validate")]
+pub async fn validate_route(
+    payload: MultipartForm<SolutionUpload>,
+    options: web::Query<UploadOptions>,
+    config: web::Data<ImportConfig>,
+) -> Result<impl Responder, ActixError> {
+    check_xml_file(&payload.file)?;
+
+    let mode = options.mode;
+    let config = **config;
+
+    let validation = web::block(move || validate_solution(&payload.file, mode, config)).await?;
+
+    return Ok(HttpResponse::Ok().json(validation));
+}
+
+/// The warnings that make a strict import fail
+fn dangling_references(warnings: &[ImportWarning]) -> Vec<ImportWarning> {
+    warnings
+        .iter()
+        .filter(|w| w.kind == WarningKind::UnknownReference.as_str())
+        .cloned()
+        .collect()
+}
+
+/// Reads the whole file in memory and runs the checks of the import on it
+fn validate_solution(
+    file: &TempFile,
+    mode: ImportMode,
+    config: ImportConfig,
+) -> SolutionValidationInfo {
+    let path = file.file.path();
+    let mut stats = SolutionStats::default();
+    let mut model = SolutionModel::default();
+    let mut errors = Vec::new();
+
+    let mut sink = Tee {
+        first: &mut stats,
+        second: &mut model,
+    };
+    if let Err(e) = pipeline::extract_file(path, &config, &mut sink) {
+        errors.push(ImportErrorInfo::from(ImportError::ExtractFileError(e)));
+    }
+
+    let mut checker = SolutionChecker::new();
+    checker.check_model(&mut model);
+
+    let calendar = model.calendar.as_ref().map(|_| {
+        let detected = checker.calendar();
+        DetectedCalendarInfo {
+            starting_date: detected.starting_date,
+            slot_duration_seconds: detected.slot_duration_seconds,
+            weeks: detected
+                .week_starts()
+                .into_iter()
+                .map(|(week, starts_on)| CalendarWeekInfo {
+                    week: week,
+                    starts_on: starts_on,
+                })
+                .collect(),
+        }
+    });
+
+    // the warnings of a validation belong to no solution
+    let warnings = match LineIndex::from_file(path) {
+        Ok(line_index) => checker.close(0, &line_index),
+        Err(e) => {
+            errors.push(ImportErrorInfo::from(ImportError::FileReadingError(e)));
+            Vec::new()
+        }
+    };
+
+    if mode == ImportMode::Strict {
+        let dangling_references = dangling_references(&warnings);
+        if !dangling_references.is_empty() {
+            errors.push(ImportErrorInfo::from(ImportError::DanglingReferences(
+                dangling_references,
+            )));
+        }
+    }
+
+    return SolutionValidationInfo {
+        valid: errors.is_empty(),
+        counts: EntityCountsInfo {
+            rooms: stats.rooms,
+            teachers: stats.teachers,
+            courses: stats.courses,
+            parts: stats.parts,
+            classes: stats.classes,
+            groups: stats.groups,
+            students: stats.students,
+            sessions: stats.sessions,
+            rules: stats.rules,
+        },
+        calendar: calendar,
+        warnings: warnings.into_iter().map(ImportWarningInfo::from).collect(),
+        errors: errors,
+    };
 }
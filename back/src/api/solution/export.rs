@@ -0,0 +1,893 @@
+use std::{collections::HashMap, hash::Hash};
+
+use chrono::{Datelike, Days, NaiveDateTime};
+use diesel::{
+    dsl::{max, sql},
+    expression::SqlLiteral,
+    sql_types::Integer,
+    ExpressionMethods, OptionalExtension, QueryDsl, QueryResult, RunQueryDsl, SelectableHelper,
+    SqliteConnection,
+};
+use quick_xml::DeError;
+use serde::Serialize;
+
+use crate::db::{
+    model::{Calendar, Class, ConstraintParameter, Part, Rule, RuleSelector, Solution},
+    schema,
+};
+
+use super::calendar_handler::{compress_seq, CalendarHandler};
+use super::xml_types::{
+    XmlAllowedRooms, XmlAllowedSlots, XmlAllowedTeacher, XmlAllowedTeachers, XmlCalendar,
+    XmlCalendarMetaData, XmlClass, XmlClasses, XmlConstraint, XmlConstraintParameter,
+    XmlConstraintParameters, XmlCourse, XmlCourseList, XmlCourses, XmlGroupClasses,
+    XmlGroupStudents, XmlPart, XmlRefIdElement, XmlRoom, XmlRoomList, XmlRule, XmlRuleList,
+    XmlSelector, XmlSession, XmlSessionList, XmlSessionRooms, XmlSessionTeachers, XmlSolution,
+    XmlSolutionClass, XmlSolutionClassGroups, XmlSolutionClassList, XmlSolutionClassRooms,
+    XmlSolutionClassTeachers, XmlSolutionGroup, XmlSolutionGroupList, XmlStudent, XmlStudentList,
+    XmlTeacher, XmlTeacherList, XmlTimetabling,
+};
+
+/// Rows are inserted in the order of the file, the elements are exported in that order. The
+/// links between elements are sorted by id, the file could give them in two places.
+fn rowid() -> SqlLiteral<Integer> {
+    sql::<Integer>("rowid")
+}
+
+fn group_by<K: Hash + Eq, V>(rows: Vec<(K, V)>) -> HashMap<K, Vec<V>> {
+    let mut groups: HashMap<K, Vec<V>> = HashMap::new();
+    rows.into_iter()
+        .for_each(|(key, value)| groups.entry(key).or_default().push(value));
+
+    return groups;
+}
+
+fn ref_ids(ids: Option<Vec<String>>) -> Vec<XmlRefIdElement<String>> {
+    ids.unwrap_or_default()
+        .into_iter()
+        .map(|id| XmlRefIdElement { ref_id: id })
+        .collect()
+}
+
+#[derive(Debug)]
+pub enum ExportError {
+    DbError(diesel::result::Error),
+    /// The sessions that have no slot in the calendar, by rank and class
+    UnplacedSessions(Vec<(i32, String)>),
+}
+
+impl From<diesel::result::Error> for ExportError {
+    fn from(value: diesel::result::Error) -> Self {
+        ExportError::DbError(value)
+    }
+}
+
+/// Rebuilds the document of a stored solution, the sessions get their slots back from the
+/// calendar of the file. A session the calendar can't place fails the export, its file would
+/// not give the same solution back.
+pub fn export_solution(
+    conn: &mut SqliteConnection,
+    solution_id: i32,
+) -> Result<XmlTimetabling, ExportError> {
+    let solution = schema::solutions::table
+        .find(solution_id)
+        .select(Solution::as_select())
+        .first(conn)?;
+
+    let xml_calendar = solution_calendar(conn, &solution)?;
+    let mut calendar = CalendarHandler::new();
+    // its warnings were given by the import
+    calendar.register_xml_calendar(&xml_calendar);
+
+    return Ok(XmlTimetabling {
+        xmlns_xsi: "http://www.w3.org/2001/XMLSchema-instance",
+        schema_location: "usp_timetabling_v1_2.xsd",
+        name: solution
+            .filename
+            .strip_suffix(".xml")
+            .unwrap_or(&solution.filename)
+            .to_string(),
+        calendar: xml_calendar,
+        rooms: XmlRoomList {
+            room: export_rooms(conn, solution_id)?,
+        },
+        teachers: XmlTeacherList {
+            teacher: export_teachers(conn, solution_id)?,
+        },
+        courses: XmlCourseList {
+            course: export_courses(conn, solution_id)?,
+        },
+        students: XmlStudentList {
+            student: export_students(conn, solution_id)?,
+        },
+        rules: XmlRuleList {
+            rule: export_rules(conn, solution_id)?,
+        },
+        solution: XmlSolution {
+            groups: XmlSolutionGroupList {
+                group: export_groups(conn, solution_id)?,
+            },
+            classes: XmlSolutionClassList {
+                class: export_solution_classes(conn, solution_id)?,
+            },
+            sessions: XmlSessionList {
+                session: export_sessions(conn, solution_id, &calendar)?,
+            },
+        },
+    });
+}
+
+/// Writes the document with the XML declaration, indented as the files of the application
+pub fn to_xml(document: &XmlTimetabling) -> Result<String, DeError> {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let mut serializer = quick_xml::se::Serializer::new(&mut xml);
+    serializer.indent(' ', 4);
+    document.serialize(serializer)?;
+
+    return Ok(xml);
+}
+
+/// The calendar of the file the solution was imported from
+pub fn solution_calendar(
+    conn: &mut SqliteConnection,
+    solution: &Solution,
+) -> QueryResult<XmlCalendar> {
+    return match schema::calendars::table
+        .find(solution.id)
+        .select(Calendar::as_select())
+        .first(conn)
+        .optional()?
+    {
+        Some(calendar) => Ok(calendar.into_xml()),
+        None => rebuild_calendar(conn, solution),
+    };
+}
+
+/// Solutions imported before their calendar was kept only have their first date and their slot
+/// duration. The weeks they span are given every day, from the monday of their first date.
+fn rebuild_calendar(conn: &mut SqliteConnection, solution: &Solution) -> QueryResult<XmlCalendar> {
+    let start = solution
+        .calendar_start
+        .unwrap_or(CalendarHandler::new().starting_date)
+        .date();
+    let monday = start - Days::new(start.weekday().num_days_from_monday() as u64);
+
+    let last_session: Option<NaiveDateTime> = schema::sessions::table
+        .filter(schema::sessions::solution_id.eq(solution.id))
+        .select(max(schema::sessions::starting_date))
+        .first(conn)?;
+    let weeks_nr = last_session.map_or(1, |last| (last.date() - monday).num_days() / 7 + 1);
+
+    return Ok(XmlCalendar {
+        starting_week: monday.iso_week().week(),
+        year: monday.iso_week().year(),
+        weeks: XmlCalendarMetaData {
+            nr: weeks_nr as i32,
+            sequence: None,
+        },
+        days: XmlCalendarMetaData {
+            nr: 7,
+            sequence: None,
+        },
+        slots: XmlCalendarMetaData {
+            nr: 60 * 60 * 24 / solution.slot_duration_seconds,
+            sequence: None,
+        },
+    });
+}
+
+fn export_rooms(conn: &mut SqliteConnection, solution_id: i32) -> QueryResult<Vec<XmlRoom>> {
+    return Ok(schema::rooms::table
+        .filter(schema::rooms::solution_id.eq(solution_id))
+        .order(rowid())
+        .select((
+            schema::rooms::id,
+            schema::rooms::capacity,
+            schema::rooms::name,
+        ))
+        .load::<(String, Option<i32>, Option<String>)>(conn)?
+        .into_iter()
+        .map(|(id, capacity, name)| XmlRoom {
+            id: id,
+            capacity: capacity,
+            label: name,
+        })
+        .collect());
+}
+
+fn export_teachers(conn: &mut SqliteConnection, solution_id: i32) -> QueryResult<Vec<XmlTeacher>> {
+    return Ok(schema::teachers::table
+        .filter(schema::teachers::solution_id.eq(solution_id))
+        .order(rowid())
+        .select((schema::teachers::name, schema::teachers::department))
+        .load::<(String, Option<String>)>(conn)?
+        .into_iter()
+        .map(|(name, department)| XmlTeacher {
+            label: department,
+            id: name,
+        })
+        .collect());
+}
+
+fn export_courses(conn: &mut SqliteConnection, solution_id: i32) -> QueryResult<Vec<XmlCourse>> {
+    let mut parts = group_by(
+        schema::parts::table
+            .filter(schema::parts::solution_id.eq(solution_id))
+            .order(rowid())
+            .select(Part::as_select())
+            .load(conn)?
+            .into_iter()
+            .map(|part| (part.course_id.clone(), part))
+            .collect(),
+    );
+    let mut classes = group_by(
+        schema::classes::table
+            .filter(schema::classes::solution_id.eq(solution_id))
+            .order(rowid())
+            .select(Class::as_select())
+            .load(conn)?
+            .into_iter()
+            .map(|class| (class.part_id.clone(), class))
+            .collect(),
+    );
+    let mut daily_slots = group_by(
+        schema::part_allowed_daily_slots::table
+            .filter(schema::part_allowed_daily_slots::solution_id.eq(solution_id))
+            .order(schema::part_allowed_daily_slots::daily_slot)
+            .select((
+                schema::part_allowed_daily_slots::part_id,
+                schema::part_allowed_daily_slots::daily_slot,
+            ))
+            .load::<(String, i32)>(conn)?,
+    );
+    let mut days = group_by(
+        schema::part_allowed_days::table
+            .filter(schema::part_allowed_days::solution_id.eq(solution_id))
+            .order(schema::part_allowed_days::day)
+            .select((
+                schema::part_allowed_days::part_id,
+                schema::part_allowed_days::day,
+            ))
+            .load::<(String, i32)>(conn)?,
+    );
+    let mut weeks = group_by(
+        schema::part_allowed_weeks::table
+            .filter(schema::part_allowed_weeks::solution_id.eq(solution_id))
+            .order(schema::part_allowed_weeks::week)
+            .select((
+                schema::part_allowed_weeks::part_id,
+                schema::part_allowed_weeks::week,
+            ))
+            .load::<(String, i32)>(conn)?,
+    );
+    let mut rooms = group_by(
+        schema::part_allowed_rooms::table
+            .filter(schema::part_allowed_rooms::solution_id.eq(solution_id))
+            .order(schema::part_allowed_rooms::room_id)
+            .select((
+                schema::part_allowed_rooms::part_id,
+                schema::part_allowed_rooms::room_id,
+            ))
+            .load::<(String, String)>(conn)?,
+    );
+    let mut teachers = group_by(
+        schema::part_allowed_teachers::table
+            .filter(schema::part_allowed_teachers::solution_id.eq(solution_id))
+            .order(schema::part_allowed_teachers::teacher_id)
+            .select((
+                schema::part_allowed_teachers::part_id,
+                (
+                    schema::part_allowed_teachers::teacher_id,
+                    schema::part_allowed_teachers::nr_sessions,
+                ),
+            ))
+            .load::<(String, (String, Option<i32>))>(conn)?,
+    );
+
+    let sequence = |values: Option<Vec<i32>>| {
+        compress_seq(
+            &values
+                .unwrap_or_default()
+                .into_iter()
+                .map(|value| value as u32)
+                .collect::<Vec<u32>>(),
+        )
+    };
+
+    return Ok(schema::courses::table
+        .filter(schema::courses::solution_id.eq(solution_id))
+        .order(rowid())
+        .select((schema::courses::id, schema::courses::name))
+        .load::<(String, Option<String>)>(conn)?
+        .into_iter()
+        .map(|(course_id, name)| XmlCourse {
+            parts: parts
+                .remove(&course_id)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|part| {
+                    let part_rooms = rooms.remove(&part.id);
+                    let part_teachers = teachers.remove(&part.id);
+
+                    XmlPart {
+                        label: part.label,
+                        nr_session: part.nr_session,
+                        allowed_slots: XmlAllowedSlots {
+                            session_lenght: part.session_length,
+                            daily_slots: sequence(daily_slots.remove(&part.id)),
+                            days: sequence(days.remove(&part.id)),
+                            weeks: sequence(weeks.remove(&part.id)),
+                        },
+                        allowed_rooms: (part_rooms.is_some() || part.session_rooms.is_some()).then(
+                            || XmlAllowedRooms {
+                                session_rooms: part.session_rooms,
+                                rooms: ref_ids(part_rooms),
+                            },
+                        ),
+                        allowed_teachers: (part_teachers.is_some()
+                            || part.session_teachers.is_some())
+                        .then(|| XmlAllowedTeachers {
+                            session_teachers: part.session_teachers,
+                            teachers: part_teachers
+                                .unwrap_or_default()
+                                .into_iter()
+                                .map(|(teacher_id, nr_sessions)| XmlAllowedTeacher {
+                                    ref_id: teacher_id,
+                                    nr_sessions: nr_sessions,
+                                })
+                                .collect(),
+                        }),
+                        classes: XmlClasses {
+                            max_head_count: part.max_head_count,
+                            class: classes
+                                .remove(&part.id)
+                                .unwrap_or_default()
+                                .into_iter()
+                                .map(|class| XmlClass {
+                                    id: class.id,
+                                    parent: class.parent_id,
+                                    label: class.label,
+                                })
+                                .collect(),
+                        },
+                        id: part.id,
+                    }
+                })
+                .collect(),
+            id: course_id,
+            label: name,
+        })
+        .collect());
+}
+
+fn export_students(conn: &mut SqliteConnection, solution_id: i32) -> QueryResult<Vec<XmlStudent>> {
+    let mut courses = group_by(
+        schema::students_courses::table
+            .filter(schema::students_courses::solution_id.eq(solution_id))
+            .order(schema::students_courses::course_id)
+            .select((
+                schema::students_courses::student_id,
+                schema::students_courses::course_id,
+            ))
+            .load::<(String, String)>(conn)?,
+    );
+
+    return Ok(schema::students::table
+        .filter(schema::students::solution_id.eq(solution_id))
+        .order(rowid())
+        .select((schema::students::id, schema::students::label))
+        .load::<(String, Option<String>)>(conn)?
+        .into_iter()
+        .map(|(id, label)| XmlStudent {
+            courses: courses.remove(&id).map(|student_courses| XmlCourses {
+                courses: ref_ids(Some(student_courses)),
+            }),
+            id: id,
+            label: label,
+        })
+        .collect());
+}
+
+fn export_rules(conn: &mut SqliteConnection, solution_id: i32) -> QueryResult<Vec<XmlRule>> {
+    let mut selectors = group_by(
+        schema::rule_selectors::table
+            .filter(schema::rule_selectors::solution_id.eq(solution_id))
+            .order(schema::rule_selectors::rank)
+            .select(RuleSelector::as_select())
+            .load(conn)?
+            .into_iter()
+            .map(|selector| (selector.rule_id, selector))
+            .collect(),
+    );
+    let mut parameters = group_by(
+        schema::constraint_parameters::table
+            .filter(schema::constraint_parameters::solution_id.eq(solution_id))
+            .order(schema::constraint_parameters::rank)
+            .select(ConstraintParameter::as_select())
+            .load(conn)?
+            .into_iter()
+            .map(|parameter| (parameter.rule_id, parameter))
+            .collect(),
+    );
+
+    return Ok(schema::rules::table
+        .filter(schema::rules::solution_id.eq(solution_id))
+        .order(schema::rules::id)
+        .select(Rule::as_select())
+        .load(conn)?
+        .into_iter()
+        .map(|rule| XmlRule {
+            selectors: selectors
+                .remove(&rule.id)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|selector| XmlSelector {
+                    generator: selector.generator,
+                    filters: selector.filters,
+                })
+                .collect(),
+            constraint: XmlConstraint {
+                name: rule.constraint_name,
+                constraint_type: rule.constraint_type,
+                parameters: parameters.remove(&rule.id).map(|rule_parameters| {
+                    XmlConstraintParameters {
+                        parameters: rule_parameters
+                            .into_iter()
+                            .map(|parameter| XmlConstraintParameter {
+                                name: parameter.name,
+                                value: parameter.value,
+                            })
+                            .collect(),
+                    }
+                }),
+            },
+        })
+        .collect());
+}
+
+fn export_groups(
+    conn: &mut SqliteConnection,
+    solution_id: i32,
+) -> QueryResult<Vec<XmlSolutionGroup>> {
+    let mut students = group_by(
+        schema::students_groups::table
+            .filter(schema::students_groups::solution_id.eq(solution_id))
+            .order(schema::students_groups::student_id)
+            .select((
+                schema::students_groups::group_id,
+                schema::students_groups::student_id,
+            ))
+            .load::<(String, String)>(conn)?,
+    );
+    let mut classes = group_by(
+        schema::classes_groups::table
+            .filter(schema::classes_groups::solution_id.eq(solution_id))
+            .order(schema::classes_groups::class_id)
+            .select((
+                schema::classes_groups::group_id,
+                schema::classes_groups::class_id,
+            ))
+            .load::<(String, String)>(conn)?,
+    );
+
+    return Ok(schema::groups::table
+        .filter(schema::groups::solution_id.eq(solution_id))
+        .order(rowid())
+        .select((schema::groups::id, schema::groups::head_count))
+        .load::<(String, Option<i32>)>(conn)?
+        .into_iter()
+        .map(|(id, head_count)| XmlSolutionGroup {
+            students: students.remove(&id).map(|group_students| XmlGroupStudents {
+                students: ref_ids(Some(group_students)),
+            }),
+            classes: classes.remove(&id).map(|group_classes| XmlGroupClasses {
+                classes: ref_ids(Some(group_classes)),
+            }),
+            id: id,
+            head_count: head_count,
+        })
+        .collect());
+}
+
+/// The classes given groups, teachers or rooms by the solution
+fn export_solution_classes(
+    conn: &mut SqliteConnection,
+    solution_id: i32,
+) -> QueryResult<Vec<XmlSolutionClass>> {
+    let mut groups = group_by(
+        schema::classes_groups::table
+            .filter(schema::classes_groups::solution_id.eq(solution_id))
+            .order(schema::classes_groups::group_id)
+            .select((
+                schema::classes_groups::class_id,
+                schema::classes_groups::group_id,
+            ))
+            .load::<(String, String)>(conn)?,
+    );
+    let mut teachers = group_by(
+        schema::classes_teachers::table
+            .filter(schema::classes_teachers::solution_id.eq(solution_id))
+            .order(schema::classes_teachers::teacher_id)
+            .select((
+                schema::classes_teachers::class_id,
+                schema::classes_teachers::teacher_id,
+            ))
+            .load::<(String, String)>(conn)?,
+    );
+    let mut rooms = group_by(
+        schema::classes_rooms::table
+            .filter(schema::classes_rooms::solution_id.eq(solution_id))
+            .order(schema::classes_rooms::room_id)
+            .select((
+                schema::classes_rooms::class_id,
+                schema::classes_rooms::room_id,
+            ))
+            .load::<(String, String)>(conn)?,
+    );
+
+    return Ok(schema::classes::table
+        .filter(schema::classes::solution_id.eq(solution_id))
+        .order(rowid())
+        .select(schema::classes::id)
+        .load::<String>(conn)?
+        .into_iter()
+        .map(|class_id| XmlSolutionClass {
+            groups: groups
+                .remove(&class_id)
+                .map(|class_groups| XmlSolutionClassGroups {
+                    groups_id: ref_ids(Some(class_groups)),
+                }),
+            teachers: teachers
+                .remove(&class_id)
+                .map(|class_teachers| XmlSolutionClassTeachers {
+                    teachers_id: ref_ids(Some(class_teachers)),
+                }),
+            rooms: rooms
+                .remove(&class_id)
+                .map(|class_rooms| XmlSolutionClassRooms {
+                    rooms_id: ref_ids(Some(class_rooms)),
+                }),
+            ref_id: class_id,
+        })
+        .filter(|class| class.groups.is_some() || class.teachers.is_some() || class.rooms.is_some())
+        .collect());
+}
+
+fn export_sessions(
+    conn: &mut SqliteConnection,
+    solution_id: i32,
+    calendar: &CalendarHandler,
+) -> Result<Vec<XmlSession>, ExportError> {
+    let mut rooms = group_by(
+        schema::sessions_rooms::table
+            .filter(schema::sessions_rooms::solution_id.eq(solution_id))
+            .order(schema::sessions_rooms::room_id)
+            .select((
+                schema::sessions_rooms::session_id,
+                schema::sessions_rooms::room_id,
+            ))
+            .load::<(i32, String)>(conn)?,
+    );
+    let mut teachers = group_by(
+        schema::sessions_teachers::table
+            .filter(schema::sessions_teachers::solution_id.eq(solution_id))
+            .order(schema::sessions_teachers::teacher_id)
+            .select((
+                schema::sessions_teachers::session_id,
+                schema::sessions_teachers::teacher_id,
+            ))
+            .load::<(i32, String)>(conn)?,
+    );
+
+    let mut unplaced = Vec::new();
+    let sessions = schema::sessions::table
+        .filter(schema::sessions::solution_id.eq(solution_id))
+        .order(schema::sessions::id)
+        .select((
+            schema::sessions::id,
+            schema::sessions::class_id,
+            schema::sessions::rank,
+            schema::sessions::starting_date,
+        ))
+        .load::<(i32, String, i32, NaiveDateTime)>(conn)?
+        .into_iter()
+        .map(|(id, class_id, rank, starting_date)| XmlSession {
+            starting_slot: calendar
+                .session_slot(starting_date)
+                .inspect_err(|_| unplaced.push((rank, class_id.clone())))
+                .ok(),
+            rooms: rooms.remove(&id).map(|session_rooms| XmlSessionRooms {
+                rooms_id: ref_ids(Some(session_rooms)),
+            }),
+            teachers: teachers
+                .remove(&id)
+                .map(|session_teachers| XmlSessionTeachers {
+                    teachers_id: ref_ids(Some(session_teachers)),
+                }),
+            rank: rank,
+            class: class_id,
+        })
+        .collect();
+
+    if !unplaced.is_empty() {
+        return Err(ExportError::UnplacedSessions(unplaced));
+    }
+
+    return Ok(sessions);
+}
+
+impl Calendar {
+    fn into_xml(self) -> XmlCalendar {
+        XmlCalendar {
+            starting_week: self.starting_week as u32,
+            year: self.year,
+            weeks: XmlCalendarMetaData {
+                nr: self.weeks_nr,
+                sequence: self.weeks_sequence,
+            },
+            days: XmlCalendarMetaData {
+                nr: self.days_nr,
+                sequence: self.days_sequence,
+            },
+            slots: XmlCalendarMetaData {
+                nr: self.slots_nr,
+                sequence: None,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, fs, hash::Hash, path::Path, sync::Arc};
+
+    use diesel::{Connection, ExpressionMethods, QueryDsl, RunQueryDsl, SqliteConnection};
+    use diesel_migrations::MigrationHarness;
+
+    use super::{export_solution, to_xml, ExportError};
+    use crate::{
+        api::{
+            import::config::ImportConfig,
+            solution::{
+                pipeline,
+                progress::NoProgress,
+                service::SolutionInserter,
+                sink::SolutionModel,
+                xml_types::{
+                    XmlCourseList, XmlRefIdElement, XmlRoomList, XmlRuleList, XmlSessionList,
+                    XmlSolution, XmlSolutionClassList, XmlSolutionGroupList, XmlStudentList,
+                    XmlTeacherList, XmlTimetabling,
+                },
+            },
+        },
+        db::schema,
+        MIGRATIONS,
+    };
+
+    const SOLUTION: &str = "../resources/solution_ua_l1_p1-p2_l3-info_2023_060524_09_44_03.xml";
+
+    fn import(conn: &mut SqliteConnection, path: &Path) -> i32 {
+        let mut inserter = SolutionInserter::new(
+            conn,
+            &(
+                schema::solutions::filename.eq("solution.xml"),
+                schema::solutions::created_at.eq(&chrono::NaiveDateTime::default()),
+                schema::solutions::slot_duration_seconds.eq(60),
+            ),
+            ImportConfig::default().batch_size,
+            Arc::new(NoProgress),
+        )
+        .unwrap();
+        assert!(pipeline::extract_file(path, &ImportConfig::default(), &mut inserter).is_ok());
+        inserter.insert_all_into_db().unwrap();
+
+        return inserter.solution_id();
+    }
+
+    #[test]
+    fn should_import_the_export_as_the_same_solution() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.run_pending_migrations(MIGRATIONS).unwrap();
+
+        let solution_id = import(&mut conn, Path::new(SOLUTION));
+        let document = export_solution(&mut conn, solution_id).unwrap();
+        assert_eq!(document.solution.sessions.session.len(), 2134);
+        assert!(document
+            .solution
+            .sessions
+            .session
+            .iter()
+            .all(|session| session.starting_slot.is_some()));
+        let exported = to_xml(&document).unwrap();
+
+        let path = std::env::temp_dir().join(format!("export_{}.xml", std::process::id()));
+        fs::write(&path, &exported).unwrap();
+        let reimported_id = import(&mut conn, &path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            to_xml(&export_solution(&mut conn, reimported_id).unwrap()).unwrap(),
+            exported
+        );
+    }
+
+    #[test]
+    fn should_keep_a_room_without_capacity_without_capacity() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.run_pending_migrations(MIGRATIONS).unwrap();
+
+        let solution_id = import(&mut conn, Path::new(SOLUTION));
+        let updated = diesel::update(schema::rooms::table)
+            .filter(schema::rooms::solution_id.eq(solution_id))
+            .set(schema::rooms::capacity.eq(None::<i32>))
+            .execute(&mut conn)
+            .unwrap();
+        assert!(updated > 0);
+
+        let document = export_solution(&mut conn, solution_id).unwrap();
+        assert!(document
+            .rooms
+            .room
+            .iter()
+            .all(|room| room.capacity.is_none()));
+        assert!(!to_xml(&document).unwrap().contains("capacity"));
+    }
+
+    #[test]
+    fn should_fail_the_export_of_sessions_out_of_the_calendar() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.run_pending_migrations(MIGRATIONS).unwrap();
+
+        let solution_id = import(&mut conn, Path::new(SOLUTION));
+        let session_id: i32 = schema::sessions::table
+            .filter(schema::sessions::solution_id.eq(solution_id))
+            .select(schema::sessions::id)
+            .first(&mut conn)
+            .unwrap();
+        let (rank, class_id): (i32, String) = schema::sessions::table
+            .find(session_id)
+            .select((schema::sessions::rank, schema::sessions::class_id))
+            .first(&mut conn)
+            .unwrap();
+        diesel::update(schema::sessions::table.find(session_id))
+            .set(schema::sessions::starting_date.eq(chrono::NaiveDateTime::default()))
+            .execute(&mut conn)
+            .unwrap();
+
+        match export_solution(&mut conn, solution_id) {
+            Err(ExportError::UnplacedSessions(sessions)) => {
+                assert_eq!(sessions, vec![(rank, class_id)])
+            }
+            other => panic!("the export should fail, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    /// The file as the export would write it, before any normalisation
+    fn read_file(path: &Path, name: &str) -> XmlTimetabling {
+        let mut model = SolutionModel::default();
+        assert!(pipeline::extract_file(path, &ImportConfig::default(), &mut model).is_ok());
+
+        return XmlTimetabling {
+            xmlns_xsi: "http://www.w3.org/2001/XMLSchema-instance",
+            schema_location: "usp_timetabling_v1_2.xsd",
+            name: name.to_string(),
+            calendar: model.calendar.unwrap().element,
+            rooms: XmlRoomList {
+                room: model.rooms.into_iter().map(|e| e.element).collect(),
+            },
+            teachers: XmlTeacherList {
+                teacher: model.teachers.into_iter().map(|e| e.element).collect(),
+            },
+            courses: XmlCourseList {
+                course: model.courses.into_iter().map(|e| e.element).collect(),
+            },
+            students: XmlStudentList {
+                student: model.students.into_iter().map(|e| e.element).collect(),
+            },
+            rules: XmlRuleList {
+                rule: model.rules.into_iter().map(|e| e.element).collect(),
+            },
+            solution: XmlSolution {
+                groups: XmlSolutionGroupList {
+                    group: model
+                        .solution_groups
+                        .into_iter()
+                        .map(|e| e.element)
+                        .collect(),
+                },
+                classes: XmlSolutionClassList {
+                    class: model
+                        .solution_classes
+                        .into_iter()
+                        .map(|e| e.element)
+                        .collect(),
+                },
+                sessions: XmlSessionList {
+                    session: model.sessions.into_iter().map(|e| e.element).collect(),
+                },
+            },
+        };
+    }
+
+    // only the first element of an id is imported
+    fn dedup<T, K: Hash + Eq>(elements: &mut Vec<T>, key: impl Fn(&T) -> K) {
+        let mut seen = HashSet::new();
+        elements.retain(|element| seen.insert(key(element)));
+    }
+
+    fn sort_links(links: &mut [XmlRefIdElement<String>]) {
+        links.sort_by(|a, b| a.ref_id.cmp(&b.ref_id));
+    }
+
+    /// Removes the differences between a file and the export of its import: the elements whose
+    /// id is already taken are dropped, the links are exported in the order of their ids and an
+    /// empty list of links is left out
+    fn normalise(document: &mut XmlTimetabling) {
+        dedup(&mut document.rooms.room, |room| room.id.clone());
+        dedup(&mut document.teachers.teacher, |teacher| teacher.id.clone());
+        dedup(&mut document.courses.course, |course| course.id.clone());
+        dedup(&mut document.students.student, |student| student.id.clone());
+        dedup(&mut document.solution.groups.group, |group| {
+            group.id.clone()
+        });
+
+        for part in document
+            .courses
+            .course
+            .iter_mut()
+            .flat_map(|course| course.parts.iter_mut())
+        {
+            if let Some(rooms) = &mut part.allowed_rooms {
+                sort_links(&mut rooms.rooms);
+            }
+            if let Some(teachers) = &mut part.allowed_teachers {
+                teachers.teachers.sort_by(|a, b| a.ref_id.cmp(&b.ref_id));
+            }
+        }
+        for student in &mut document.students.student {
+            if let Some(courses) = &mut student.courses {
+                sort_links(&mut courses.courses);
+            }
+        }
+        for group in &mut document.solution.groups.group {
+            if let Some(students) = &mut group.students {
+                sort_links(&mut students.students);
+            }
+            if let Some(classes) = &mut group.classes {
+                sort_links(&mut classes.classes);
+            }
+        }
+        for class in &mut document.solution.classes.class {
+            class.groups = class.groups.take().filter(|g| !g.groups_id.is_empty());
+            class.teachers = class.teachers.take().filter(|t| !t.teachers_id.is_empty());
+            class.rooms = class.rooms.take().filter(|r| !r.rooms_id.is_empty());
+            if let Some(groups) = &mut class.groups {
+                sort_links(&mut groups.groups_id);
+            }
+            if let Some(teachers) = &mut class.teachers {
+                sort_links(&mut teachers.teachers_id);
+            }
+            if let Some(rooms) = &mut class.rooms {
+                sort_links(&mut rooms.rooms_id);
+            }
+        }
+    }
+
+    #[test]
+    fn should_export_the_file_the_solution_was_imported_from() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.run_pending_migrations(MIGRATIONS).unwrap();
+
+        let solution_id = import(&mut conn, Path::new(SOLUTION));
+        let mut exported = export_solution(&mut conn, solution_id).unwrap();
+        let mut file = read_file(Path::new(SOLUTION), &exported.name);
+
+        // the sample has duplicate teachers, the export does not
+        assert_eq!(file.teachers.teacher.len(), 186);
+        assert_eq!(exported.teachers.teacher.len(), 94);
+
+        normalise(&mut file);
+        normalise(&mut exported);
+        assert_eq!(to_xml(&exported).unwrap(), to_xml(&file).unwrap());
+    }
+}
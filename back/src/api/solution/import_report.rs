@@ -0,0 +1,248 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use crate::{db::model::ImportWarning, xml_parsing::reader::LineIndex};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Entity {
+    Room,
+    Teacher,
+    Course,
+    Part,
+    Class,
+    Student,
+    Group,
+    Session,
+}
+
+impl fmt::Display for Entity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Entity::Room => "room",
+            Entity::Teacher => "teacher",
+            Entity::Course => "course",
+            Entity::Part => "part",
+            Entity::Class => "class",
+            Entity::Student => "student",
+            Entity::Group => "group",
+            Entity::Session => "session",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarningKind {
+    SessionWithoutDate,
+    InvalidSessionDate,
+    InvalidCalendar,
+    InvalidAllowedSlots,
+    DuplicateId,
+    UnknownReference,
+    /// Something of the source that the application has no place for
+    UnsupportedElement,
+    /// A time of an ITC class whose days or weeks are not bitstrings
+    InvalidTime,
+    /// The times of an ITC subpart don't all have the length of its sessions
+    SeveralSessionLengths,
+}
+
+impl WarningKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WarningKind::SessionWithoutDate => "session_without_date",
+            WarningKind::InvalidSessionDate => "invalid_session_date",
+            WarningKind::InvalidCalendar => "invalid_calendar",
+            WarningKind::InvalidAllowedSlots => "invalid_allowed_slots",
+            WarningKind::DuplicateId => "duplicate_id",
+            WarningKind::UnknownReference => "unknown_reference",
+            WarningKind::UnsupportedElement => "unsupported_element",
+            WarningKind::InvalidTime => "invalid_time",
+            WarningKind::SeveralSessionLengths => "several_session_lengths",
+        }
+    }
+}
+
+/// A warning of the reading of a source that is not a file of the application, such as an ITC
+/// instance, sent with its elements
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceWarning {
+    pub kind: WarningKind,
+    pub subject: String,
+    pub detail: Option<String>,
+}
+
+struct Warning {
+    kind: WarningKind,
+    subject: String,
+    detail: Option<String>,
+    offset: Option<usize>,
+}
+
+struct Reference {
+    entity: Entity,
+    id: String,
+    referenced_by: String,
+    offset: Option<usize>,
+}
+
+/// Collects everything that is dropped or ignored while importing a solution.
+///
+/// References are only checked when the report is closed, since the file can
+/// reference an element before declaring it.
+pub struct ImportReport {
+    warnings: Vec<Warning>,
+    declared: HashMap<Entity, HashSet<String>>,
+    references: Vec<Reference>,
+    // byte offset of the element being imported
+    current_offset: Option<usize>,
+}
+
+impl ImportReport {
+    pub fn new() -> Self {
+        ImportReport {
+            warnings: Vec::new(),
+            declared: HashMap::new(),
+            references: Vec::new(),
+            current_offset: None,
+        }
+    }
+
+    /// Following warnings and references are located at this offset of the file
+    pub fn set_offset(&mut self, offset: usize) {
+        self.current_offset = Some(offset);
+    }
+
+    pub fn warn(&mut self, kind: WarningKind, subject: String, detail: Option<String>) {
+        self.warnings.push(Warning {
+            kind: kind,
+            subject: subject,
+            detail: detail,
+            offset: self.current_offset,
+        });
+    }
+
+    /// Registers the id of an element, returns false (and reports it) if it was already declared
+    pub fn declare(&mut self, entity: Entity, id: &str) -> bool {
+        let first_declaration = self
+            .declared
+            .entry(entity)
+            .or_default()
+            .insert(id.to_string());
+
+        if !first_declaration {
+            self.warn(WarningKind::DuplicateId, format!("{} {}", entity, id), None);
+        }
+
+        return first_declaration;
+    }
+
+    pub fn reference(&mut self, entity: Entity, id: &str, referenced_by: &str) {
+        self.references.push(Reference {
+            entity: entity,
+            id: id.to_string(),
+            referenced_by: referenced_by.to_string(),
+            offset: self.current_offset,
+        });
+    }
+
+    fn check_references(&mut self) {
+        let references = std::mem::take(&mut self.references);
+
+        references
+            .into_iter()
+            .filter(|reference| {
+                !self
+                    .declared
+                    .get(&reference.entity)
+                    .is_some_and(|ids| ids.contains(&reference.id))
+            })
+            .for_each(|reference| {
+                self.warnings.push(Warning {
+                    kind: WarningKind::UnknownReference,
+                    subject: format!("{} {}", reference.entity, reference.id),
+                    detail: Some(format!("referenced by {}", reference.referenced_by)),
+                    offset: reference.offset,
+                })
+            });
+    }
+
+    /// Checks the references and turns the report into rows of the import_warnings table
+    pub fn close(&mut self, given_solution_id: i32, line_index: &LineIndex) -> Vec<ImportWarning> {
+        self.check_references();
+
+        return self
+            .warnings
+            .drain(..)
+            .enumerate()
+            .map(|(index, warning)| {
+                let position = warning.offset.map(|offset| line_index.position(offset));
+
+                ImportWarning {
+                    solution_id: given_solution_id,
+                    id: index as i32,
+                    kind: warning.kind.as_str().to_string(),
+                    subject: warning.subject,
+                    detail: warning.detail,
+                    line_number: position.map(|p| p.line as i32),
+                    column_number: position.map(|p| p.column as i32),
+                }
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        api::solution::import_report::{Entity, ImportReport},
+        xml_parsing::reader::LineIndex,
+    };
+
+    #[test]
+    fn should_report_duplicate_ids() {
+        let mut report = ImportReport::new();
+
+        assert!(report.declare(Entity::Room, "R1"));
+        assert!(report.declare(Entity::Teacher, "R1"));
+        assert!(!report.declare(Entity::Room, "R1"));
+
+        let warnings = report.close(1, &LineIndex::from_reader("".as_bytes()).unwrap());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, "duplicate_id");
+        assert_eq!(warnings[0].subject, "room R1");
+    }
+
+    #[test]
+    fn should_report_every_unknown_reference_with_its_position() {
+        let line_index = LineIndex::from_reader("<a/>\n<b/>\n<c/>".as_bytes()).unwrap();
+        let mut report = ImportReport::new();
+
+        report.set_offset(0);
+        report.reference(Entity::Room, "R2", "class C1");
+        report.reference(Entity::Room, "R1", "class C1");
+        report.set_offset(5);
+        report.declare(Entity::Room, "R1");
+        report.set_offset(10);
+        report.reference(Entity::Room, "R2", "class C2");
+
+        let warnings = report.close(1, &line_index);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings.iter().all(|w| w.kind == "unknown_reference"));
+        assert!(warnings.iter().all(|w| w.subject == "room R2"));
+        assert_eq!(
+            warnings[0].detail.as_deref(),
+            Some("referenced by class C1")
+        );
+        assert_eq!(
+            (warnings[0].line_number, warnings[0].column_number),
+            (Some(1), Some(1))
+        );
+        assert_eq!(
+            (warnings[1].line_number, warnings[1].column_number),
+            (Some(3), Some(1))
+        );
+    }
+}
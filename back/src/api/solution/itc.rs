@@ -0,0 +1,533 @@
+use std::{collections::BTreeSet, fs::File, io::BufReader, path::Path};
+
+use quick_xml::events::Event;
+use serde::de::DeserializeOwned;
+
+use crate::api::import::config::ImportConfig;
+use crate::xml_parsing::{
+    limits::XmlLimits,
+    reader::{EventHandlingError, Router, XmlParser, XmlRouting, XmlRoutingError},
+};
+
+use super::calendar_handler::{bitstring_positions, compress_seq};
+use super::import_report::{Entity, SourceWarning, WarningKind};
+use super::itc_types::{
+    ItcCourse, ItcDistribution, ItcProblem, ItcRoom, ItcSolutionClass, ItcStudent, ItcSubpart,
+};
+use super::pipeline::{
+    self, ExtractFileError, ParsedElement, ParsingError, Sender, SolutionRecord,
+};
+use super::sink::SolutionSink;
+use super::xml_types::{
+    XmlAllowedRooms, XmlAllowedSlots, XmlCalendar, XmlCalendarMetaData, XmlClass, XmlClasses,
+    XmlConstraint, XmlConstraintParameter, XmlConstraintParameters, XmlCourse, XmlCourses,
+    XmlGroupClasses, XmlGroupStudents, XmlPart, XmlRefIdElement, XmlRoom, XmlRule, XmlSelector,
+    XmlSession, XmlSessionRooms, XmlSessionStartingSlot, XmlSolutionClass, XmlSolutionClassGroups,
+    XmlSolutionClassRooms, XmlSolutionGroup, XmlStudent,
+};
+
+/// The files of an ITC 2019 instance. They don't date the weeks, `year` and `starting_week`
+/// place the first one in the calendar.
+pub struct ItcFiles<'a> {
+    pub problem: &'a Path,
+    pub solution: Option<&'a Path>,
+    pub year: i32,
+    pub starting_week: u32,
+}
+
+fn routing<T: DeserializeOwned + 'static>(
+    route: Vec<&'static str>,
+    // offset of the file in what is imported, the solution comes after the problem
+    file_offset: usize,
+    into_records: fn(T) -> Vec<SolutionRecord>,
+) -> XmlRouting<'static, BufReader<File>, ParsingError, Sender> {
+    XmlRouting {
+        route: route,
+        handler: Box::from(
+            move |event: Event, parser: &mut XmlParser<BufReader<File>>, sender: &mut Sender| {
+                let route = parser.element_route().to_string();
+                let offset = file_offset + parser.element_offset();
+
+                return parser
+                    .handle_event(event, |element: T| {
+                        into_records(element).into_iter().try_for_each(|record| {
+                            sender
+                                .send(ParsedElement {
+                                    route: route.clone(),
+                                    offset: offset,
+                                    record: record,
+                                })
+                                .map_err(|_| ParsingError::InserterStopped)
+                        })
+                    })
+                    .map_err(ParsingError::HandlingError)?;
+            },
+        ),
+    }
+}
+
+fn root_error(error: XmlRoutingError<EventHandlingError>) -> ExtractFileError {
+    ExtractFileError::RoutingError(match error {
+        XmlRoutingError::HandlingError(e) => {
+            XmlRoutingError::HandlingError(ParsingError::HandlingError(e))
+        }
+        XmlRoutingError::RoutingError(e) => XmlRoutingError::RoutingError(e),
+        XmlRoutingError::LimitExceeded(e) => XmlRoutingError::LimitExceeded(e),
+        XmlRoutingError::DecodingError { offset, error } => XmlRoutingError::DecodingError {
+            offset: offset,
+            error: error,
+        },
+    })
+}
+
+fn walk_file(
+    file: &Path,
+    routings: Vec<XmlRouting<'static, BufReader<File>, ParsingError, Sender>>,
+    limits: XmlLimits,
+    sender: &mut Sender,
+) -> Result<(), ExtractFileError> {
+    let mut router = Router::new(routings).map_err(ExtractFileError::InvalidRoute)?;
+
+    return XmlParser::from_file(file)
+        .map_err(ExtractFileError::FileOpeningError)?
+        .with_limits(limits)
+        .walk_buffer(&mut router, sender)
+        .map_err(ExtractFileError::RoutingError);
+}
+
+/// Parses the problem then the solution, and sends what they describe as the elements of a
+/// solution of the application
+fn parse_files(
+    files: &ItcFiles,
+    limits: XmlLimits,
+    mut sender: Sender,
+) -> Result<(), ExtractFileError> {
+    // the calendar is in the attributes of the root, it is read alone and sent first so that the
+    // sessions can be dated. The file is then read again from its start, where the routes begin.
+    let mut parser = XmlParser::from_file(files.problem)
+        .map_err(ExtractFileError::FileOpeningError)?
+        .with_limits(limits);
+    let problem: ItcProblem = parser.read_root().map_err(root_error)?;
+
+    sender
+        .send(ParsedElement {
+            route: String::from("problem"),
+            offset: parser.element_offset(),
+            record: SolutionRecord::Calendar(problem.into_calendar(files)),
+        })
+        .map_err(|_| {
+            ExtractFileError::RoutingError(XmlRoutingError::HandlingError(
+                ParsingError::InserterStopped,
+            ))
+        })?;
+
+    walk_file(
+        files.problem,
+        vec![
+            routing(vec!["problem", "rooms", "room"], 0, ItcRoom::into_records),
+            routing(
+                vec!["problem", "courses", "course"],
+                0,
+                ItcCourse::into_records,
+            ),
+            routing(
+                vec!["problem", "distributions", "distribution"],
+                0,
+                |d: ItcDistribution| vec![SolutionRecord::Rule(d.into_rule())],
+            ),
+            routing(
+                vec!["problem", "students", "student"],
+                0,
+                |s: ItcStudent| vec![SolutionRecord::Student(s.into_student())],
+            ),
+        ],
+        limits,
+        &mut sender,
+    )?;
+
+    if let Some(solution) = files.solution {
+        let problem_bytes = std::fs::metadata(files.problem)
+            .map_err(|e| ExtractFileError::FileOpeningError(e.into()))?
+            .len() as usize;
+
+        walk_file(
+            solution,
+            vec![routing(
+                vec!["solution", "class"],
+                problem_bytes,
+                ItcSolutionClass::into_records,
+            )],
+            limits,
+            &mut sender,
+        )?;
+    }
+
+    return Ok(());
+}
+
+/// Gives the elements of an ITC 2019 instance to the sink, as `extract_file` does for a file of
+/// the application
+pub fn extract_itc_files<S: SolutionSink + ?Sized>(
+    files: &ItcFiles,
+    config: &ImportConfig,
+    sink: &mut S,
+) -> Result<(), ExtractFileError> {
+    return pipeline::extract_with(config, sink, |limits, sender| {
+        parse_files(files, limits, sender)
+    });
+}
+
+fn ref_ids(ids: impl IntoIterator<Item = String>) -> Vec<XmlRefIdElement<String>> {
+    ids.into_iter()
+        .map(|id| XmlRefIdElement { ref_id: id })
+        .collect()
+}
+
+impl ItcProblem {
+    fn into_calendar(self, files: &ItcFiles) -> XmlCalendar {
+        XmlCalendar {
+            starting_week: files.starting_week,
+            year: files.year,
+            weeks: XmlCalendarMetaData {
+                nr: self.nr_weeks,
+                sequence: None,
+            },
+            // the first day of the bitstrings is a monday
+            days: XmlCalendarMetaData {
+                nr: self.nr_days,
+                sequence: None,
+            },
+            slots: XmlCalendarMetaData {
+                nr: self.slots_per_day,
+                sequence: None,
+            },
+        }
+    }
+}
+
+impl ItcRoom {
+    /// The room, after a warning if it has travel times or unavailabilities, which are dropped
+    fn into_records(self) -> Vec<SolutionRecord> {
+        let mut records = Vec::new();
+
+        if !self.travels.is_empty() || !self.unavailabilities.is_empty() {
+            records.push(SolutionRecord::Warning(SourceWarning {
+                kind: WarningKind::UnsupportedElement,
+                subject: format!("{} {}", Entity::Room, self.id),
+                detail: Some(format!(
+                    "{} travel times and {} unavailabilities are not imported",
+                    self.travels.len(),
+                    self.unavailabilities.len()
+                )),
+            }));
+        }
+
+        records.push(SolutionRecord::Room(XmlRoom {
+            id: self.id,
+            capacity: Some(self.capacity),
+            label: None,
+        }));
+
+        return records;
+    }
+}
+
+impl ItcCourse {
+    /// The configurations are flattened, each subpart becomes a part labelled by its
+    /// configuration. What the parts can't keep of their subparts follows the course as warnings.
+    fn into_records(self) -> Vec<SolutionRecord> {
+        let mut warnings = Vec::new();
+        let course = XmlCourse {
+            id: self.id,
+            label: None,
+            parts: self
+                .configs
+                .into_iter()
+                .flat_map(|config| {
+                    let config_id = config.id;
+                    config
+                        .subparts
+                        .into_iter()
+                        .map(|subpart| subpart.into_part(&config_id, &mut warnings))
+                        .collect::<Vec<XmlPart>>()
+                })
+                .collect(),
+        };
+
+        return std::iter::once(SolutionRecord::Course(course))
+            .chain(warnings.into_iter().map(SolutionRecord::Warning))
+            .collect();
+    }
+}
+
+impl ItcSubpart {
+    /// The allowed slots and rooms of the part gather the options of its classes. A part has a
+    /// single session length, the one of the first time option, and the times that are not
+    /// bitstrings are ignored: both are warned about.
+    fn into_part(self, config_id: &str, warnings: &mut Vec<SourceWarning>) -> XmlPart {
+        let lengths: BTreeSet<i32> = self
+            .classes
+            .iter()
+            .flat_map(|class| class.times.iter().map(|time| time.length))
+            .collect();
+        let session_length = self
+            .classes
+            .iter()
+            .flat_map(|class| class.times.first())
+            .map(|time| time.length)
+            .next()
+            .unwrap_or(0);
+        if lengths.len() > 1 {
+            warnings.push(SourceWarning {
+                kind: WarningKind::SeveralSessionLengths,
+                subject: format!("{} {}", Entity::Part, self.id),
+                detail: Some(format!(
+                    "the times have the lengths {:?}, the sessions are given {}",
+                    lengths, session_length
+                )),
+            });
+        }
+
+        let mut daily_slots = BTreeSet::new();
+        let mut days = BTreeSet::new();
+        let mut weeks = BTreeSet::new();
+        let mut rooms = BTreeSet::new();
+
+        self.classes.iter().for_each(|class| {
+            class.times.iter().for_each(|time| {
+                match (
+                    bitstring_positions(&time.days),
+                    bitstring_positions(&time.weeks),
+                ) {
+                    (Ok(time_days), Ok(time_weeks)) => {
+                        daily_slots.insert(time.start as u32);
+                        days.extend(time_days);
+                        weeks.extend(time_weeks);
+                    }
+                    _ => warnings.push(SourceWarning {
+                        kind: WarningKind::InvalidTime,
+                        subject: format!("{} {}", Entity::Class, class.id),
+                        detail: Some(format!(
+                            "the days \"{}\" or the weeks \"{}\" are not bitstrings, the time is ignored",
+                            time.days, time.weeks
+                        )),
+                    }),
+                }
+            });
+            rooms.extend(class.rooms.iter().map(|room| room.id.clone()));
+        });
+
+        let seq = |values: BTreeSet<u32>| compress_seq(&values.into_iter().collect::<Vec<_>>());
+
+        XmlPart {
+            label: Some(format!("config {}", config_id)),
+            nr_session: None,
+            allowed_slots: XmlAllowedSlots {
+                session_lenght: session_length,
+                daily_slots: seq(daily_slots),
+                days: seq(days),
+                weeks: seq(weeks),
+            },
+            allowed_rooms: (!rooms.is_empty()).then(|| XmlAllowedRooms {
+                session_rooms: None,
+                rooms: ref_ids(rooms),
+            }),
+            allowed_teachers: None,
+            classes: XmlClasses {
+                max_head_count: self.classes.iter().filter_map(|class| class.limit).max(),
+                class: self
+                    .classes
+                    .into_iter()
+                    .map(|class| XmlClass {
+                        id: class.id,
+                        parent: class.parent,
+                        label: None,
+                    })
+                    .collect(),
+            },
+            id: self.id,
+        }
+    }
+}
+
+impl ItcDistribution {
+    /// Each class of the distribution is selected by its id, the penalty of a soft one is kept as
+    /// a parameter
+    fn into_rule(self) -> XmlRule {
+        XmlRule {
+            selectors: self
+                .classes
+                .into_iter()
+                .map(|class| XmlSelector {
+                    generator: String::from("(class, *)"),
+                    filters: Some(format!("class[id='{}']", class.id)),
+                })
+                .collect(),
+            constraint: XmlConstraint {
+                name: self.distribution_type,
+                constraint_type: Some(String::from(match self.required {
+                    true => "hard",
+                    false => "soft",
+                })),
+                parameters: self.penalty.map(|penalty| XmlConstraintParameters {
+                    parameters: vec![XmlConstraintParameter {
+                        name: String::from("penalty"),
+                        value: penalty.to_string(),
+                    }],
+                }),
+            },
+        }
+    }
+}
+
+impl ItcStudent {
+    fn into_student(self) -> XmlStudent {
+        XmlStudent {
+            id: self.id,
+            label: None,
+            courses: Some(XmlCourses {
+                courses: ref_ids(self.courses.into_iter().map(|course| course.id)),
+            }),
+        }
+    }
+}
+
+impl ItcSolutionClass {
+    /// A session for each day of each week of the class, its students become a group named after
+    /// it. Bitstrings that can't be read give a single session without date, which is reported.
+    fn into_records(self) -> Vec<SolutionRecord> {
+        let mut records = Vec::new();
+        let group_id = format!("class-{}", self.id);
+        let has_students = !self.students.is_empty();
+
+        if has_students {
+            records.push(SolutionRecord::SolutionGroup(XmlSolutionGroup {
+                id: group_id.clone(),
+                head_count: Some(self.students.len() as i32),
+                students: Some(XmlGroupStudents {
+                    students: ref_ids(self.students.into_iter().map(|student| student.id)),
+                }),
+                classes: Some(XmlGroupClasses {
+                    classes: ref_ids([self.id.clone()]),
+                }),
+            }));
+        }
+
+        records.push(SolutionRecord::SolutionClass(XmlSolutionClass {
+            ref_id: self.id.clone(),
+            groups: has_students.then(|| XmlSolutionClassGroups {
+                groups_id: ref_ids([group_id]),
+            }),
+            teachers: None,
+            rooms: self.room.clone().map(|room| XmlSolutionClassRooms {
+                rooms_id: ref_ids([room]),
+            }),
+        }));
+
+        let starting_slots: Vec<Option<XmlSessionStartingSlot>> = match (
+            bitstring_positions(&self.weeks),
+            bitstring_positions(&self.days),
+        ) {
+            (Ok(weeks), Ok(days)) => weeks
+                .into_iter()
+                .flat_map(|week| {
+                    days.iter().map(move |day| {
+                        Some(XmlSessionStartingSlot {
+                            daily_slot: self.start,
+                            day: *day,
+                            week: week,
+                        })
+                    })
+                })
+                .collect(),
+            _ => vec![None],
+        };
+
+        records.extend(
+            starting_slots
+                .into_iter()
+                .enumerate()
+                .map(|(index, starting_slot)| {
+                    SolutionRecord::Session(XmlSession {
+                        rank: index as i32 + 1,
+                        class: self.id.clone(),
+                        starting_slot: starting_slot,
+                        rooms: self.room.clone().map(|room| XmlSessionRooms {
+                            rooms_id: ref_ids([room]),
+                        }),
+                        teachers: None,
+                    })
+                }),
+        );
+
+        return records;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{extract_itc_files, ItcFiles, WarningKind};
+    use crate::api::{import::config::ImportConfig, solution::sink::SolutionModel};
+
+    const PROBLEM: &str = "../resources/itc2019_sample.xml";
+    const SOLUTION: &str = "../resources/itc2019_sample_solution.xml";
+
+    #[test]
+    fn should_extract_an_itc_instance_as_a_solution() {
+        let mut model = SolutionModel::default();
+        let files = ItcFiles {
+            problem: Path::new(PROBLEM),
+            solution: Some(Path::new(SOLUTION)),
+            year: 2024,
+            starting_week: 36,
+        };
+
+        assert!(extract_itc_files(&files, &ImportConfig::default(), &mut model).is_ok());
+
+        let calendar = model.calendar.unwrap();
+        assert_eq!(
+            (calendar.weeks.nr, calendar.days.nr, calendar.slots.nr),
+            (4, 7, 288)
+        );
+        assert_eq!(model.rooms.len(), 2);
+        // the travel times of the first room and the unavailability of the second are dropped
+        let warned: Vec<&str> = model
+            .warnings
+            .iter()
+            .filter(|warning| warning.element.kind == WarningKind::UnsupportedElement)
+            .map(|warning| warning.element.subject.as_str())
+            .collect();
+        assert_eq!(warned, vec!["room 1", "room 2"]);
+        assert_eq!(model.students.len(), 2);
+        assert_eq!(model.rules.len(), 2);
+        assert_eq!(
+            model.rules[1].constraint.constraint_type.as_deref(),
+            Some("soft")
+        );
+
+        let parts = &model.courses[0].parts;
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].allowed_slots.daily_slots, "96,120");
+        assert_eq!(parts[0].allowed_slots.days, "1-4");
+        assert_eq!(parts[1].allowed_slots.session_lenght, 12);
+        assert_eq!(parts[1].allowed_slots.weeks, "1-4");
+        assert_eq!(parts[1].classes.class.len(), 2);
+
+        assert_eq!(model.solution_classes.len(), 3);
+        assert_eq!(model.solution_groups.len(), 3);
+        // four weeks of two days, then two weeks of one day for each of the other classes
+        assert_eq!(model.sessions.len(), 12);
+        let slots: Vec<(u32, u32)> = model
+            .sessions
+            .iter()
+            .filter(|session| session.class == "2")
+            .map(|session| {
+                let slot = session.starting_slot.as_ref().unwrap();
+                (slot.week, slot.day)
+            })
+            .collect();
+        assert_eq!(slots, vec![(1, 5), (3, 5)]);
+    }
+}
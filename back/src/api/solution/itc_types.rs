@@ -0,0 +1,127 @@
+use serde::{de::IgnoredAny, Deserialize};
+
+/// The root element of an ITC 2019 problem, only its attributes are read
+#[derive(Deserialize, Debug, Clone)]
+pub struct ItcProblem {
+    #[serde(rename = "@nrDays")]
+    pub nr_days: i32,
+    #[serde(rename = "@slotsPerDay")]
+    pub slots_per_day: i32,
+    #[serde(rename = "@nrWeeks")]
+    pub nr_weeks: i32,
+}
+
+/// The travel times and the unavailabilities of the room have no table, they are only counted
+#[derive(Deserialize, Debug, Clone)]
+pub struct ItcRoom {
+    #[serde(rename = "@id")]
+    pub id: String,
+    #[serde(rename = "@capacity")]
+    pub capacity: i32,
+
+    #[serde(rename = "travel", default)]
+    pub travels: Vec<IgnoredAny>,
+    #[serde(rename = "unavailable", default)]
+    pub unavailabilities: Vec<IgnoredAny>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ItcCourse {
+    #[serde(rename = "@id")]
+    pub id: String,
+
+    #[serde(rename = "config", default)]
+    pub configs: Vec<ItcConfig>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ItcConfig {
+    #[serde(rename = "@id")]
+    pub id: String,
+
+    #[serde(rename = "subpart", default)]
+    pub subparts: Vec<ItcSubpart>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ItcSubpart {
+    #[serde(rename = "@id")]
+    pub id: String,
+
+    #[serde(rename = "class", default)]
+    pub classes: Vec<ItcClass>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ItcClass {
+    #[serde(rename = "@id")]
+    pub id: String,
+    #[serde(rename = "@limit")]
+    pub limit: Option<i32>,
+    #[serde(rename = "@parent")]
+    pub parent: Option<String>,
+
+    #[serde(rename = "room", default)]
+    pub rooms: Vec<ItcIdElement>,
+    #[serde(rename = "time", default)]
+    pub times: Vec<ItcTime>,
+}
+
+/// A time a class can be given, `days` and `weeks` are bitstrings
+#[derive(Deserialize, Debug, Clone)]
+pub struct ItcTime {
+    #[serde(rename = "@days")]
+    pub days: String,
+    #[serde(rename = "@start")]
+    pub start: u16,
+    #[serde(rename = "@length")]
+    pub length: i32,
+    #[serde(rename = "@weeks")]
+    pub weeks: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ItcDistribution {
+    #[serde(rename = "@type")]
+    pub distribution_type: String,
+    #[serde(rename = "@required", default)]
+    pub required: bool,
+    #[serde(rename = "@penalty")]
+    pub penalty: Option<i32>,
+
+    #[serde(rename = "class", default)]
+    pub classes: Vec<ItcIdElement>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ItcStudent {
+    #[serde(rename = "@id")]
+    pub id: String,
+
+    #[serde(rename = "course", default)]
+    pub courses: Vec<ItcIdElement>,
+}
+
+/// Where and when a class is given in a solution, with the students attending it
+#[derive(Deserialize, Debug, Clone)]
+pub struct ItcSolutionClass {
+    #[serde(rename = "@id")]
+    pub id: String,
+    #[serde(rename = "@days")]
+    pub days: String,
+    #[serde(rename = "@start")]
+    pub start: u16,
+    #[serde(rename = "@weeks")]
+    pub weeks: String,
+    #[serde(rename = "@room")]
+    pub room: Option<String>,
+
+    #[serde(rename = "student", default)]
+    pub students: Vec<ItcIdElement>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ItcIdElement {
+    #[serde(rename = "@id")]
+    pub id: String,
+}
@@ -0,0 +1,178 @@
+use std::{
+    fs::File,
+    io::BufReader,
+    path::Path,
+    sync::mpsc::{self, SyncSender},
+    thread,
+};
+
+use quick_xml::events::Event;
+use serde::de::DeserializeOwned;
+
+use crate::api::import::config::ImportConfig;
+use crate::xml_parsing::{
+    limits::XmlLimits,
+    reader::{EventHandlingError, Router, XmlParser, XmlRouting, XmlRoutingError},
+    route::RoutePatternError,
+};
+
+use super::import_report::SourceWarning;
+use super::sink::{self, SinkError, SolutionSink};
+use super::xml_types::{
+    XmlCalendar, XmlCourse, XmlRoom, XmlRule, XmlSession, XmlSolutionClass, XmlSolutionGroup,
+    XmlStudent, XmlTeacher,
+};
+
+/// An element of the file, deserialized by the parser thread for the sink thread
+pub enum SolutionRecord {
+    Teacher(XmlTeacher),
+    Student(XmlStudent),
+    Course(XmlCourse),
+    Room(XmlRoom),
+    Rule(XmlRule),
+    Session(XmlSession),
+    SolutionGroup(XmlSolutionGroup),
+    SolutionClass(XmlSolutionClass),
+    Calendar(XmlCalendar),
+    Warning(SourceWarning),
+}
+
+/// A record with where it was found, to report its progress and its warnings
+pub struct ParsedElement {
+    pub route: String,
+    pub offset: usize,
+    pub record: SolutionRecord,
+}
+
+pub enum ParsingError {
+    HandlingError(EventHandlingError),
+    /// The sink stopped receiving the elements
+    InserterStopped,
+}
+
+pub enum ExtractFileError {
+    RoutingError(XmlRoutingError<ParsingError>),
+    FileOpeningError(quick_xml::Error),
+    InvalidRoute(RoutePatternError),
+    ParserPanicked,
+    /// The sink could not take an element, the parsing was stopped
+    SinkError(SinkError),
+}
+
+pub type Sender = SyncSender<ParsedElement>;
+
+fn routing<T: DeserializeOwned + 'static>(
+    route: Vec<&'static str>,
+    into_record: fn(T) -> SolutionRecord,
+) -> XmlRouting<'static, BufReader<File>, ParsingError, Sender> {
+    XmlRouting {
+        route: route,
+        handler: Box::from(
+            move |event: Event, parser: &mut XmlParser<BufReader<File>>, sender: &mut Sender| {
+                let route = parser.element_route().to_string();
+                let offset = parser.element_offset();
+
+                return parser
+                    .handle_event(event, |element: T| {
+                        sender
+                            .send(ParsedElement {
+                                route: route.clone(),
+                                offset: offset,
+                                record: into_record(element),
+                            })
+                            .map_err(|_| ParsingError::InserterStopped)
+                    })
+                    .map_err(ParsingError::HandlingError)?;
+            },
+        ),
+    }
+}
+
+/// Parses the file and sends its elements to the sink, blocking while the channel is full.
+/// Dropping the receiver stops the parsing.
+fn parse_file(file: &Path, limits: XmlLimits, mut sender: Sender) -> Result<(), ExtractFileError> {
+    let mut router = Router::new(vec![
+        routing(
+            vec!["timetabling", "teachers", "teacher"],
+            SolutionRecord::Teacher,
+        ),
+        routing(
+            vec!["timetabling", "students", "student"],
+            SolutionRecord::Student,
+        ),
+        routing(
+            vec!["timetabling", "courses", "course"],
+            SolutionRecord::Course,
+        ),
+        routing(vec!["timetabling", "rooms", "room"], SolutionRecord::Room),
+        routing(vec!["timetabling", "rules", "rule"], SolutionRecord::Rule),
+        routing(
+            vec!["timetabling", "solution", "sessions", "session"],
+            SolutionRecord::Session,
+        ),
+        routing(
+            vec!["timetabling", "solution", "groups", "group"],
+            SolutionRecord::SolutionGroup,
+        ),
+        routing(
+            vec!["timetabling", "solution", "classes", "class"],
+            SolutionRecord::SolutionClass,
+        ),
+        routing(vec!["timetabling", "calendar"], SolutionRecord::Calendar),
+    ])
+    .map_err(ExtractFileError::InvalidRoute)?;
+
+    let mut parser = XmlParser::from_file(file)
+        .map_err(ExtractFileError::FileOpeningError)?
+        .with_limits(limits);
+
+    parser
+        .walk_buffer(&mut router, &mut sender)
+        .map_err(ExtractFileError::RoutingError)?;
+
+    return Ok(());
+}
+
+/// Gives the elements of the file to the sink
+pub fn extract_file<S: SolutionSink + ?Sized>(
+    file: &Path,
+    config: &ImportConfig,
+    sink: &mut S,
+) -> Result<(), ExtractFileError> {
+    return extract_with(config, sink, |limits, sender| {
+        parse_file(file, limits, sender)
+    });
+}
+
+/// Gives what `parse` sends to the sink, `parse` runs on its own thread while this one runs the
+/// sink. The bounded channel between them keeps the memory flat when the sink is slower.
+/// The first error of the sink stops the parsing and is returned.
+pub fn extract_with<S, P>(
+    config: &ImportConfig,
+    sink: &mut S,
+    parse: P,
+) -> Result<(), ExtractFileError>
+where
+    S: SolutionSink + ?Sized,
+    P: FnOnce(XmlLimits, Sender) -> Result<(), ExtractFileError> + Send,
+{
+    let limits = config.xml_limits;
+
+    return thread::scope(|scope| {
+        let (sender, receiver) = mpsc::sync_channel(config.channel_capacity);
+        let parser = scope.spawn(move || parse(limits, sender));
+
+        // ends when the parser is done, or has failed
+        let added = receiver
+            .into_iter()
+            .try_for_each(|element| sink::add_element(sink, element));
+        // the receiver is dropped, a parser still running fails on its next element
+
+        let parsed = parser
+            .join()
+            .map_err(|_| ExtractFileError::ParserPanicked)?;
+        added.map_err(ExtractFileError::SinkError)?;
+
+        return parsed;
+    });
+}
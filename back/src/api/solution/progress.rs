@@ -0,0 +1,22 @@
+/// Receives the progress of an import, the calls come from the importing thread
+pub trait ImportProgress: Send + Sync {
+    /// A new element of the file is read, `offset` is its position in bytes
+    fn on_element(&self, route: &str, offset: usize);
+    /// The buffered rows of `table` are being inserted
+    fn on_table_insert(&self, table: &'static str);
+    fn on_rows_inserted(&self, table: &'static str, rows: usize);
+    /// Every buffered row has been inserted
+    fn on_flush(&self);
+}
+
+/// For the imports nobody follows, such as the ones of the tests
+#[cfg(test)]
+pub struct NoProgress;
+
+#[cfg(test)]
+impl ImportProgress for NoProgress {
+    fn on_element(&self, _route: &str, _offset: usize) {}
+    fn on_table_insert(&self, _table: &'static str) {}
+    fn on_rows_inserted(&self, _table: &'static str, _rows: usize) {}
+    fn on_flush(&self) {}
+}
@@ -1,37 +1,56 @@
+use std::sync::Arc;
+
+use chrono::NaiveDateTime;
 use diesel::{self, ExpressionMethods, QueryResult, RunQueryDsl, SqliteConnection};
 use uuid::Uuid;
 
-use crate::db::{
-    model::{
-        last_insert_rowid, Class, ClassGroupOwn, ClassRoomOwn, ClassTeacherOwn, Course,
-        InsertSolution, Part, Room, Session, SolutionGroupOwn, Student, StudentGroupOwn, Teacher,
+use crate::{
+    db::{
+        model::{
+            last_insert_rowid, Calendar, Class, ClassGroupOwn, ClassRoomOwn, ClassTeacherOwn,
+            ConstraintParameter, Course, ImportWarning, InsertSolution, Part, PartAllowedDailySlot,
+            PartAllowedDay, PartAllowedRoom, PartAllowedTeacher, PartAllowedWeek, Room, Rule,
+            RuleSelector, Session, SolutionGroupOwn, Student, StudentCourseOwn, StudentGroupOwn,
+            Teacher,
+        },
+        schema::{self},
     },
-    schema::{self},
+    xml_parsing::reader::LineIndex,
 };
 
 use super::{
-    calendar_handler::CalendarHandler,
+    calendar_handler::{expand_str_seq, DomainLimits},
+    checker::SolutionChecker,
+    import_report::SourceWarning,
+    progress::ImportProgress,
+    sink::{SinkResult, SolutionSink},
     xml_types::{
-        XmlCalendar, XmlClass, XmlCourse, XmlGroupClasses, XmlGroupStudents, XmlPart, XmlRoom,
-        XmlSession, XmlSolutionClass, XmlSolutionClassRooms, XmlSolutionClassTeachers,
-        XmlSolutionGroup, XmlStudent, XmlTeacher,
+        XmlAllowedRooms, XmlAllowedSlots, XmlAllowedTeachers, XmlCalendar, XmlClass,
+        XmlConstraintParameters, XmlCourse, XmlCourses, XmlGroupClasses, XmlGroupStudents, XmlPart,
+        XmlRoom, XmlRule, XmlSelector, XmlSession, XmlSolutionClass, XmlSolutionClassGroups,
+        XmlSolutionClassRooms, XmlSolutionClassTeachers, XmlSolutionGroup, XmlStudent, XmlTeacher,
     },
 };
 
-use super::buffer_handler::BufferHandler;
+use super::buffer_handler::{BufferHandler, SessionLink, SessionRoomLink, SessionTeacherLink};
 
 pub struct SolutionInserter<'a> {
     conn: &'a mut SqliteConnection,
     solution_id: i32,
+    // rules have no id in the file, they are numbered in reading order
+    rules_count: i32,
 
+    checker: SolutionChecker,
+    progress: Arc<dyn ImportProgress>,
     buffer_handler: BufferHandler,
-    calendar_data_handler: CalendarHandler,
 }
 
 impl<'a> SolutionInserter<'a> {
     pub fn new(
         conn: &'a mut SqliteConnection,
         solution: &InsertSolution,
+        batch_size: usize,
+        progress: Arc<dyn ImportProgress>,
     ) -> Result<Self, diesel::result::Error> {
         diesel::insert_into(schema::solutions::table)
             .values(solution)
@@ -42,9 +61,11 @@ impl<'a> SolutionInserter<'a> {
         return Ok(SolutionInserter {
             conn: conn,
             solution_id: solution_id,
+            rules_count: 0,
 
-            buffer_handler: BufferHandler::new(),
-            calendar_data_handler: CalendarHandler::new(),
+            checker: SolutionChecker::new(),
+            progress: progress.clone(),
+            buffer_handler: BufferHandler::new(solution_id, batch_size, progress),
         });
     }
 
@@ -56,135 +77,265 @@ impl<'a> SolutionInserter<'a> {
         self.buffer_handler.insert_all_into_db(self.conn)
     }
 
-    pub fn add_calendar(&mut self, xml_calendar: XmlCalendar) -> QueryResult<usize> {
-        self.calendar_data_handler
-            .register_xml_calendar(&xml_calendar);
+    /// Checks the references between the elements of the file and queues the warnings,
+    /// to be called once the whole file has been read
+    pub fn close_report(&mut self, line_index: &LineIndex) -> QueryResult<Vec<ImportWarning>> {
+        let warnings = self.checker.close(self.solution_id, line_index);
+
+        self.buffer_handler
+            .extend(self.conn, warnings.iter().cloned())?;
+
+        return Ok(warnings);
+    }
+
+    fn update_calendar(&mut self, xml_calendar: XmlCalendar) -> QueryResult<usize> {
+        self.checker.check_calendar(&xml_calendar);
+        let calendar = self.checker.calendar();
 
         diesel::update(schema::solutions::table)
             .filter(schema::solutions::id.eq(self.solution_id))
             .set((
-                schema::solutions::slot_duration
-                    .eq(self.calendar_data_handler.slot_duration as i32),
-                schema::solutions::calendar_start.eq(self.calendar_data_handler.starting_date),
+                schema::solutions::slot_duration_seconds.eq(calendar.slot_duration_seconds as i32),
+                schema::solutions::calendar_start.eq(calendar.starting_date),
             ))
+            .execute(self.conn)?;
+
+        // kept as in the file, to give the sessions their slots back on export
+        diesel::replace_into(schema::calendars::table)
+            .values(xml_calendar.into_db_entry(self.solution_id))
             .execute(self.conn)
     }
+}
+
+impl SolutionSink for SolutionInserter<'_> {
+    /// To be called before adding each element of the file, `offset` locates the warnings of
+    /// the element, see `XmlParser::element_route` and `XmlParser::element_offset`
+    fn start_element(&mut self, route: &str, offset: usize) {
+        self.checker.set_offset(offset);
+        self.progress.on_element(route, offset);
+    }
+
+    fn add_calendar(&mut self, calendar: XmlCalendar) -> SinkResult {
+        self.update_calendar(calendar)?;
+
+        return Ok(());
+    }
+
+    fn add_student(&mut self, student: XmlStudent) -> SinkResult {
+        if !self.checker.check_student(&student) {
+            return Ok(());
+        }
 
-    pub fn add_student(&mut self, student: XmlStudent) {
         self.buffer_handler
-            .students_to_insert
-            .push(student.into_db_entry(self.solution_id));
+            .push(self.conn, student.to_db_entry(self.solution_id))?;
 
-        self.buffer_handler.on_add_callback(self.conn);
+        if let Some(courses) = student.courses {
+            self.buffer_handler.extend(
+                self.conn,
+                courses.into_db_entry(self.solution_id, &student.id),
+            )?;
+        }
+
+        return Ok(());
     }
 
-    pub fn add_teacher(&mut self, teacher: XmlTeacher) {
+    fn add_teacher(&mut self, teacher: XmlTeacher) -> SinkResult {
+        if !self.checker.check_teacher(&teacher) {
+            return Ok(());
+        }
+
         self.buffer_handler
-            .teachers_to_insert
-            .push(teacher.into_db_entry(self.solution_id));
-        self.buffer_handler.on_add_callback(self.conn);
+            .push(self.conn, teacher.into_db_entry(self.solution_id))?;
+
+        return Ok(());
     }
 
-    pub fn add_room(&mut self, room: XmlRoom) {
+    fn add_room(&mut self, room: XmlRoom) -> SinkResult {
+        if !self.checker.check_room(&room) {
+            return Ok(());
+        }
+
         self.buffer_handler
-            .rooms_to_insert
-            .push(room.into_db_entry(self.solution_id));
-        self.buffer_handler.on_add_callback(self.conn);
+            .push(self.conn, room.into_db_entry(self.solution_id))?;
+
+        return Ok(());
     }
 
-    pub fn add_solution_group(&mut self, group: XmlSolutionGroup) {
+    fn add_solution_group(&mut self, group: XmlSolutionGroup) -> SinkResult {
+        if !self.checker.check_solution_group(&group) {
+            return Ok(());
+        }
+
         self.buffer_handler
-            .solution_groups_to_insert
-            .push(group.into_db_entry(self.solution_id));
+            .push(self.conn, group.to_db_entry(self.solution_id))?;
 
-        group.classes.map(|classes| {
-            self.buffer_handler
-                .classes_groups_to_insert
-                .extend(classes.into_db_entry(self.solution_id, &group.id));
-            self.buffer_handler.on_add_callback(self.conn);
-        });
-        group.students.map(|students| {
-            self.buffer_handler
-                .students_groups_to_insert
-                .extend(students.into_db_entry(self.solution_id, &group.id));
-            self.buffer_handler.on_add_callback(self.conn);
-        });
+        if let Some(classes) = group.classes {
+            self.buffer_handler.extend(
+                self.conn,
+                classes.into_db_entry(self.solution_id, &group.id),
+            )?;
+        }
+        if let Some(students) = group.students {
+            self.buffer_handler.extend(
+                self.conn,
+                students.into_db_entry(self.solution_id, &group.id),
+            )?;
+        }
+
+        return Ok(());
     }
 
-    pub fn add_solution_class(&mut self, class: XmlSolutionClass) {
-        class.teachers.map(|teachers| {
-            self.buffer_handler
-                .classes_teachers_to_insert
-                .extend(teachers.into_db_entry(self.solution_id, &class.ref_id));
-            self.buffer_handler.on_add_callback(self.conn);
-        });
+    fn add_solution_class(&mut self, class: XmlSolutionClass) -> SinkResult {
+        self.checker.check_solution_class(&class);
 
-        class.rooms.map(|rooms| {
-            self.buffer_handler
-                .classes_rooms_to_insert
-                .extend(rooms.into_db_entry(self.solution_id, &class.ref_id));
-            self.buffer_handler.on_add_callback(self.conn);
-        });
+        if let Some(groups) = class.groups {
+            self.buffer_handler.extend(
+                self.conn,
+                groups.into_db_entry(self.solution_id, &class.ref_id),
+            )?;
+        }
+        if let Some(teachers) = class.teachers {
+            self.buffer_handler.extend(
+                self.conn,
+                teachers.into_db_entry(self.solution_id, &class.ref_id),
+            )?;
+        }
+        if let Some(rooms) = class.rooms {
+            self.buffer_handler.extend(
+                self.conn,
+                rooms.into_db_entry(self.solution_id, &class.ref_id),
+            )?;
+        }
+
+        return Ok(());
     }
 
-    pub fn add_session(&mut self, session: XmlSession) -> Result<(), String> {
-        session
-            .into_db_entry(self.solution_id, &self.calendar_data_handler)
-            .map(|s| self.buffer_handler.sessions_to_insert.push(s))?;
-
-        session.teachers.map(|teachs| {
-            teachs.teachers_id.into_iter().for_each(|t| {
-                let query = format!(
-                    r#"
-                        (SELECT id from sessions WHERE rank = {} AND class_id = "{}" AND solution_id = {}), "{}", "{}"
-                    "#, session.rank, session.class.clone(), self.solution_id, t.ref_id, self.solution_id
-                );
-                // debug!("query for session_teacher: {}", query);
-                self.buffer_handler.sessions_teachers_to_insert_queries.push(query);
-                self.buffer_handler.on_add_callback(self.conn);
-            })
-        });
+    /// Sessions that can't be placed in the calendar are skipped and reported
+    fn add_session(&mut self, session: XmlSession) -> SinkResult {
+        let date = match self.checker.check_session(&session) {
+            Some(date) => date,
+            None => return Ok(()),
+        };
 
-        session.rooms.map(|s_rooms| {
-            s_rooms.rooms_id.into_iter().for_each(|r| {
-                let query = format!(
-                    r#"
-                        (SELECT id from sessions WHERE rank = {} AND class_id = "{}" AND solution_id = {}), "{}", "{}"
-                    "#, session.rank, session.class.clone(), self.solution_id, r.ref_id, self.solution_id
-                );
+        self.buffer_handler
+            .push(self.conn, session.to_db_entry(self.solution_id, date))?;
+
+        if let Some(teachs) = session.teachers {
+            for t in teachs.teachers_id {
+                self.buffer_handler.push(
+                    self.conn,
+                    SessionTeacherLink(SessionLink {
+                        class_id: session.class.clone(),
+                        rank: session.rank,
+                        ref_id: t.ref_id,
+                    }),
+                )?;
+            }
+        }
 
-                self.buffer_handler.sessions_rooms_to_insert_queries.push(query);
+        if let Some(s_rooms) = session.rooms {
+            for r in s_rooms.rooms_id {
+                self.buffer_handler.push(
+                    self.conn,
+                    SessionRoomLink(SessionLink {
+                        class_id: session.class.clone(),
+                        rank: session.rank,
+                        ref_id: r.ref_id,
+                    }),
+                )?;
+            }
+        }
 
-                self.buffer_handler.on_add_callback(self.conn);
-            })
-        });
+        return Ok(());
+    }
+
+    /// The duplicate parts and classes of the course are skipped and reported
+    fn add_course(&mut self, mut course: XmlCourse) -> SinkResult {
+        if !self.checker.check_course(&mut course) {
+            return Ok(());
+        }
+
+        self.buffer_handler
+            .push(self.conn, course.to_db_entry(self.solution_id))?;
 
-        Ok(())
+        let solution_id = self.solution_id;
+        let limits = self.checker.calendar().domain_limits();
+
+        for part in course.parts {
+            self.buffer_handler
+                .push(self.conn, part.to_db_entry(solution_id, &course.id))?;
+
+            for c in part.classes.class {
+                self.buffer_handler
+                    .push(self.conn, c.into_db_entry(solution_id, &part.id))?;
+            }
+
+            // the invalid domains are reported by the checker
+            if let Ok((daily_slots, days, weeks)) =
+                part.allowed_slots
+                    .to_db_entry(solution_id, &part.id, limits)
+            {
+                self.buffer_handler.extend(self.conn, daily_slots)?;
+                self.buffer_handler.extend(self.conn, days)?;
+                self.buffer_handler.extend(self.conn, weeks)?;
+            }
+
+            if let Some(rooms) = part.allowed_rooms {
+                self.buffer_handler
+                    .extend(self.conn, rooms.into_db_entry(solution_id, &part.id))?;
+            }
+            if let Some(teachers) = part.allowed_teachers {
+                self.buffer_handler
+                    .extend(self.conn, teachers.into_db_entry(solution_id, &part.id))?;
+            }
+        }
+
+        return Ok(());
     }
 
-    pub fn add_course(&mut self, course: XmlCourse) {
+    fn add_rule(&mut self, rule: XmlRule) -> SinkResult {
+        let rule_id = self.rules_count;
+        self.rules_count += 1;
+
         self.buffer_handler
-            .courses_to_insert
-            .push(course.into_db_entry(self.solution_id));
+            .push(self.conn, rule.to_db_entry(self.solution_id, rule_id))?;
 
         let solution_id = self.solution_id;
 
-        course.parts.into_iter().for_each(|part| {
+        for (rank, selector) in rule.selectors.into_iter().enumerate() {
+            self.buffer_handler.push(
+                self.conn,
+                selector.into_db_entry(solution_id, rule_id, rank as i32),
+            )?;
+        }
+
+        if let Some(parameters) = rule.constraint.parameters {
             self.buffer_handler
-                .parts_to_insert
-                .push(part.into_db_entry(solution_id, &course.id));
-
-            self.buffer_handler.on_add_callback(self.conn);
-
-            part.classes
-                .class
-                .into_iter()
-                .map(|c| c.into_db_entry(solution_id, &part.id))
-                .for_each(|c| {
-                    self.buffer_handler.classes_to_insert.push(c);
-                    self.buffer_handler.on_add_callback(self.conn);
-                });
-        });
+                .extend(self.conn, parameters.into_db_entry(solution_id, rule_id))?;
+        }
+
+        return Ok(());
+    }
+
+    fn add_warning(&mut self, warning: SourceWarning) -> SinkResult {
+        self.checker.add_source_warning(warning);
+
+        return Ok(());
+    }
+}
+
+impl XmlCalendar {
+    fn into_db_entry(self, given_solution_id: i32) -> Calendar {
+        Calendar {
+            solution_id: given_solution_id,
+            year: self.year,
+            starting_week: self.starting_week as i32,
+            weeks_nr: self.weeks.nr,
+            weeks_sequence: self.weeks.sequence,
+            days_nr: self.days.nr,
+            days_sequence: self.days.sequence,
+            slots_nr: self.slots.nr,
+        }
     }
 }
 
@@ -194,6 +345,8 @@ impl XmlClass {
             solution_id: given_solution_id,
             id: self.id,
             part_id: given_part_id.to_string(),
+            parent_id: self.parent,
+            label: self.label,
         }
     }
 }
@@ -220,7 +373,7 @@ impl XmlRoom {
 }
 
 impl XmlCourse {
-    fn into_db_entry(&self, given_solution_id: i32) -> Course {
+    fn to_db_entry(&self, given_solution_id: i32) -> Course {
         Course {
             solution_id: given_solution_id,
             id: self.id.clone(),
@@ -230,31 +383,126 @@ impl XmlCourse {
 }
 
 impl XmlPart {
-    fn into_db_entry(&self, given_solution_id: i32, given_course_id: &str) -> Part {
+    fn to_db_entry(&self, given_solution_id: i32, given_course_id: &str) -> Part {
         Part {
             solution_id: given_solution_id,
             id: self.id.clone(),
             course_id: given_course_id.to_string(),
             session_length: self.allowed_slots.session_lenght,
-            session_teachers: None,
-            session_rooms: None,
+            session_teachers: self
+                .allowed_teachers
+                .as_ref()
+                .and_then(|t| t.session_teachers),
+            session_rooms: self
+                .allowed_rooms
+                .as_ref()
+                .and_then(|r| r.session_rooms.clone()),
             label: self.label.clone(),
             max_head_count: self.classes.max_head_count,
-            nr_session: None,
+            nr_session: self.nr_session,
         }
     }
 }
 
+// the daily slots, the days and the weeks allowed to a part
+type AllowedSlots = (
+    Vec<PartAllowedDailySlot>,
+    Vec<PartAllowedDay>,
+    Vec<PartAllowedWeek>,
+);
+
+impl XmlAllowedSlots {
+    /// Fails on a domain that is not a sequence of values of the calendar
+    fn to_db_entry(
+        &self,
+        given_solution_id: i32,
+        given_part_id: &str,
+        limits: DomainLimits,
+    ) -> Result<AllowedSlots, ()> {
+        let daily_slots = expand_str_seq(&self.daily_slots, limits.daily_slot)?
+            .into_iter()
+            .map(|slot| PartAllowedDailySlot {
+                solution_id: given_solution_id,
+                part_id: given_part_id.to_string(),
+                daily_slot: slot as i32,
+            })
+            .collect();
+
+        let days = expand_str_seq(&self.days, limits.day)?
+            .into_iter()
+            .map(|day| PartAllowedDay {
+                solution_id: given_solution_id,
+                part_id: given_part_id.to_string(),
+                day: day as i32,
+            })
+            .collect();
+
+        let weeks = expand_str_seq(&self.weeks, limits.week)?
+            .into_iter()
+            .map(|week| PartAllowedWeek {
+                solution_id: given_solution_id,
+                part_id: given_part_id.to_string(),
+                week: week as i32,
+            })
+            .collect();
+
+        return Ok((daily_slots, days, weeks));
+    }
+}
+
+impl XmlAllowedRooms {
+    fn into_db_entry(
+        self,
+        given_solution_id: i32,
+        given_part_id: &str,
+    ) -> impl Iterator<Item = PartAllowedRoom> + '_ {
+        self.rooms.into_iter().map(move |r| PartAllowedRoom {
+            solution_id: given_solution_id,
+            part_id: given_part_id.to_string(),
+            room_id: r.ref_id,
+        })
+    }
+}
+
+impl XmlAllowedTeachers {
+    fn into_db_entry(
+        self,
+        given_solution_id: i32,
+        given_part_id: &str,
+    ) -> impl Iterator<Item = PartAllowedTeacher> + '_ {
+        self.teachers.into_iter().map(move |t| PartAllowedTeacher {
+            solution_id: given_solution_id,
+            part_id: given_part_id.to_string(),
+            teacher_id: t.ref_id,
+            nr_sessions: t.nr_sessions,
+        })
+    }
+}
+
 impl XmlStudent {
-    fn into_db_entry(self, given_solution_id: i32) -> Student {
+    fn to_db_entry(&self, given_solution_id: i32) -> Student {
         Student {
             solution_id: given_solution_id,
-            id: self.id,
-            label: self.label,
+            id: self.id.clone(),
+            label: self.label.clone(),
         }
     }
 }
 
+impl XmlCourses {
+    fn into_db_entry(
+        self,
+        given_solution_id: i32,
+        given_student_id: &str,
+    ) -> impl Iterator<Item = StudentCourseOwn> + '_ {
+        self.courses.into_iter().map(move |c| StudentCourseOwn {
+            solution_id: given_solution_id,
+            student_id: given_student_id.to_string(),
+            course_id: c.ref_id,
+        })
+    }
+}
+
 impl XmlGroupClasses {
     fn into_db_entry(
         self,
@@ -284,10 +532,11 @@ impl XmlGroupStudents {
 }
 
 impl XmlSolutionGroup {
-    fn into_db_entry(&self, given_solution_id: i32) -> SolutionGroupOwn {
+    fn to_db_entry(&self, given_solution_id: i32) -> SolutionGroupOwn {
         SolutionGroupOwn {
             id: self.id.to_owned(),
             solution_id: given_solution_id,
+            head_count: self.head_count,
         }
     }
 }
@@ -305,6 +554,20 @@ impl XmlSolutionClassRooms {
         })
     }
 }
+impl XmlSolutionClassGroups {
+    fn into_db_entry(
+        self,
+        given_solution_id: i32,
+        given_class_id: &str,
+    ) -> impl Iterator<Item = ClassGroupOwn> + '_ {
+        self.groups_id.into_iter().map(move |g| ClassGroupOwn {
+            class_id: given_class_id.to_string(),
+            solution_id: given_solution_id,
+            group_id: g.ref_id,
+        })
+    }
+}
+
 impl XmlSolutionClassTeachers {
     fn into_db_entry(
         self,
@@ -320,30 +583,60 @@ impl XmlSolutionClassTeachers {
 }
 
 impl XmlSession {
+    fn to_db_entry(&self, given_solution_id: i32, given_date: NaiveDateTime) -> Session {
+        Session {
+            solution_id: given_solution_id,
+            uuid: Uuid::new_v4().to_string(),
+            class_id: self.class.to_string(),
+            rank: self.rank,
+            starting_date: given_date,
+        }
+    }
+}
+
+impl XmlRule {
+    fn to_db_entry(&self, given_solution_id: i32, given_rule_id: i32) -> Rule {
+        Rule {
+            solution_id: given_solution_id,
+            id: given_rule_id,
+            constraint_name: self.constraint.name.clone(),
+            constraint_type: self.constraint.constraint_type.clone(),
+        }
+    }
+}
+
+impl XmlSelector {
     fn into_db_entry(
-        &self,
+        self,
         given_solution_id: i32,
-        calendar_handler: &CalendarHandler,
-    ) -> Result<Session, String> {
-        if self.starting_slot.is_none() {
-            return Err(String::from("This session doesn't have any date."));
+        given_rule_id: i32,
+        given_rank: i32,
+    ) -> RuleSelector {
+        RuleSelector {
+            solution_id: given_solution_id,
+            rule_id: given_rule_id,
+            rank: given_rank,
+            generator: self.generator,
+            filters: self.filters,
         }
+    }
+}
 
-        let starting_slot = self.starting_slot.as_ref().unwrap();
-
-        calendar_handler
-            .extract_session_date(
-                starting_slot.daily_slot,
-                starting_slot.week,
-                starting_slot.day,
-            )
-            .map(|date| Session {
+impl XmlConstraintParameters {
+    fn into_db_entry(
+        self,
+        given_solution_id: i32,
+        given_rule_id: i32,
+    ) -> impl Iterator<Item = ConstraintParameter> {
+        self.parameters
+            .into_iter()
+            .enumerate()
+            .map(move |(rank, p)| ConstraintParameter {
                 solution_id: given_solution_id,
-                uuid: Uuid::new_v4().to_string(),
-                class_id: self.class.to_string(),
-                rank: self.rank,
-                starting_date: date,
+                rule_id: given_rule_id,
+                rank: rank as i32,
+                name: p.name,
+                value: p.value,
             })
-            .ok_or(String::from("The date is invalid"))
     }
 }
@@ -0,0 +1,179 @@
+mod memory;
+mod stats;
+mod tee;
+
+pub use memory::SolutionModel;
+pub use stats::SolutionStats;
+pub use tee::Tee;
+
+use super::{
+    import_report::SourceWarning,
+    pipeline::{ParsedElement, SolutionRecord},
+    xml_types::{
+        XmlCalendar, XmlCourse, XmlRoom, XmlRule, XmlSession, XmlSolutionClass, XmlSolutionGroup,
+        XmlStudent, XmlTeacher,
+    },
+};
+
+/// Why a sink could not take an element, the extraction stops on it
+#[derive(Debug)]
+pub enum SinkError {
+    DbError(diesel::result::Error),
+}
+
+impl From<diesel::result::Error> for SinkError {
+    fn from(value: diesel::result::Error) -> Self {
+        SinkError::DbError(value)
+    }
+}
+
+pub type SinkResult = Result<(), SinkError>;
+
+/// Receives the elements of a solution file in reading order, writing them to the database is
+/// one way to use them
+pub trait SolutionSink {
+    /// Called before adding each element, `offset` is its position in the file
+    fn start_element(&mut self, _route: &str, _offset: usize) {}
+    fn add_teacher(&mut self, teacher: XmlTeacher) -> SinkResult;
+    fn add_student(&mut self, student: XmlStudent) -> SinkResult;
+    fn add_course(&mut self, course: XmlCourse) -> SinkResult;
+    fn add_room(&mut self, room: XmlRoom) -> SinkResult;
+    fn add_rule(&mut self, rule: XmlRule) -> SinkResult;
+    fn add_session(&mut self, session: XmlSession) -> SinkResult;
+    fn add_solution_group(&mut self, group: XmlSolutionGroup) -> SinkResult;
+    fn add_solution_class(&mut self, class: XmlSolutionClass) -> SinkResult;
+    fn add_calendar(&mut self, calendar: XmlCalendar) -> SinkResult;
+    fn add_warning(&mut self, warning: SourceWarning) -> SinkResult;
+}
+
+/// Gives an element sent by the parser thread to the sink
+pub fn add_element<S: SolutionSink + ?Sized>(sink: &mut S, element: ParsedElement) -> SinkResult {
+    sink.start_element(&element.route, element.offset);
+
+    return match element.record {
+        SolutionRecord::Teacher(teacher) => sink.add_teacher(teacher),
+        SolutionRecord::Student(student) => sink.add_student(student),
+        SolutionRecord::Course(course) => sink.add_course(course),
+        SolutionRecord::Room(room) => sink.add_room(room),
+        SolutionRecord::Rule(rule) => sink.add_rule(rule),
+        SolutionRecord::Session(session) => sink.add_session(session),
+        SolutionRecord::SolutionGroup(group) => sink.add_solution_group(group),
+        SolutionRecord::SolutionClass(class) => sink.add_solution_class(class),
+        SolutionRecord::Calendar(calendar) => sink.add_calendar(calendar),
+        SolutionRecord::Warning(warning) => sink.add_warning(warning),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{SinkError, SinkResult, SolutionModel, SolutionSink, SolutionStats, Tee};
+    use crate::api::{
+        import::config::ImportConfig,
+        solution::{
+            import_report::SourceWarning,
+            pipeline::{extract_file, ExtractFileError},
+            xml_types::{
+                XmlCalendar, XmlCourse, XmlRoom, XmlRule, XmlSession, XmlSolutionClass,
+                XmlSolutionGroup, XmlStudent, XmlTeacher,
+            },
+        },
+    };
+
+    const SOLUTION: &str = "../resources/solution_ua_l1_p1-p2_l3-info_2023_060524_09_44_03.xml";
+
+    #[test]
+    fn should_give_the_same_elements_to_every_sink() {
+        let mut stats = SolutionStats::default();
+        let mut model = SolutionModel::default();
+
+        let config = ImportConfig {
+            channel_capacity: 16,
+            ..ImportConfig::default()
+        };
+
+        assert!(extract_file(Path::new(SOLUTION), &config, &mut stats).is_ok());
+        assert!(extract_file(Path::new(SOLUTION), &config, &mut model).is_ok());
+
+        assert_eq!(
+            stats,
+            SolutionStats {
+                rooms: 117,
+                teachers: 186,
+                courses: 36,
+                parts: 82,
+                classes: 291,
+                groups: 44,
+                students: 705,
+                sessions: 2134,
+                rules: 304,
+                calendars: 1,
+            }
+        );
+        assert_eq!(model.rooms.len(), stats.rooms);
+        assert_eq!(model.sessions.len(), stats.sessions);
+        assert_eq!(model.solution_classes.len(), 291);
+        assert!(model.calendar.is_some());
+    }
+
+    /// Refuses the rooms, as a database that can not insert them
+    struct NoRooms;
+
+    impl SolutionSink for NoRooms {
+        fn add_teacher(&mut self, _teacher: XmlTeacher) -> SinkResult {
+            return Ok(());
+        }
+        fn add_student(&mut self, _student: XmlStudent) -> SinkResult {
+            return Ok(());
+        }
+        fn add_course(&mut self, _course: XmlCourse) -> SinkResult {
+            return Ok(());
+        }
+        fn add_room(&mut self, _room: XmlRoom) -> SinkResult {
+            return Err(SinkError::DbError(
+                diesel::result::Error::RollbackTransaction,
+            ));
+        }
+        fn add_rule(&mut self, _rule: XmlRule) -> SinkResult {
+            return Ok(());
+        }
+        fn add_session(&mut self, _session: XmlSession) -> SinkResult {
+            return Ok(());
+        }
+        fn add_solution_group(&mut self, _group: XmlSolutionGroup) -> SinkResult {
+            return Ok(());
+        }
+        fn add_solution_class(&mut self, _class: XmlSolutionClass) -> SinkResult {
+            return Ok(());
+        }
+        fn add_calendar(&mut self, _calendar: XmlCalendar) -> SinkResult {
+            return Ok(());
+        }
+        fn add_warning(&mut self, _warning: SourceWarning) -> SinkResult {
+            return Ok(());
+        }
+    }
+
+    #[test]
+    fn should_stop_at_the_first_error_of_the_sink() {
+        let mut stats = SolutionStats::default();
+        let mut sink = Tee {
+            first: &mut stats,
+            second: &mut NoRooms,
+        };
+
+        let config = ImportConfig {
+            channel_capacity: 16,
+            ..ImportConfig::default()
+        };
+
+        assert!(matches!(
+            extract_file(Path::new(SOLUTION), &config, &mut sink),
+            Err(ExtractFileError::SinkError(SinkError::DbError(_)))
+        ));
+        // nothing is given to the sinks after the failed room
+        assert_eq!(stats.rooms, 1);
+        assert_eq!(stats.sessions, 0);
+    }
+}
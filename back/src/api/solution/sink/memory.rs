@@ -0,0 +1,123 @@
+use std::ops::{Deref, DerefMut};
+
+use super::{SinkResult, SolutionSink};
+use crate::api::solution::{
+    import_report::SourceWarning,
+    xml_types::{
+        XmlCalendar, XmlCourse, XmlRoom, XmlRule, XmlSession, XmlSolutionClass, XmlSolutionGroup,
+        XmlStudent, XmlTeacher,
+    },
+};
+
+/// An element with its position in the file
+pub struct Located<T> {
+    pub offset: usize,
+    pub element: T,
+}
+
+impl<T> Deref for Located<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.element
+    }
+}
+
+impl<T> DerefMut for Located<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.element
+    }
+}
+
+/// The whole solution kept in memory as it is in the file, nothing is checked
+#[derive(Default)]
+pub struct SolutionModel {
+    pub calendar: Option<Located<XmlCalendar>>,
+    pub teachers: Vec<Located<XmlTeacher>>,
+    pub students: Vec<Located<XmlStudent>>,
+    pub courses: Vec<Located<XmlCourse>>,
+    pub rooms: Vec<Located<XmlRoom>>,
+    pub rules: Vec<Located<XmlRule>>,
+    pub sessions: Vec<Located<XmlSession>>,
+    pub solution_groups: Vec<Located<XmlSolutionGroup>>,
+    pub solution_classes: Vec<Located<XmlSolutionClass>>,
+    pub warnings: Vec<Located<SourceWarning>>,
+    // offset of the element being added
+    offset: usize,
+}
+
+impl SolutionModel {
+    fn locate<T>(&self, element: T) -> Located<T> {
+        Located {
+            offset: self.offset,
+            element: element,
+        }
+    }
+}
+
+impl SolutionSink for SolutionModel {
+    fn start_element(&mut self, _route: &str, offset: usize) {
+        self.offset = offset;
+    }
+
+    fn add_teacher(&mut self, teacher: XmlTeacher) -> SinkResult {
+        self.teachers.push(self.locate(teacher));
+
+        return Ok(());
+    }
+
+    fn add_student(&mut self, student: XmlStudent) -> SinkResult {
+        self.students.push(self.locate(student));
+
+        return Ok(());
+    }
+
+    fn add_course(&mut self, course: XmlCourse) -> SinkResult {
+        self.courses.push(self.locate(course));
+
+        return Ok(());
+    }
+
+    fn add_room(&mut self, room: XmlRoom) -> SinkResult {
+        self.rooms.push(self.locate(room));
+
+        return Ok(());
+    }
+
+    fn add_rule(&mut self, rule: XmlRule) -> SinkResult {
+        self.rules.push(self.locate(rule));
+
+        return Ok(());
+    }
+
+    fn add_session(&mut self, session: XmlSession) -> SinkResult {
+        self.sessions.push(self.locate(session));
+
+        return Ok(());
+    }
+
+    fn add_solution_group(&mut self, group: XmlSolutionGroup) -> SinkResult {
+        self.solution_groups.push(self.locate(group));
+
+        return Ok(());
+    }
+
+    fn add_solution_class(&mut self, class: XmlSolutionClass) -> SinkResult {
+        self.solution_classes.push(self.locate(class));
+
+        return Ok(());
+    }
+
+    // a file has a single calendar, the last one wins as in the database
+    fn add_calendar(&mut self, calendar: XmlCalendar) -> SinkResult {
+        self.calendar = Some(self.locate(calendar));
+
+        return Ok(());
+    }
+
+    fn add_warning(&mut self, warning: SourceWarning) -> SinkResult {
+        self.warnings.push(self.locate(warning));
+
+        return Ok(());
+    }
+}
@@ -0,0 +1,88 @@
+use super::{SinkResult, SolutionSink};
+use crate::api::solution::{
+    import_report::SourceWarning,
+    xml_types::{
+        XmlCalendar, XmlCourse, XmlRoom, XmlRule, XmlSession, XmlSolutionClass, XmlSolutionGroup,
+        XmlStudent, XmlTeacher,
+    },
+};
+
+/// Counts the entities of a solution, without keeping them
+#[derive(Default, Debug, PartialEq)]
+pub struct SolutionStats {
+    pub rooms: usize,
+    pub teachers: usize,
+    pub courses: usize,
+    pub parts: usize,
+    pub classes: usize,
+    pub groups: usize,
+    pub students: usize,
+    pub sessions: usize,
+    pub rules: usize,
+    pub calendars: usize,
+}
+
+impl SolutionSink for SolutionStats {
+    fn add_teacher(&mut self, _teacher: XmlTeacher) -> SinkResult {
+        self.teachers += 1;
+
+        return Ok(());
+    }
+
+    fn add_student(&mut self, _student: XmlStudent) -> SinkResult {
+        self.students += 1;
+
+        return Ok(());
+    }
+
+    fn add_course(&mut self, course: XmlCourse) -> SinkResult {
+        self.courses += 1;
+        self.parts += course.parts.len();
+        self.classes += course
+            .parts
+            .iter()
+            .map(|part| part.classes.class.len())
+            .sum::<usize>();
+
+        return Ok(());
+    }
+
+    fn add_room(&mut self, _room: XmlRoom) -> SinkResult {
+        self.rooms += 1;
+
+        return Ok(());
+    }
+
+    fn add_rule(&mut self, _rule: XmlRule) -> SinkResult {
+        self.rules += 1;
+
+        return Ok(());
+    }
+
+    fn add_session(&mut self, _session: XmlSession) -> SinkResult {
+        self.sessions += 1;
+
+        return Ok(());
+    }
+
+    fn add_solution_group(&mut self, _group: XmlSolutionGroup) -> SinkResult {
+        self.groups += 1;
+
+        return Ok(());
+    }
+
+    // the classes are counted in their course
+    fn add_solution_class(&mut self, _class: XmlSolutionClass) -> SinkResult {
+        return Ok(());
+    }
+
+    fn add_calendar(&mut self, _calendar: XmlCalendar) -> SinkResult {
+        self.calendars += 1;
+
+        return Ok(());
+    }
+
+    fn add_warning(&mut self, _warning: SourceWarning) -> SinkResult {
+        return Ok(());
+    }
+}
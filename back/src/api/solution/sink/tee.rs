@@ -0,0 +1,71 @@
+use super::{SinkResult, SolutionSink};
+use crate::api::solution::{
+    import_report::SourceWarning,
+    xml_types::{
+        XmlCalendar, XmlCourse, XmlRoom, XmlRule, XmlSession, XmlSolutionClass, XmlSolutionGroup,
+        XmlStudent, XmlTeacher,
+    },
+};
+
+/// Gives every element to two sinks, the first one gets a copy
+pub struct Tee<'a, A: ?Sized, B: ?Sized> {
+    pub first: &'a mut A,
+    pub second: &'a mut B,
+}
+
+impl<A: SolutionSink + ?Sized, B: SolutionSink + ?Sized> SolutionSink for Tee<'_, A, B> {
+    fn start_element(&mut self, route: &str, offset: usize) {
+        self.first.start_element(route, offset);
+        self.second.start_element(route, offset);
+    }
+
+    fn add_teacher(&mut self, teacher: XmlTeacher) -> SinkResult {
+        self.first.add_teacher(teacher.clone())?;
+        return self.second.add_teacher(teacher);
+    }
+
+    fn add_student(&mut self, student: XmlStudent) -> SinkResult {
+        self.first.add_student(student.clone())?;
+        return self.second.add_student(student);
+    }
+
+    fn add_course(&mut self, course: XmlCourse) -> SinkResult {
+        self.first.add_course(course.clone())?;
+        return self.second.add_course(course);
+    }
+
+    fn add_room(&mut self, room: XmlRoom) -> SinkResult {
+        self.first.add_room(room.clone())?;
+        return self.second.add_room(room);
+    }
+
+    fn add_rule(&mut self, rule: XmlRule) -> SinkResult {
+        self.first.add_rule(rule.clone())?;
+        return self.second.add_rule(rule);
+    }
+
+    fn add_session(&mut self, session: XmlSession) -> SinkResult {
+        self.first.add_session(session.clone())?;
+        return self.second.add_session(session);
+    }
+
+    fn add_solution_group(&mut self, group: XmlSolutionGroup) -> SinkResult {
+        self.first.add_solution_group(group.clone())?;
+        return self.second.add_solution_group(group);
+    }
+
+    fn add_solution_class(&mut self, class: XmlSolutionClass) -> SinkResult {
+        self.first.add_solution_class(class.clone())?;
+        return self.second.add_solution_class(class);
+    }
+
+    fn add_calendar(&mut self, calendar: XmlCalendar) -> SinkResult {
+        self.first.add_calendar(calendar.clone())?;
+        return self.second.add_calendar(calendar);
+    }
+
+    fn add_warning(&mut self, warning: SourceWarning) -> SinkResult {
+        self.first.add_warning(warning.clone())?;
+        return self.second.add_warning(warning);
+    }
+}
@@ -0,0 +1,337 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+};
+
+use diesel::{QueryResult, SqliteConnection};
+
+use super::progress::ImportProgress;
+
+// SQLite limits the number of bound values of a statement, rows are inserted by chunks
+const ROWS_PER_STATEMENT: usize = 1000;
+
+/// Inserts rows of a table, `C` is what the buffers share to build their rows
+pub type InsertRows<T, C> = fn(&mut SqliteConnection, &mut C, &[T]) -> QueryResult<usize>;
+
+/// The rows of one table waiting to be inserted
+pub trait TableBuffer<C> {
+    fn table(&self) -> &'static str;
+    /// Tables whose rows must be inserted before the rows of this one
+    fn dependencies(&self) -> &'static [&'static str];
+    fn len(&self) -> usize;
+    /// Rows inserted since the start of the import
+    fn inserted(&self) -> usize;
+    /// Inserts the buffered rows, the buffer is emptied even if the insertion failed: the
+    /// error ends the import and rolls its transaction back
+    fn flush(&mut self, conn: &mut SqliteConnection, context: &mut C) -> QueryResult<usize>;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+/// A table buffer holding rows of type `T`
+struct RowsBuffer<T, C> {
+    table: &'static str,
+    dependencies: &'static [&'static str],
+    rows: Vec<T>,
+    inserted: usize,
+    insert: InsertRows<T, C>,
+}
+
+impl<T: 'static, C: 'static> TableBuffer<C> for RowsBuffer<T, C> {
+    fn table(&self) -> &'static str {
+        self.table
+    }
+
+    fn dependencies(&self) -> &'static [&'static str] {
+        self.dependencies
+    }
+
+    fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    fn inserted(&self) -> usize {
+        self.inserted
+    }
+
+    fn flush(&mut self, conn: &mut SqliteConnection, context: &mut C) -> QueryResult<usize> {
+        let ret = self
+            .rows
+            .chunks(ROWS_PER_STATEMENT)
+            .try_fold(0, |inserted, chunk| {
+                (self.insert)(conn, context, chunk).map(|rows| inserted + rows)
+            });
+        self.rows.clear();
+
+        if let Ok(inserted) = ret {
+            self.inserted += inserted;
+        }
+
+        return ret;
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Orders tables so that each one comes after its dependencies, in registration order otherwise
+fn topological_order(tables: &[(&'static str, &[&'static str])]) -> Result<Vec<usize>, String> {
+    let indexes: HashMap<&str, usize> = tables
+        .iter()
+        .enumerate()
+        .map(|(index, (table, _))| (*table, index))
+        .collect();
+
+    let mut order = Vec::with_capacity(tables.len());
+    let mut placed = vec![false; tables.len()];
+
+    while order.len() < tables.len() {
+        let mut next = None;
+        for (index, (table, dependencies)) in tables.iter().enumerate() {
+            if placed[index] {
+                continue;
+            }
+
+            let mut ready = true;
+            for dependency in dependencies.iter() {
+                match indexes.get(dependency) {
+                    Some(dependency) => ready &= placed[*dependency],
+                    None => {
+                        return Err(format!("{} depends on unknown table {}", table, dependency))
+                    }
+                }
+            }
+            if ready {
+                next = Some(index);
+                break;
+            }
+        }
+
+        match next {
+            Some(index) => {
+                placed[index] = true;
+                order.push(index);
+            }
+            None => {
+                let remaining: Vec<&str> = tables
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, _)| !placed[*index])
+                    .map(|(_, (table, _))| *table)
+                    .collect();
+                return Err(format!("cyclic dependencies between {:?}", remaining));
+            }
+        }
+    }
+
+    return Ok(order);
+}
+
+/// The buffers of every table of an import, each row type has a single table.
+///
+/// A table is flushed when it holds more than `batch_size` rows, along with the tables it
+/// depends on so that its rows never come before theirs.
+pub struct TableBuffers<C> {
+    buffers: Vec<Box<dyn TableBuffer<C>>>,
+    by_row_type: HashMap<TypeId, usize>,
+    // every table in insertion order
+    order: Vec<usize>,
+    // for each table, itself and the tables it depends on in insertion order
+    flush_plans: Vec<Vec<usize>>,
+    batch_size: usize,
+}
+
+impl<C: 'static> TableBuffers<C> {
+    pub fn new(batch_size: usize) -> Self {
+        TableBuffers {
+            buffers: Vec::new(),
+            by_row_type: HashMap::new(),
+            order: Vec::new(),
+            flush_plans: Vec::new(),
+            batch_size: batch_size,
+        }
+    }
+
+    /// Adds the buffer of a table, the registrations are checked by `build`
+    pub fn register<T: 'static>(
+        mut self,
+        table: &'static str,
+        dependencies: &'static [&'static str],
+        insert: InsertRows<T, C>,
+    ) -> Self {
+        if self
+            .by_row_type
+            .insert(TypeId::of::<T>(), self.buffers.len())
+            .is_some()
+        {
+            panic!(
+                "{} is already the row type of another table",
+                std::any::type_name::<T>()
+            );
+        }
+
+        self.buffers.push(Box::new(RowsBuffer {
+            table: table,
+            dependencies: dependencies,
+            rows: Vec::new(),
+            inserted: 0,
+            insert: insert,
+        }));
+
+        return self;
+    }
+
+    /// Computes the insertion order, panics on unknown or cyclic dependencies
+    pub fn build(mut self) -> Self {
+        let tables: Vec<(&'static str, &[&'static str])> = self
+            .buffers
+            .iter()
+            .map(|buffer| (buffer.table(), buffer.dependencies()))
+            .collect();
+        self.order = topological_order(&tables).unwrap_or_else(|e| panic!("{}", e));
+
+        let indexes: HashMap<&str, usize> = tables
+            .iter()
+            .enumerate()
+            .map(|(index, (table, _))| (*table, index))
+            .collect();
+        self.flush_plans = (0..tables.len())
+            .map(|table| {
+                let mut needed = vec![false; tables.len()];
+                let mut stack = vec![table];
+                while let Some(index) = stack.pop() {
+                    if !needed[index] {
+                        needed[index] = true;
+                        stack.extend(tables[index].1.iter().map(|d| indexes[d]));
+                    }
+                }
+                self.order
+                    .iter()
+                    .copied()
+                    .filter(|index| needed[*index])
+                    .collect()
+            })
+            .collect();
+
+        return self;
+    }
+
+    fn rows<T: 'static>(&mut self) -> (usize, &mut Vec<T>) {
+        let index = *self
+            .by_row_type
+            .get(&TypeId::of::<T>())
+            .unwrap_or_else(|| panic!("no table for {}", std::any::type_name::<T>()));
+        let buffer = self.buffers[index]
+            .as_any_mut()
+            .downcast_mut::<RowsBuffer<T, C>>()
+            .expect("the buffer should hold its row type");
+
+        return (index, &mut buffer.rows);
+    }
+
+    /// Buffers rows, returns the table to flush when it is full
+    pub fn extend<T: 'static, I: IntoIterator<Item = T>>(&mut self, rows: I) -> Option<usize> {
+        let batch_size = self.batch_size;
+        let (index, buffer) = self.rows::<T>();
+        buffer.extend(rows);
+
+        return match buffer.len() > batch_size {
+            true => Some(index),
+            false => None,
+        };
+    }
+
+    /// Rows of `table` inserted since the start of the import
+    pub fn inserted(&self, table: &str) -> usize {
+        self.buffers
+            .iter()
+            .find(|buffer| buffer.table() == table)
+            .map_or(0, |buffer| buffer.inserted())
+    }
+
+    fn flush_tables(
+        &mut self,
+        tables: &[usize],
+        conn: &mut SqliteConnection,
+        context: &mut C,
+        progress: &dyn ImportProgress,
+    ) -> QueryResult<usize> {
+        let mut nb_inserted: usize = 0;
+
+        for index in tables {
+            let buffer = &mut self.buffers[*index];
+            if buffer.len() == 0 {
+                continue;
+            }
+
+            progress.on_table_insert(buffer.table());
+            let inserted = buffer.flush(conn, context)?;
+            progress.on_rows_inserted(buffer.table(), inserted);
+            nb_inserted += inserted;
+        }
+        progress.on_flush();
+
+        return Ok(nb_inserted);
+    }
+
+    /// Inserts the rows of a table given by `push` or `extend`, after the ones it depends on
+    pub fn flush_table(
+        &mut self,
+        table: usize,
+        conn: &mut SqliteConnection,
+        context: &mut C,
+        progress: &dyn ImportProgress,
+    ) -> QueryResult<usize> {
+        let plan = std::mem::take(&mut self.flush_plans[table]);
+        let ret = self.flush_tables(&plan, conn, context, progress);
+        self.flush_plans[table] = plan;
+
+        return ret;
+    }
+
+    pub fn flush_all(
+        &mut self,
+        conn: &mut SqliteConnection,
+        context: &mut C,
+        progress: &dyn ImportProgress,
+    ) -> QueryResult<usize> {
+        let order = std::mem::take(&mut self.order);
+        let ret = self.flush_tables(&order, conn, context, progress);
+        self.order = order;
+
+        return ret;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::topological_order;
+
+    #[test]
+    fn should_order_tables_after_their_dependencies() {
+        let tables: Vec<(&'static str, &[&'static str])> = vec![
+            ("sessions_rooms", &["sessions", "rooms"]),
+            ("classes", &["parts"]),
+            ("rooms", &[]),
+            ("sessions", &["classes"]),
+            ("parts", &[]),
+        ];
+
+        let order: Vec<&str> = topological_order(&tables)
+            .unwrap()
+            .into_iter()
+            .map(|index| tables[index].0)
+            .collect();
+
+        assert_eq!(
+            order,
+            vec!["rooms", "parts", "classes", "sessions", "sessions_rooms"]
+        );
+    }
+
+    #[test]
+    fn should_reject_unknown_and_cyclic_dependencies() {
+        assert!(topological_order(&[("parts", &["courses"])]).is_err());
+        assert!(topological_order(&[("parts", &["classes"]), ("classes", &["parts"])]).is_err());
+    }
+}
@@ -1,35 +1,35 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct XmlRoom {
     #[serde(rename = "@id")]
     pub id: String,
-    #[serde(rename = "@capacity")]
-    pub capacity: i32,
-    #[serde(rename = "@label")]
+    #[serde(rename = "@capacity", default, skip_serializing_if = "Option::is_none")]
+    pub capacity: Option<i32>,
+    #[serde(rename = "@label", skip_serializing_if = "Option::is_none")]
     pub label: Option<String>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct XmlTeacher {
-    #[serde(rename = "@label")]
+    #[serde(rename = "@label", skip_serializing_if = "Option::is_none")]
     pub label: Option<String>,
     #[serde(rename = "@id")]
     pub id: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct XmlCourse {
     #[serde(rename = "@id")]
     pub id: String,
-    #[serde(rename = "@label")]
+    #[serde(rename = "@label", skip_serializing_if = "Option::is_none")]
     pub label: Option<String>,
 
     #[serde(rename = "part", default)]
     pub parts: Vec<XmlPart>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct XmlAllowedSlots {
     #[serde(rename = "@sessionLength")]
     pub session_lenght: i32,
@@ -40,115 +40,154 @@ pub struct XmlAllowedSlots {
     pub weeks: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct XmlPart {
     #[serde(rename = "@id")]
     pub id: String,
-    #[serde(rename = "@label")]
+    #[serde(rename = "@label", skip_serializing_if = "Option::is_none")]
     pub label: Option<String>,
-    #[serde(rename = "@nrSessions")]
+    #[serde(rename = "@nrSessions", skip_serializing_if = "Option::is_none")]
     pub nr_session: Option<i32>,
 
     #[serde(rename = "allowedSlots")]
     pub allowed_slots: XmlAllowedSlots,
 
+    #[serde(rename = "allowedRooms", skip_serializing_if = "Option::is_none")]
+    pub allowed_rooms: Option<XmlAllowedRooms>,
+
+    #[serde(rename = "allowedTeachers", skip_serializing_if = "Option::is_none")]
+    pub allowed_teachers: Option<XmlAllowedTeachers>,
+
     pub classes: XmlClasses,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct XmlAllowedRooms {
+    #[serde(rename = "@sessionRooms", skip_serializing_if = "Option::is_none")]
+    pub session_rooms: Option<String>,
+
+    #[serde(rename = "room", default)]
+    pub rooms: Vec<XmlRefIdElement<String>>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct XmlAllowedTeachers {
+    #[serde(rename = "@sessionTeachers", skip_serializing_if = "Option::is_none")]
+    pub session_teachers: Option<i32>,
+
+    #[serde(rename = "teacher", default)]
+    pub teachers: Vec<XmlAllowedTeacher>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct XmlAllowedTeacher {
+    #[serde(rename = "@refId")]
+    pub ref_id: String,
+
+    #[serde(rename = "@nrSessions", skip_serializing_if = "Option::is_none")]
+    pub nr_sessions: Option<i32>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct XmlClasses {
-    #[serde(rename = "@maxHeadCount")]
+    #[serde(rename = "@maxHeadCount", skip_serializing_if = "Option::is_none")]
     pub max_head_count: Option<i32>,
 
     #[serde(default)]
     pub class: Vec<XmlClass>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct XmlClass {
     #[serde(rename = "@id")]
     pub id: String,
 
-    #[serde(rename = "@parent")]
+    #[serde(rename = "@parent", skip_serializing_if = "Option::is_none")]
     pub parent: Option<String>,
 
-    #[serde(rename = "@label")]
+    #[serde(rename = "@label", skip_serializing_if = "Option::is_none")]
     pub label: Option<String>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct XmlSolutionGroup {
     #[serde(rename = "@id")]
     pub id: String,
-    #[serde(rename = "@headCount")]
+    #[serde(rename = "@headCount", skip_serializing_if = "Option::is_none")]
     pub head_count: Option<i32>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub students: Option<XmlGroupStudents>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub classes: Option<XmlGroupClasses>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct XmlGroupClasses {
     #[serde(rename = "class", default)]
     pub classes: Vec<XmlRefIdElement<String>>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct XmlStudent {
     #[serde(rename = "@id")]
     pub id: String,
 
-    #[serde(rename = "@label")]
+    #[serde(rename = "@label", skip_serializing_if = "Option::is_none")]
     pub label: Option<String>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub courses: Option<XmlCourses>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct XmlCourses {
     #[serde(rename = "course", default)]
     pub courses: Vec<XmlRefIdElement<String>>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct XmlGroupStudents {
     #[serde(rename = "student", default)]
     pub students: Vec<XmlRefIdElement<String>>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct XmlRefIdElement<T> {
     #[serde(rename = "@refId")]
     pub ref_id: T,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct XmlSolutionClass {
     #[serde(rename = "@refId")]
     pub ref_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub groups: Option<XmlSolutionClassGroups>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub teachers: Option<XmlSolutionClassTeachers>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub rooms: Option<XmlSolutionClassRooms>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct XmlSolutionClassTeachers {
     #[serde(rename = "teacher", default)]
     pub teachers_id: Vec<XmlRefIdElement<String>>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct XmlSolutionClassRooms {
     #[serde(rename = "room", default)]
     pub rooms_id: Vec<XmlRefIdElement<String>>,
 }
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct XmlSolutionClassGroups {
     #[serde(rename = "group", default)]
     pub groups_id: Vec<XmlRefIdElement<String>>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct XmlSession {
     #[serde(rename = "@rank")]
     pub rank: i32,
@@ -156,14 +195,16 @@ pub struct XmlSession {
     #[serde(rename = "@class")]
     pub class: String,
 
-    #[serde(rename = "startingSlot")]
+    #[serde(rename = "startingSlot", skip_serializing_if = "Option::is_none")]
     pub starting_slot: Option<XmlSessionStartingSlot>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub rooms: Option<XmlSessionRooms>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub teachers: Option<XmlSessionTeachers>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct XmlCalendar {
     #[serde(rename = "@startingWeek")]
     pub starting_week: u32,
@@ -181,16 +222,16 @@ pub struct XmlCalendar {
     pub slots: XmlCalendarMetaData,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct XmlCalendarMetaData {
     #[serde(rename = "@nr")]
     pub nr: i32,
 
-    #[serde(rename = "@sequence", default)]
+    #[serde(rename = "@sequence", default, skip_serializing_if = "Option::is_none")]
     pub sequence: Option<String>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct XmlSessionStartingSlot {
     #[serde(rename = "@dailySlot")]
     pub daily_slot: u16,
@@ -202,14 +243,348 @@ pub struct XmlSessionStartingSlot {
     pub week: u32,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct XmlSessionRooms {
     #[serde(rename = "room", default)]
     pub rooms_id: Vec<XmlRefIdElement<String>>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct XmlSessionTeachers {
     #[serde(rename = "teacher", default)]
     pub teachers_id: Vec<XmlRefIdElement<String>>,
 }
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct XmlRule {
+    #[serde(rename = "selector", default)]
+    pub selectors: Vec<XmlSelector>,
+
+    pub constraint: XmlConstraint,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct XmlSelector {
+    #[serde(rename = "@generator")]
+    pub generator: String,
+
+    #[serde(rename = "@filters", default, skip_serializing_if = "Option::is_none")]
+    pub filters: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct XmlConstraint {
+    #[serde(rename = "@name")]
+    pub name: String,
+
+    #[serde(rename = "@type", skip_serializing_if = "Option::is_none")]
+    pub constraint_type: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<XmlConstraintParameters>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct XmlConstraintParameters {
+    #[serde(rename = "parameter", default)]
+    pub parameters: Vec<XmlConstraintParameter>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct XmlConstraintParameter {
+    #[serde(rename = "@name")]
+    pub name: String,
+
+    #[serde(rename = "$text", default)]
+    pub value: String,
+}
+
+/// A whole document, only written by the export: the import reads it element by element
+#[derive(Serialize, Debug)]
+#[serde(rename = "timetabling")]
+pub struct XmlTimetabling {
+    #[serde(rename = "@xmlns:xsi")]
+    pub xmlns_xsi: &'static str,
+    #[serde(rename = "@xsi:noNamespaceSchemaLocation")]
+    pub schema_location: &'static str,
+    #[serde(rename = "@name")]
+    pub name: String,
+
+    pub calendar: XmlCalendar,
+    pub rooms: XmlRoomList,
+    pub teachers: XmlTeacherList,
+    pub courses: XmlCourseList,
+    pub students: XmlStudentList,
+    pub rules: XmlRuleList,
+    pub solution: XmlSolution,
+}
+
+#[derive(Serialize, Debug)]
+pub struct XmlRoomList {
+    pub room: Vec<XmlRoom>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct XmlTeacherList {
+    pub teacher: Vec<XmlTeacher>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct XmlCourseList {
+    pub course: Vec<XmlCourse>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct XmlStudentList {
+    pub student: Vec<XmlStudent>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct XmlRuleList {
+    pub rule: Vec<XmlRule>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct XmlSolution {
+    pub groups: XmlSolutionGroupList,
+    pub classes: XmlSolutionClassList,
+    pub sessions: XmlSessionList,
+}
+
+#[derive(Serialize, Debug)]
+pub struct XmlSolutionGroupList {
+    pub group: Vec<XmlSolutionGroup>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct XmlSolutionClassList {
+    pub class: Vec<XmlSolutionClass>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct XmlSessionList {
+    pub session: Vec<XmlSession>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fmt::Debug,
+        fs::File,
+        io::BufReader,
+        path::Path,
+        time::{Duration, Instant},
+    };
+
+    use quick_xml::{events::Event, name::QName, NsReader, Writer};
+    use serde::de::DeserializeOwned;
+
+    use super::{
+        XmlCalendar, XmlCourse, XmlRoom, XmlRule, XmlSession, XmlSolutionClass, XmlSolutionGroup,
+        XmlStudent, XmlTeacher,
+    };
+    use crate::xml_parsing::reader::{EventHandlingError, Router, XmlParser, XmlRouting};
+
+    const SOLUTION: &str = "../resources/solution_ua_l1_p1-p2_l3-info_2023_060524_09_44_03.xml";
+
+    struct Deserialized {
+        // the benchmark does not format the elements
+        describe: bool,
+        elements: Vec<String>,
+    }
+
+    impl Deserialized {
+        fn push<T: Debug>(&mut self, element: T) {
+            self.elements.push(match self.describe {
+                true => format!("{:?}", element),
+                false => String::new(),
+            });
+        }
+    }
+
+    fn describe<T: DeserializeOwned + Debug>(xml: &str, deserialized: &mut Deserialized) {
+        deserialized.push(quick_xml::de::from_str::<T>(xml).unwrap());
+    }
+
+    type Describe = fn(&str, &mut Deserialized);
+
+    // the routes of the solution importer, with what deserializes a copy of their elements
+    const ROUTES: [(&[&str], Describe); 9] = [
+        (
+            &["timetabling", "teachers", "teacher"],
+            describe::<XmlTeacher>,
+        ),
+        (
+            &["timetabling", "students", "student"],
+            describe::<XmlStudent>,
+        ),
+        (&["timetabling", "courses", "course"], describe::<XmlCourse>),
+        (&["timetabling", "rooms", "room"], describe::<XmlRoom>),
+        (&["timetabling", "rules", "rule"], describe::<XmlRule>),
+        (
+            &["timetabling", "solution", "sessions", "session"],
+            describe::<XmlSession>,
+        ),
+        (
+            &["timetabling", "solution", "groups", "group"],
+            describe::<XmlSolutionGroup>,
+        ),
+        (
+            &["timetabling", "solution", "classes", "class"],
+            describe::<XmlSolutionClass>,
+        ),
+        (&["timetabling", "calendar"], describe::<XmlCalendar>),
+    ];
+
+    fn copy_children(
+        reader: &mut NsReader<BufReader<File>>,
+        writer: &mut Writer<&mut Vec<u8>>,
+        buffer: &mut Vec<u8>,
+        name: QName,
+    ) {
+        let mut depth = 0;
+
+        loop {
+            buffer.clear();
+            let event = reader.read_event_into(buffer).unwrap();
+            writer.write_event(&event).unwrap();
+            match event {
+                Event::Start(e) if e.name() == name => depth += 1,
+                Event::End(e) if e.name() == name => {
+                    if depth == 0 {
+                        return;
+                    }
+                    depth -= 1;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// How elements were deserialized before: each one is written again in a buffer, then parsed.
+    /// It reads the file with the same reader as the parser, only the deserialization differs
+    fn deserialize_copies(path: &Path, describe: bool) -> Vec<String> {
+        let mut reader = NsReader::from_file(path).unwrap();
+        let mut buffer = Vec::new();
+        let mut copy = Vec::new();
+        let mut inner_buffer = Vec::new();
+        let mut route: Vec<String> = Vec::new();
+        let mut deserialized = Deserialized {
+            describe: describe,
+            elements: Vec::new(),
+        };
+
+        loop {
+            buffer.clear();
+            let event = reader.read_event_into(&mut buffer).unwrap();
+            let (start, is_empty) = match event {
+                Event::Eof => return deserialized.elements,
+                Event::End(_) => {
+                    route.pop();
+                    continue;
+                }
+                Event::Start(e) => (e, false),
+                Event::Empty(e) => (e, true),
+                _ => continue,
+            };
+
+            route.push(String::from_utf8(start.name().as_ref().to_vec()).unwrap());
+            let routed = ROUTES.iter().find(|(r, _)| *r == route.as_slice());
+            if routed.is_none() {
+                if is_empty {
+                    route.pop();
+                }
+                continue;
+            }
+
+            copy.clear();
+            let mut writer = Writer::new(&mut copy);
+            if is_empty {
+                writer.write_event(Event::Empty(start)).unwrap();
+            } else {
+                let end = start.to_end().into_owned();
+                writer.write_event(Event::Start(start)).unwrap();
+                copy_children(&mut reader, &mut writer, &mut inner_buffer, end.name());
+            }
+            route.pop();
+            routed.unwrap().1(std::str::from_utf8(&copy).unwrap(), &mut deserialized);
+        }
+    }
+
+    fn routing<'a, T: DeserializeOwned + Debug>(
+        route: &[&'static str],
+    ) -> XmlRouting<'a, BufReader<File>, EventHandlingError, Deserialized> {
+        XmlRouting {
+            route: route.to_vec(),
+            handler: Box::new(
+                |event: Event,
+                 parser: &mut XmlParser<BufReader<File>>,
+                 deserialized: &mut Deserialized| {
+                    return parser.handle_event(event, |element: T| deserialized.push(element));
+                },
+            ),
+        }
+    }
+
+    /// How elements are deserialized now, straight from the events of the parser
+    fn deserialize_events(path: &Path, describe: bool) -> Vec<String> {
+        let mut router = Router::new(vec![
+            routing::<XmlTeacher>(ROUTES[0].0),
+            routing::<XmlStudent>(ROUTES[1].0),
+            routing::<XmlCourse>(ROUTES[2].0),
+            routing::<XmlRoom>(ROUTES[3].0),
+            routing::<XmlRule>(ROUTES[4].0),
+            routing::<XmlSession>(ROUTES[5].0),
+            routing::<XmlSolutionGroup>(ROUTES[6].0),
+            routing::<XmlSolutionClass>(ROUTES[7].0),
+            routing::<XmlCalendar>(ROUTES[8].0),
+        ])
+        .unwrap();
+        let mut parser = XmlParser::from_file(path).unwrap();
+        let mut deserialized = Deserialized {
+            describe: describe,
+            elements: Vec::new(),
+        };
+
+        parser.walk_buffer(&mut router, &mut deserialized).unwrap();
+
+        return deserialized.elements;
+    }
+
+    #[test]
+    fn should_deserialize_the_solution_as_the_serde_deserializer_of_quick_xml() {
+        let path = Path::new(SOLUTION);
+
+        let deserialized = deserialize_events(path, true);
+
+        assert_eq!(deserialized.len(), 3818);
+        assert_eq!(deserialized, deserialize_copies(path, true));
+    }
+
+    /// `cargo test --release deserialization_benchmark -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn deserialization_benchmark() {
+        const RUNS: u32 = 10;
+        let path = Path::new(SOLUTION);
+
+        let time = |f: fn(&Path, bool) -> Vec<String>| {
+            let mut total = Duration::ZERO;
+            for _ in 0..RUNS {
+                let start = Instant::now();
+                f(path, false);
+                total += start.elapsed();
+            }
+            total / RUNS
+        };
+
+        let copies = time(deserialize_copies);
+        let events = time(deserialize_events);
+        println!("deserializing copies of the elements: {:?}", copies);
+        println!("deserializing from the events: {:?}", events);
+        println!(
+            "speedup: {:.2}x",
+            copies.as_secs_f64() / events.as_secs_f64()
+        );
+    }
+}
@@ -7,6 +7,7 @@ use crate::db::schema;
 pub type InsertSolution<'a> = (
     Eq<schema::solutions::filename, &'a str>,
     Eq<schema::solutions::created_at, &'a NaiveDateTime>,
+    Eq<schema::solutions::slot_duration_seconds, i32>,
 );
 
 #[derive(Queryable, Serialize, Selectable, Debug)]
@@ -14,11 +15,26 @@ pub type InsertSolution<'a> = (
 pub struct Solution {
     pub id: i32,
     pub filename: String,
-    pub slot_duration: i32,
+    pub slot_duration_seconds: i32,
     pub calendar_start: Option<NaiveDateTime>,
     pub created_at: NaiveDateTime,
 }
 
+/// The calendar as the file describes it, the sessions are dated with it
+#[derive(Queryable, Selectable, Insertable, Debug)]
+#[diesel(table_name = schema::calendars)]
+#[diesel(belongs_to(Solution))]
+pub struct Calendar {
+    pub solution_id: i32,
+    pub year: i32,
+    pub starting_week: i32,
+    pub weeks_nr: i32,
+    pub weeks_sequence: Option<String>,
+    pub days_nr: i32,
+    pub days_sequence: Option<String>,
+    pub slots_nr: i32,
+}
+
 #[derive(Queryable, Selectable, Insertable, Debug, Hash, Eq, PartialEq)]
 #[diesel(table_name = schema::teachers)]
 #[diesel(belongs_to(Solution))]
@@ -28,21 +44,13 @@ pub struct Teacher {
     pub department: Option<String>,
 }
 
-#[derive(Queryable, Selectable)]
-#[diesel(table_name = schema::sessions_rooms)]
-pub struct SessionRoom {
-    pub session_id: i32,
-    pub solution_id: i32,
-    pub room_id: String,
-}
-
 #[derive(Queryable, Selectable, Insertable, Debug, Hash, Eq, PartialEq)]
 #[diesel(table_name = schema::rooms)]
 #[diesel(belongs_to(Solution))]
 pub struct Room {
     pub id: String,
     pub solution_id: i32,
-    pub capacity: i32,
+    pub capacity: Option<i32>,
     pub name: Option<String>,
 }
 
@@ -72,6 +80,52 @@ pub struct Part {
     pub nr_session: Option<i32>,
 }
 
+#[derive(Queryable, Selectable, Insertable, Debug, Hash, Eq, PartialEq)]
+#[diesel(table_name = schema::part_allowed_daily_slots)]
+#[diesel(belongs_to(Part))]
+pub struct PartAllowedDailySlot {
+    pub solution_id: i32,
+    pub part_id: String,
+    pub daily_slot: i32,
+}
+
+#[derive(Queryable, Selectable, Insertable, Debug, Hash, Eq, PartialEq)]
+#[diesel(table_name = schema::part_allowed_days)]
+#[diesel(belongs_to(Part))]
+pub struct PartAllowedDay {
+    pub solution_id: i32,
+    pub part_id: String,
+    pub day: i32,
+}
+
+#[derive(Queryable, Selectable, Insertable, Debug, Hash, Eq, PartialEq)]
+#[diesel(table_name = schema::part_allowed_weeks)]
+#[diesel(belongs_to(Part))]
+pub struct PartAllowedWeek {
+    pub solution_id: i32,
+    pub part_id: String,
+    pub week: i32,
+}
+
+#[derive(Queryable, Selectable, Insertable, Debug, Hash, Eq, PartialEq)]
+#[diesel(table_name = schema::part_allowed_rooms)]
+#[diesel(belongs_to(Part))]
+pub struct PartAllowedRoom {
+    pub solution_id: i32,
+    pub part_id: String,
+    pub room_id: String,
+}
+
+#[derive(Queryable, Selectable, Insertable, Debug, Hash, Eq, PartialEq)]
+#[diesel(table_name = schema::part_allowed_teachers)]
+#[diesel(belongs_to(Part))]
+pub struct PartAllowedTeacher {
+    pub solution_id: i32,
+    pub part_id: String,
+    pub teacher_id: String,
+    pub nr_sessions: Option<i32>,
+}
+
 #[derive(Queryable, Selectable, Insertable, Debug, Hash, Eq, PartialEq)]
 #[diesel(belongs_to(Part))]
 #[diesel(belongs_to(Solution))]
@@ -80,6 +134,8 @@ pub struct Class {
     pub solution_id: i32,
     pub id: String,
     pub part_id: String,
+    pub parent_id: Option<String>,
+    pub label: Option<String>,
 }
 
 #[derive(Queryable, Selectable, Insertable, Debug, Hash, Eq, PartialEq)]
@@ -92,6 +148,14 @@ pub struct Student {
     pub label: Option<String>,
 }
 
+#[derive(Queryable, Selectable, Insertable, Debug, Hash, Eq, PartialEq)]
+#[diesel(table_name = schema::students_courses)]
+pub struct StudentCourseOwn {
+    pub solution_id: i32,
+    pub student_id: String,
+    pub course_id: String,
+}
+
 #[derive(Queryable, Selectable, Insertable, Debug, Hash, Eq, PartialEq)]
 #[diesel(table_name = schema::students_groups)]
 pub struct StudentGroupOwn {
@@ -129,10 +193,10 @@ pub struct SessionTeacherOwn {
 
 #[derive(Queryable, Selectable, Insertable, Debug, Hash, Eq, PartialEq)]
 #[diesel(table_name = schema::groups)]
-
 pub struct SolutionGroupOwn {
     pub solution_id: i32,
     pub id: String,
+    pub head_count: Option<i32>,
 }
 
 #[derive(Queryable, Selectable, Insertable, Debug, Hash, Eq, PartialEq)]
@@ -159,6 +223,51 @@ pub struct ClassRoomOwn {
     pub room_id: String,
 }
 
-sql_function! {
+#[derive(Queryable, Selectable, Insertable, Debug, Hash, Eq, PartialEq)]
+#[diesel(table_name = schema::rules)]
+#[diesel(belongs_to(Solution))]
+pub struct Rule {
+    pub solution_id: i32,
+    pub id: i32,
+    pub constraint_name: String,
+    pub constraint_type: Option<String>,
+}
+
+#[derive(Queryable, Selectable, Insertable, Debug, Hash, Eq, PartialEq)]
+#[diesel(table_name = schema::rule_selectors)]
+#[diesel(belongs_to(Rule))]
+pub struct RuleSelector {
+    pub solution_id: i32,
+    pub rule_id: i32,
+    pub rank: i32,
+    pub generator: String,
+    pub filters: Option<String>,
+}
+
+#[derive(Queryable, Selectable, Insertable, Debug, Hash, Eq, PartialEq)]
+#[diesel(table_name = schema::constraint_parameters)]
+#[diesel(belongs_to(Rule))]
+pub struct ConstraintParameter {
+    pub solution_id: i32,
+    pub rule_id: i32,
+    pub rank: i32,
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = schema::import_warnings)]
+#[diesel(belongs_to(Solution))]
+pub struct ImportWarning {
+    pub solution_id: i32,
+    pub id: i32,
+    pub kind: String,
+    pub subject: String,
+    pub detail: Option<String>,
+    pub line_number: Option<i32>,
+    pub column_number: Option<i32>,
+}
+
+define_sql_function! {
     fn last_insert_rowid() -> Integer
 }
@@ -1,10 +1,25 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    calendars (solution_id) {
+        solution_id -> Integer,
+        year -> Integer,
+        starting_week -> Integer,
+        weeks_nr -> Integer,
+        weeks_sequence -> Nullable<Text>,
+        days_nr -> Integer,
+        days_sequence -> Nullable<Text>,
+        slots_nr -> Integer,
+    }
+}
+
 diesel::table! {
     classes (solution_id, id) {
         solution_id -> Integer,
         id -> Text,
         part_id -> Text,
+        parent_id -> Nullable<Text>,
+        label -> Nullable<Text>,
     }
 }
 
@@ -32,6 +47,16 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    constraint_parameters (solution_id, rule_id, rank) {
+        solution_id -> Integer,
+        rule_id -> Integer,
+        rank -> Integer,
+        name -> Text,
+        value -> Text,
+    }
+}
+
 diesel::table! {
     courses (solution_id, id) {
         solution_id -> Integer,
@@ -44,6 +69,60 @@ diesel::table! {
     groups (solution_id, id) {
         solution_id -> Integer,
         id -> Text,
+        head_count -> Nullable<Integer>,
+    }
+}
+
+diesel::table! {
+    import_warnings (solution_id, id) {
+        solution_id -> Integer,
+        id -> Integer,
+        kind -> Text,
+        subject -> Text,
+        detail -> Nullable<Text>,
+        line_number -> Nullable<Integer>,
+        column_number -> Nullable<Integer>,
+    }
+}
+
+diesel::table! {
+    part_allowed_daily_slots (solution_id, part_id, daily_slot) {
+        solution_id -> Integer,
+        part_id -> Text,
+        daily_slot -> Integer,
+    }
+}
+
+diesel::table! {
+    part_allowed_days (solution_id, part_id, day) {
+        solution_id -> Integer,
+        part_id -> Text,
+        day -> Integer,
+    }
+}
+
+diesel::table! {
+    part_allowed_rooms (solution_id, part_id, room_id) {
+        solution_id -> Integer,
+        part_id -> Text,
+        room_id -> Text,
+    }
+}
+
+diesel::table! {
+    part_allowed_teachers (solution_id, part_id, teacher_id) {
+        solution_id -> Integer,
+        part_id -> Text,
+        teacher_id -> Text,
+        nr_sessions -> Nullable<Integer>,
+    }
+}
+
+diesel::table! {
+    part_allowed_weeks (solution_id, part_id, week) {
+        solution_id -> Integer,
+        part_id -> Text,
+        week -> Integer,
     }
 }
 
@@ -70,6 +149,25 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    rule_selectors (solution_id, rule_id, rank) {
+        solution_id -> Integer,
+        rule_id -> Integer,
+        rank -> Integer,
+        generator -> Text,
+        filters -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    rules (solution_id, id) {
+        solution_id -> Integer,
+        id -> Integer,
+        constraint_name -> Text,
+        constraint_type -> Nullable<Text>,
+    }
+}
+
 diesel::table! {
     sessions (id) {
         id -> Integer,
@@ -101,7 +199,7 @@ diesel::table! {
     solutions (id) {
         id -> Integer,
         filename -> Text,
-        slot_duration -> Integer,
+        slot_duration_seconds -> Integer,
         calendar_start -> Nullable<Timestamp>,
         created_at -> Timestamp,
     }
@@ -115,6 +213,14 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    students_courses (solution_id, student_id, course_id) {
+        solution_id -> Integer,
+        student_id -> Text,
+        course_id -> Text,
+    }
+}
+
 diesel::table! {
     students_groups (solution_id, student_id, group_id) {
         solution_id -> Integer,
@@ -131,37 +237,59 @@ diesel::table! {
     }
 }
 
+diesel::joinable!(calendars -> solutions (solution_id));
 diesel::joinable!(classes -> solutions (solution_id));
 diesel::joinable!(classes_groups -> solutions (solution_id));
 diesel::joinable!(classes_rooms -> solutions (solution_id));
 diesel::joinable!(classes_teachers -> solutions (solution_id));
+diesel::joinable!(constraint_parameters -> solutions (solution_id));
 diesel::joinable!(courses -> solutions (solution_id));
 diesel::joinable!(groups -> solutions (solution_id));
+diesel::joinable!(import_warnings -> solutions (solution_id));
+diesel::joinable!(part_allowed_daily_slots -> solutions (solution_id));
+diesel::joinable!(part_allowed_days -> solutions (solution_id));
+diesel::joinable!(part_allowed_rooms -> solutions (solution_id));
+diesel::joinable!(part_allowed_teachers -> solutions (solution_id));
+diesel::joinable!(part_allowed_weeks -> solutions (solution_id));
 diesel::joinable!(parts -> solutions (solution_id));
 diesel::joinable!(rooms -> solutions (solution_id));
+diesel::joinable!(rule_selectors -> solutions (solution_id));
+diesel::joinable!(rules -> solutions (solution_id));
 diesel::joinable!(sessions -> solutions (solution_id));
 diesel::joinable!(sessions_rooms -> sessions (session_id));
 diesel::joinable!(sessions_rooms -> solutions (solution_id));
 diesel::joinable!(sessions_teachers -> sessions (session_id));
 diesel::joinable!(sessions_teachers -> solutions (solution_id));
 diesel::joinable!(students -> solutions (solution_id));
+diesel::joinable!(students_courses -> solutions (solution_id));
 diesel::joinable!(students_groups -> solutions (solution_id));
 diesel::joinable!(teachers -> solutions (solution_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    calendars,
     classes,
     classes_groups,
     classes_rooms,
     classes_teachers,
+    constraint_parameters,
     courses,
     groups,
+    import_warnings,
+    part_allowed_daily_slots,
+    part_allowed_days,
+    part_allowed_rooms,
+    part_allowed_teachers,
+    part_allowed_weeks,
     parts,
     rooms,
+    rule_selectors,
+    rules,
     sessions,
     sessions_rooms,
     sessions_teachers,
     solutions,
     students,
+    students_courses,
     students_groups,
     teachers,
 );
@@ -5,6 +5,8 @@ use diesel::{r2d2, sqlite::Sqlite, SqliteConnection};
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
 use log::{error, info};
 
+use crate::api::import::{config::ImportConfig, job::ImportJobs};
+
 mod api;
 mod db;
 mod xml_parsing;
@@ -42,12 +44,24 @@ async fn main() -> std::io::Result<()> {
         .inspect_err(|e| error!("{}", e))
         .expect("The migrations failed");
 
+    let import_config = web::Data::new(ImportConfig::from_env());
+    // shared by every worker, so that a job can be followed from any of them
+    let import_jobs = web::Data::new(ImportJobs::new(
+        import_config.jobs_ttl,
+        import_config.max_finished_jobs,
+    ));
+    let multipart_config = import_config.multipart_config();
+
     HttpServer::new(move || {
         App::new()
             .app_data(web::Data::new(pool.clone()))
+            .app_data(import_jobs.clone())
+            .app_data(import_config.clone())
+            .app_data(multipart_config.clone())
             .wrap(Logger::default())
             .service(hello)
             .service(api::get_scope())
+            .service(api::get_imports_scope())
     })
     .bind(("0.0.0.0", 5000))?
     .run()
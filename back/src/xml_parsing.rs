@@ -1 +1,4 @@
+pub mod limits;
 pub mod reader;
+pub mod route;
+mod subtree;
@@ -0,0 +1,58 @@
+use std::fmt;
+
+/// Bounds on what a document can hold, the parsing stops at the first one crossed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct XmlLimits {
+    /// Elements open at the same time
+    pub max_depth: usize,
+    /// Bytes of a routed element, from its start tag to its end tag
+    pub max_element_bytes: usize,
+    /// Attributes of a single element
+    pub max_attributes: usize,
+}
+
+impl Default for XmlLimits {
+    fn default() -> Self {
+        XmlLimits {
+            max_depth: 64,
+            max_element_bytes: 16 * 1024 * 1024,
+            max_attributes: 256,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Limit {
+    Depth(usize),
+    ElementBytes(usize),
+    Attributes(usize),
+}
+
+/// A limit crossed at `offset`, the position of the element in the document
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LimitError {
+    pub limit: Limit,
+    pub offset: usize,
+}
+
+impl fmt::Display for LimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.limit {
+            Limit::Depth(max) => write!(
+                f,
+                "more than {} elements are nested at byte {}",
+                max, self.offset
+            ),
+            Limit::ElementBytes(max) => write!(
+                f,
+                "the element at byte {} is longer than {} bytes",
+                self.offset, max
+            ),
+            Limit::Attributes(max) => write!(
+                f,
+                "the element at byte {} has more than {} attributes",
+                self.offset, max
+            ),
+        }
+    }
+}
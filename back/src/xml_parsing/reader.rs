@@ -1,22 +1,33 @@
 use std::{
     fs::File,
-    io::{BufRead, BufReader},
+    io::{self, BufRead, BufReader},
     path::Path,
-    str::{self, FromStr},
+    str,
 };
 
 use log::error;
 use quick_xml::{
-    de::from_str,
     events::{self, Event},
-    name::QName,
-    DeError, Reader, Writer,
+    DeError, NsReader,
 };
 use serde::de::DeserializeOwned;
 
+use super::{
+    limits::{Limit, LimitError, XmlLimits},
+    route::{RoutePatternError, RouteTrie, TrieState},
+    subtree::{self, ElementError},
+};
+
 #[derive(Debug)]
 pub enum EventHandlingError {
     DeserializationError(DeserializationError),
+    /// The element holds bytes that are not valid in the encoding of the document, `offset` is
+    /// the position of the tag or the text that holds them
+    DecodingError {
+        offset: usize,
+        error: DeError,
+    },
+    LimitExceeded(LimitError),
     UnsupportedEventType,
 }
 
@@ -24,45 +35,161 @@ pub enum EventHandlingError {
 pub enum XmlRoutingError<E> {
     HandlingError(E),
     RoutingError(quick_xml::Error),
+    LimitExceeded(LimitError),
+    /// The name or a routed attribute of the element at `offset` can not be decoded
+    DecodingError {
+        offset: usize,
+        error: DeError,
+    },
 }
 
 #[derive(Debug)]
 pub enum DeserializationError {
-    Utf8Error(str::Utf8Error),
     DeError(DeError),
-    QuickXmlError(quick_xml::Error),
 }
 
-pub type Router<'a, R, E, Context> = Vec<XmlRouting<'a, R, E, Context>>;
+/// Routes the elements of a document to their handlers.
+///
+/// A route is a list of segments matched against the path of an element:
+/// - `name` matches the local name of an element, whatever its namespace
+/// - `prefix:name` matches the name as written in the document
+/// - `{uri}name` matches a local name in the `uri` namespace, `{}name` in no namespace
+/// - `*` matches any element, `{uri}*` any element of a namespace
+/// - `**` matches any number of elements, even none
+/// - `[@attribute]` and `[@attribute='value']` after a segment filter on attributes
+///
+/// When several routes match an element, the first one wins.
+pub struct Router<'a, R: BufRead, E, Context> {
+    routings: Vec<XmlRouting<'a, R, E, Context>>,
+    // each route joined with '/'
+    labels: Vec<String>,
+    trie: RouteTrie,
+}
+
+impl<'a, R: BufRead, E, Context> Router<'a, R, E, Context> {
+    pub fn new(routings: Vec<XmlRouting<'a, R, E, Context>>) -> Result<Self, RoutePatternError> {
+        let trie = RouteTrie::new(routings.iter().map(|routing| routing.route.as_slice()))?;
+
+        return Ok(Router {
+            labels: routings
+                .iter()
+                .map(|routing| routing.route.join("/"))
+                .collect(),
+            routings: routings,
+            trie: trie,
+        });
+    }
+}
+
+/// Called with the opening event of each element matching the route
+pub type XmlHandler<'a, R, E, Context> =
+    Box<dyn FnMut(Event, &mut XmlParser<R>, &mut Context) -> Result<(), E> + 'a>;
 
 pub struct XmlRouting<'a, R: BufRead, E, Context> {
     pub route: Vec<&'static str>,
-    pub handler: Box<dyn FnMut(Event, &mut XmlParser<R>, &mut Context) -> Result<(), E> + 'a>,
+    pub handler: XmlHandler<'a, R, E, Context>,
 }
 
 pub struct XmlParser<R: BufRead> {
-    reader: Reader<R>,
+    reader: NsReader<R>,
     buffer: Vec<u8>,
-    serialization_buffer: Vec<u8>,
+    limits: XmlLimits,
+    element_offset: usize,
+    // elements the routed element is in, itself included
+    element_depth: usize,
+    element_route: String,
+}
+
+/// Position in a document, line and column start at 1, the column is counted in bytes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct XmlPosition {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Maps the byte offsets given by the parser to lines and columns
+pub struct LineIndex {
+    line_starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn from_file(file_path: &Path) -> io::Result<Self> {
+        return Self::from_reader(BufReader::new(File::open(file_path)?));
+    }
+
+    pub fn from_reader<R: BufRead>(mut reader: R) -> io::Result<Self> {
+        let mut line_starts = vec![0];
+        let mut chunk_offset = 0;
+
+        loop {
+            let chunk = reader.fill_buf()?;
+            if chunk.is_empty() {
+                break;
+            }
+
+            line_starts.extend(
+                chunk
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, byte)| **byte == b'\n')
+                    .map(|(index, _)| chunk_offset + index + 1),
+            );
+
+            let chunk_len = chunk.len();
+            chunk_offset += chunk_len;
+            reader.consume(chunk_len);
+        }
+
+        return Ok(LineIndex {
+            line_starts: line_starts,
+        });
+    }
+
+    pub fn position(&self, offset: usize) -> XmlPosition {
+        let line = self.line_starts.partition_point(|start| *start <= offset);
+
+        return XmlPosition {
+            line: line,
+            column: offset - self.line_starts[line - 1] + 1,
+        };
+    }
 }
 
-impl<'a> XmlParser<BufReader<File>> {
+impl XmlParser<BufReader<File>> {
     pub fn from_file(file_path: &Path) -> Result<Self, quick_xml::Error> {
-        let reader = Reader::from_file(file_path)?;
+        let reader = NsReader::from_file(file_path)?;
 
         return Ok(Self::new(reader));
     }
 }
 
 impl<'a, R: BufRead> XmlParser<R> {
-    pub fn new(reader: Reader<R>) -> Self {
+    pub fn new(reader: NsReader<R>) -> Self {
         XmlParser {
             reader: reader,
             buffer: Vec::new(),
-            serialization_buffer: Vec::new(),
+            limits: XmlLimits::default(),
+            element_offset: 0,
+            element_depth: 0,
+            element_route: String::new(),
         }
     }
 
+    pub fn with_limits(mut self, limits: XmlLimits) -> Self {
+        self.limits = limits;
+        return self;
+    }
+
+    /// Byte offset of the element being routed, use a `LineIndex` to get its line and column
+    pub fn element_offset(&self) -> usize {
+        self.element_offset
+    }
+
+    /// Route of the element being routed, joined with '/'
+    pub fn element_route(&self) -> &str {
+        &self.element_route
+    }
+
     pub fn walk_buffer<E, Context>(
         &mut self,
         router: &mut Router<'a, R, E, Context>,
@@ -70,10 +197,12 @@ impl<'a, R: BufRead> XmlParser<R> {
     ) -> Result<(), XmlRoutingError<E>> {
         let mut buffer = Vec::new();
 
-        let mut current_route = Vec::new();
+        // the trie nodes reached by each open element
+        let mut states = vec![router.trie.root()];
 
         loop {
             buffer.clear();
+            let event_offset = self.reader.buffer_position();
             let event_result = self.reader.read_event_into(&mut buffer);
 
             match event_result {
@@ -82,25 +211,26 @@ impl<'a, R: BufRead> XmlParser<R> {
                     Event::Eof => break,
 
                     Event::Start(bs) => {
-                        current_route.push(Self::qname_to_string(&bs.name()));
+                        self.enter_element(event_offset, states.len())?;
+                        let state = self.next_state(router, &states, bs, event_offset)?;
                         let consumed_event = self
-                            .route_xml_event(event, router, &mut current_route, context)
+                            .route_xml_event(event, router, &state, context)
                             .map_err(XmlRoutingError::HandlingError)?;
 
-                        if consumed_event {
-                            current_route.pop();
+                        if !consumed_event {
+                            states.push(state);
                         }
                     }
 
                     Event::End(_) => {
-                        current_route.pop();
+                        states.pop();
                     }
 
                     Event::Empty(e) => {
-                        current_route.push(Self::qname_to_string(&e.name()));
-                        self.route_xml_event(event, router, &mut current_route, context)
+                        self.enter_element(event_offset, states.len())?;
+                        let state = self.next_state(router, &states, e, event_offset)?;
+                        self.route_xml_event(event, router, &state, context)
                             .map_err(XmlRoutingError::HandlingError)?;
-                        current_route.pop();
                     }
 
                     _ => {}
@@ -110,28 +240,90 @@ impl<'a, R: BufRead> XmlParser<R> {
         return Result::Ok(());
     }
 
-    fn qname_to_string(name: &QName) -> String {
-        String::from_str(str::from_utf8(name.into_inner()).unwrap()).unwrap()
+    /// Deserializes the attributes of the root element and stops there, its children are for
+    /// the `walk_buffer` of another parser
+    pub fn read_root<T: DeserializeOwned>(
+        &mut self,
+    ) -> Result<T, XmlRoutingError<EventHandlingError>> {
+        let mut buffer = Vec::new();
+
+        loop {
+            buffer.clear();
+            let event_offset = self.reader.buffer_position();
+
+            match self.reader.read_event_into(&mut buffer) {
+                Err(e) => return Err(XmlRoutingError::RoutingError(e)),
+                Ok(Event::Start(start)) | Ok(Event::Empty(start)) => {
+                    self.enter_element(event_offset, 1)?;
+                    // read as empty, so that none of its children are
+                    return self
+                        .deserialize_event(Event::Empty(start))
+                        .map_err(XmlRoutingError::HandlingError);
+                }
+                Ok(Event::Eof) => {
+                    return Err(XmlRoutingError::RoutingError(
+                        quick_xml::Error::UnexpectedEof(String::from("the root element")),
+                    ))
+                }
+                Ok(_) => {}
+            }
+        }
+    }
+
+    fn enter_element<E>(&mut self, offset: usize, depth: usize) -> Result<(), XmlRoutingError<E>> {
+        if depth > self.limits.max_depth {
+            return Err(XmlRoutingError::LimitExceeded(LimitError {
+                limit: Limit::Depth(self.limits.max_depth),
+                offset: offset,
+            }));
+        }
+        self.element_offset = offset;
+        self.element_depth = depth;
+
+        return Ok(());
     }
 
-    // match the route with the router, returns true if it consumed the event
+    fn next_state<E, Context>(
+        &self,
+        router: &Router<'a, R, E, Context>,
+        states: &[TrieState],
+        element: &events::BytesStart,
+        offset: usize,
+    ) -> Result<TrieState, XmlRoutingError<E>> {
+        let (namespace, _) = self.reader.resolve_element(element.name());
+        let parent = match states.last() {
+            Some(parent) => parent,
+            None => return Ok(TrieState::new()),
+        };
+
+        return router
+            .trie
+            .step(parent, element, &namespace, self.reader.decoder())
+            .map_err(|error| match error {
+                quick_xml::Error::NonDecodable(_) => XmlRoutingError::DecodingError {
+                    offset: offset,
+                    error: DeError::InvalidXml(error),
+                },
+                _ => XmlRoutingError::RoutingError(error),
+            });
+    }
+
+    // calls the handler of the first route reached by the element, returns true if it consumed the event
     fn route_xml_event<E, Context>(
         &mut self,
         event: Event,
         router: &mut Router<'a, R, E, Context>,
-        current_route: &mut Vec<String>,
+        state: &TrieState,
         context: &mut Context,
     ) -> Result<bool, E> {
-        // debug!("In {:?}", current_route);
-        for routing in router {
-            // debug!("comparing with {:?}", routing.route);
-            if check_eq_vecs(&routing.route, current_route) {
-                let ret = (&mut routing.handler)(event, self, context);
+        match router.trie.route(state) {
+            Some(index) => {
+                self.element_route.clone_from(&router.labels[index]);
+                let ret = (router.routings[index].handler)(event, self, context);
                 return ret.map(|_| true);
             }
+            None => return Ok(false),
         }
-
-        return Ok(false);
     }
 
     pub fn handle_event<T: DeserializeOwned, F, Ret>(
@@ -158,128 +350,321 @@ impl<'a, R: BufRead> XmlParser<R> {
         &mut self,
         event: Event,
     ) -> Result<T, EventHandlingError> {
-        match event {
-            Event::Empty(e) => self
-                .deserialize_empty_element(e)
-                .map_err(EventHandlingError::DeserializationError),
-            Event::Start(e) => self
-                .deserialize_element(e)
-                .map_err(EventHandlingError::DeserializationError),
-            _ => Err(EventHandlingError::UnsupportedEventType),
-        }
+        let (start, is_empty) = match event {
+            Event::Empty(e) => (e, true),
+            Event::Start(e) => (e, false),
+            _ => return Err(EventHandlingError::UnsupportedEventType),
+        };
+
+        return subtree::deserialize_element(
+            &mut self.reader,
+            &mut self.buffer,
+            start,
+            is_empty,
+            self.element_offset,
+            self.element_depth,
+            self.limits,
+        )
+        .map_err(|e| match e {
+            ElementError::Deserialization(de) => {
+                EventHandlingError::DeserializationError(DeserializationError::DeError(de))
+            }
+            ElementError::Decoding { offset, error } => EventHandlingError::DecodingError {
+                offset: offset,
+                error: error,
+            },
+            ElementError::LimitExceeded(limit) => EventHandlingError::LimitExceeded(limit),
+        });
     }
+}
 
-    /// Deserialize an xml element that has the form : ```<tag />```
-    fn deserialize_empty_element<T: DeserializeOwned>(
-        &mut self,
-        element: events::BytesStart,
-    ) -> Result<T, DeserializationError> {
-        self.buffer.clear();
-
-        let mut w = Writer::new(&mut self.buffer);
-        w.write_event(Event::Empty(element))
-            .map_err(DeserializationError::QuickXmlError)?;
-
-        return deserialize_from_buffer(&mut self.buffer);
+#[cfg(test)]
+mod tests {
+    use quick_xml::{events::Event, NsReader};
+    use serde::{de::IgnoredAny, Deserialize};
+
+    use super::{
+        EventHandlingError, Limit, LineIndex, Router, XmlLimits, XmlParser, XmlPosition,
+        XmlRouting, XmlRoutingError,
+    };
+
+    type Routed = Vec<(String, String)>;
+
+    // routes every element of the document, returns the route and the name of each routed element
+    fn route(document: &str, routes: Vec<Vec<&'static str>>) -> Routed {
+        let routings = routes
+            .into_iter()
+            .map(|route| XmlRouting {
+                route: route,
+                handler: Box::new(
+                    |event: Event, parser: &mut XmlParser<&[u8]>, routed: &mut Routed| {
+                        let name = match &event {
+                            Event::Start(e) | Event::Empty(e) => {
+                                String::from_utf8(e.name().as_ref().to_vec()).unwrap()
+                            }
+                            _ => String::new(),
+                        };
+                        routed.push((parser.element_route().to_string(), name));
+                        return parser.deserialize_event::<IgnoredAny>(event).map(|_| ());
+                    },
+                ),
+            })
+            .collect();
+        let mut router: Router<&[u8], EventHandlingError, Routed> = Router::new(routings).unwrap();
+        let mut parser = XmlParser::new(NsReader::from_reader(document.as_bytes()));
+        let mut routed = Vec::new();
+
+        parser.walk_buffer(&mut router, &mut routed).unwrap();
+
+        return routed;
     }
 
-    /// From https://capnfabs.net/posts/parsing-huge-xml-quickxml-rust-serde/
-    /// Deserialize an XML element that has the form: ```<tag> </tag>```
-    fn deserialize_element<T: DeserializeOwned>(
-        &mut self,
-        start_tag: events::BytesStart,
-    ) -> Result<T, DeserializationError> {
-        let mut depth: u8 = 0;
+    fn routed(expected: &[(&str, &str)]) -> Routed {
+        expected
+            .iter()
+            .map(|(route, name)| (route.to_string(), name.to_string()))
+            .collect()
+    }
 
-        self.buffer.clear();
+    #[test]
+    fn should_route_exact_paths() {
+        let document = "<a><b><c/></b><c/><b><c><b/></c></b></a>";
 
-        let mut w = Writer::new(&mut self.buffer);
+        assert_eq!(
+            route(document, vec![vec!["a", "b", "c"]]),
+            routed(&[("a/b/c", "c"), ("a/b/c", "c")])
+        );
+    }
 
-        let tag_name = start_tag.name();
+    #[test]
+    fn should_route_wildcards() {
+        let document = "<a><b><c/></b><d><c/></d><b><e><c/></e></b></a>";
+
+        assert_eq!(
+            route(document, vec![vec!["a", "*", "c"]]),
+            routed(&[("a/*/c", "c"), ("a/*/c", "c")])
+        );
+        assert_eq!(
+            route(document, vec![vec!["**", "c"]]),
+            routed(&[("**/c", "c"), ("**/c", "c"), ("**/c", "c")])
+        );
+        assert_eq!(
+            route(document, vec![vec!["a", "b", "**", "c"]]),
+            routed(&[("a/b/**/c", "c"), ("a/b/**/c", "c")])
+        );
+    }
 
-        w.write_event(Event::Start(start_tag.clone()))
-            .map_err(DeserializationError::QuickXmlError)?;
+    #[test]
+    fn should_route_on_attributes() {
+        let document = r#"<rules>
+            <rule type="hard" id="1"/><rule type="soft" id="2"/><rule id="3"/>
+        </rules>"#;
+        let routes = vec![
+            vec!["rules", "rule[@type='hard']"],
+            vec!["rules", "rule[@type]"],
+        ];
+
+        assert_eq!(
+            route(document, routes),
+            routed(&[
+                ("rules/rule[@type='hard']", "rule"),
+                ("rules/rule[@type]", "rule")
+            ])
+        );
+    }
 
-        loop {
-            self.serialization_buffer.clear();
-            let event = self
-                .reader
-                .read_event_into(&mut self.serialization_buffer)
-                .map_err(DeserializationError::QuickXmlError)?;
-
-            w.write_event(&event)
-                .map_err(DeserializationError::QuickXmlError)?;
-
-            match event {
-                Event::Start(e) if e.name() == tag_name => depth += 1,
-                Event::End(e) if e.name() == tag_name => {
-                    if depth == 0 {
-                        return deserialize_from_buffer(&mut self.buffer);
-                    }
-                    depth -= 1;
-                }
-                Event::Eof => {
-                    return Err(DeserializationError::QuickXmlError(
-                        quick_xml::Error::UnexpectedEof(format!(
-                            "Unexpected end of file while reading {:?}",
-                            tag_name
-                        )),
-                    ))
-                }
-                _ => {}
-            }
-        }
+    #[test]
+    fn should_route_on_namespaces() {
+        let document = r#"<tt:a xmlns:tt="urn:tt" xmlns:o="urn:other">
+            <tt:b/><o:b/><b/>
+        </tt:a>"#;
+
+        assert_eq!(
+            route(document, vec![vec!["*", "{urn:tt}b"]]),
+            routed(&[("*/{urn:tt}b", "tt:b")])
+        );
+        assert_eq!(
+            route(document, vec![vec!["tt:a", "{}b"]]),
+            routed(&[("tt:a/{}b", "b")])
+        );
+        assert_eq!(
+            route(document, vec![vec!["a", "b"]]),
+            routed(&[("a/b", "tt:b"), ("a/b", "o:b"), ("a/b", "b")])
+        );
     }
-}
 
-/// Errors can be Utf8Error or DeError
-fn deserialize_from_buffer<T: DeserializeOwned>(
-    output_buffer: &mut Vec<u8>,
-) -> Result<T, DeserializationError> {
-    let str = std::str::from_utf8(&output_buffer).map_err(DeserializationError::Utf8Error)?;
+    #[test]
+    fn should_give_elements_to_the_first_matching_route() {
+        let document = "<a><b/><c/></a>";
+        let routes = vec![vec!["a", "b"], vec!["a", "*"], vec!["**"]];
+
+        assert_eq!(route(document, routes), routed(&[("**", "a")]));
+        assert_eq!(
+            route(document, vec![vec!["a", "b"], vec!["a", "*"]]),
+            routed(&[("a/b", "b"), ("a/*", "c")])
+        );
+    }
 
-    return from_str::<T>(str).map_err(DeserializationError::DeError);
-}
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Room {
+        #[serde(rename = "@id")]
+        id: String,
+        label: String,
+    }
 
-fn check_eq_vecs<T: PartialEq<U>, U>(v1: &Vec<T>, v2: &Vec<U>) -> bool {
-    if v1.len() != v2.len() {
-        return false;
+    // deserializes every room of the document
+    fn rooms(document: &[u8]) -> Result<Vec<Room>, XmlRoutingError<EventHandlingError>> {
+        return rooms_within(document, XmlLimits::default());
     }
 
-    let mut v1_iterator = v1.iter();
-    let mut v2_iterator = v2.iter();
+    fn rooms_within(
+        document: &[u8],
+        limits: XmlLimits,
+    ) -> Result<Vec<Room>, XmlRoutingError<EventHandlingError>> {
+        let mut router: Router<&[u8], EventHandlingError, Vec<Room>> =
+            Router::new(vec![XmlRouting {
+                route: vec!["rooms", "room"],
+                handler: Box::new(
+                    |event: Event, parser: &mut XmlParser<&[u8]>, rooms: &mut Vec<Room>| {
+                        return parser.handle_event(event, |room: Room| rooms.push(room));
+                    },
+                ),
+            }])
+            .unwrap();
+        let mut parser = XmlParser::new(NsReader::from_reader(document)).with_limits(limits);
+        let mut rooms = Vec::new();
+
+        parser.walk_buffer(&mut router, &mut rooms)?;
+
+        return Ok(rooms);
+    }
 
-    let mut v1_next = v1_iterator.next();
-    let mut v2_next = v2_iterator.next();
+    #[test]
+    fn should_decode_the_declared_encoding() {
+        // "Amphithéâtre" in ISO-8859-1
+        let document = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?>\n\
+            <rooms><room id=\"Amphith\xe9\xe2tre\"><label>Amphith\xe9\xe2tre</label></room></rooms>";
+
+        assert_eq!(
+            rooms(document).unwrap(),
+            vec![Room {
+                id: String::from("Amphithéâtre"),
+                label: String::from("Amphithéâtre"),
+            }]
+        );
+    }
 
-    while v1_next.is_some() && v2_next.is_some() {
-        if v1_next.unwrap() != v2_next.unwrap() {
-            return false;
+    #[test]
+    fn should_locate_the_bytes_that_can_not_be_decoded() {
+        let document = b"<rooms><room id=\"A\"><label>Amphith\xe9\xe2tre</label></room>\n\
+            <room id=\"B\xff\"><label>B</label></room></rooms>";
+
+        match rooms(document) {
+            Err(XmlRoutingError::HandlingError(EventHandlingError::DecodingError {
+                offset,
+                ..
+            })) => assert_eq!(offset, 27),
+            other => panic!("the text should not be decoded, got {:?}", other),
         }
 
-        v1_next = v1_iterator.next();
-        v2_next = v2_iterator.next();
+        let document = b"<rooms>\n<room id=\"B\xff\"><label>B</label></room></rooms>";
+        match rooms(document) {
+            Err(XmlRoutingError::HandlingError(EventHandlingError::DecodingError {
+                offset,
+                ..
+            })) => assert_eq!(offset, 8),
+            other => panic!("the tag should not be decoded, got {:?}", other),
+        }
     }
 
-    return true;
-}
+    #[test]
+    fn should_locate_the_routed_names_that_can_not_be_decoded() {
+        let document = b"<rooms><room id=\"A\"><label>A</label></room>\n<ro\xffom/></rooms>";
 
-#[cfg(test)]
-mod tests {
-    use super::check_eq_vecs;
+        match rooms(document) {
+            Err(XmlRoutingError::DecodingError { offset, .. }) => assert_eq!(offset, 44),
+            other => panic!("the name should not be decoded, got {:?}", other),
+        }
+    }
 
     #[test]
-    fn should_be_eq_vectors() {
-        let v1: Vec<&'static str> = vec!["a", "b", "c"];
-        let v2 = vec![String::from("a"), String::from("b"), String::from("c")];
+    fn should_stop_at_the_limits() {
+        let limits = XmlLimits {
+            max_depth: 4,
+            max_element_bytes: 64,
+            max_attributes: 2,
+        };
+        let exceeded = |document: &[u8]| match rooms_within(document, limits) {
+            Err(XmlRoutingError::LimitExceeded(error))
+            | Err(XmlRoutingError::HandlingError(EventHandlingError::LimitExceeded(error))) => {
+                (error.limit, error.offset)
+            }
+            other => panic!("a limit should be exceeded, got {:?}", other),
+        };
+
+        assert!(rooms_within(
+            b"<rooms><room id=\"A\"><label>L</label></room></rooms>",
+            limits
+        )
+        .is_ok());
+        assert_eq!(
+            exceeded(b"<a><b><c><d><e/></d></c></b></a>"),
+            (Limit::Depth(4), 12)
+        );
+        assert_eq!(
+            exceeded(b"<rooms><room id=\"A\"><x><y><z/></y></x><label>L</label></room></rooms>"),
+            (Limit::Depth(4), 26)
+        );
+        assert_eq!(
+            exceeded(
+                format!(
+                    "<rooms><room id=\"A\"><label>{}</label></room></rooms>",
+                    "L".repeat(64)
+                )
+                .as_bytes()
+            ),
+            (Limit::ElementBytes(64), 7)
+        );
+        assert_eq!(
+            exceeded(b"<rooms><room id=\"A\" a=\"1\" b=\"2\"><label>L</label></room></rooms>"),
+            (Limit::Attributes(2), 7)
+        );
+    }
 
-        assert!(check_eq_vecs(&v1, &v2));
+    #[test]
+    fn should_read_only_the_root_attributes() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Problem {
+            #[serde(rename = "@nrDays")]
+            nr_days: u32,
+            #[serde(rename = "@nrWeeks")]
+            nr_weeks: u32,
+        }
+        let document = "<?xml version=\"1.0\"?>\n<!-- instance -->\n\
+            <problem nrDays=\"7\" nrWeeks=\"13\"><rooms><room id=\"1\"/></rooms></problem>";
+        let mut parser = XmlParser::new(NsReader::from_reader(document.as_bytes()));
+
+        assert_eq!(
+            parser.read_root::<Problem>().unwrap(),
+            Problem {
+                nr_days: 7,
+                nr_weeks: 13
+            }
+        );
+        assert!(
+            XmlParser::new(NsReader::from_reader("<!-- empty -->".as_bytes()))
+                .read_root::<Problem>()
+                .is_err()
+        );
     }
+
     #[test]
-    fn should_not_be_eq_vectors() {
-        let v1: Vec<&'static str> = vec!["a", "b", "d"];
-        let v2 = vec![String::from("a"), String::from("b"), String::from("c")];
+    fn should_map_offsets_to_positions() {
+        let document = "<a>\n  <b/>\n\n<c/></a>";
+        let index = LineIndex::from_reader(document.as_bytes()).unwrap();
 
-        assert!(false == check_eq_vecs(&v1, &v2));
+        assert_eq!(index.position(0), XmlPosition { line: 1, column: 1 });
+        assert_eq!(index.position(6), XmlPosition { line: 2, column: 3 });
+        assert_eq!(index.position(12), XmlPosition { line: 4, column: 1 });
     }
 }
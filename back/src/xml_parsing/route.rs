@@ -0,0 +1,444 @@
+use std::{collections::HashMap, fmt, str};
+
+use quick_xml::{encoding::Decoder, escape, events::BytesStart, name::ResolveResult};
+
+/// A route segment that could not be parsed
+#[derive(Debug, PartialEq)]
+pub struct RoutePatternError {
+    pub segment: String,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for RoutePatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid route segment {:?}: {}",
+            self.segment, self.reason
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum NamespaceTest {
+    Any,
+    /// `{}name`, the element is in no namespace
+    Unbound,
+    /// `{uri}name`
+    Uri(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum NameTest {
+    /// `*`
+    Any,
+    /// `name`, compared to the local name
+    Local(String),
+    /// `prefix:name`, compared to the name as written in the file
+    Qualified(String),
+}
+
+/// `[@attribute]` or `[@attribute='value']`
+#[derive(Debug, Clone, PartialEq)]
+struct Predicate {
+    attribute: String,
+    value: Option<String>,
+}
+
+/// A segment matching a single element
+#[derive(Debug, Clone, PartialEq)]
+struct Step {
+    namespace: NamespaceTest,
+    name: NameTest,
+    predicates: Vec<Predicate>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Step(Step),
+    /// `**`, matches any number of elements, even none
+    Descendants,
+}
+
+impl Segment {
+    /// Parses segments like `rule`, `*`, `**`, `tt:rule`, `{http://uri}rule` or
+    /// `constraint[@type='hard'][@name]`
+    fn parse(segment: &str) -> Result<Self, RoutePatternError> {
+        let error = |reason| RoutePatternError {
+            segment: segment.to_string(),
+            reason: reason,
+        };
+
+        if segment == "**" {
+            return Ok(Segment::Descendants);
+        }
+
+        let (namespace, rest) = match segment.strip_prefix('{') {
+            Some(rest) => {
+                let (uri, rest) = rest
+                    .split_once('}')
+                    .ok_or_else(|| error("the namespace is not closed"))?;
+                let namespace = match uri {
+                    "" => NamespaceTest::Unbound,
+                    _ => NamespaceTest::Uri(uri.to_string()),
+                };
+                (namespace, rest)
+            }
+            None => (NamespaceTest::Any, segment),
+        };
+
+        let name_end = rest.find('[').unwrap_or(rest.len());
+        let (name, mut rest) = rest.split_at(name_end);
+        let name = match name {
+            "" => return Err(error("the element name is missing")),
+            "*" => NameTest::Any,
+            _ if name.contains(':') => {
+                if namespace != NamespaceTest::Any {
+                    return Err(error("a prefixed name can not have a namespace"));
+                }
+                NameTest::Qualified(name.to_string())
+            }
+            _ => NameTest::Local(name.to_string()),
+        };
+
+        let mut predicates = Vec::new();
+        while !rest.is_empty() {
+            let (predicate, next) = rest
+                .strip_prefix("[@")
+                .and_then(split_predicate)
+                .ok_or_else(|| error("predicates are written [@attribute='value']"))?;
+            predicates.push(Predicate::parse(predicate).ok_or_else(|| error("invalid predicate"))?);
+            rest = next;
+        }
+
+        return Ok(Segment::Step(Step {
+            namespace: namespace,
+            name: name,
+            predicates: predicates,
+        }));
+    }
+}
+
+/// Splits `attribute='value']rest` at the bracket closing the predicate, a quoted value can
+/// hold brackets
+fn split_predicate(predicate: &str) -> Option<(&str, &str)> {
+    let mut quote: Option<char> = None;
+
+    for (index, c) in predicate.char_indices() {
+        match (quote, c) {
+            (None, '\'' | '"') => quote = Some(c),
+            (Some(open), _) if open == c => quote = None,
+            (None, ']') => return Some((&predicate[..index], &predicate[index + 1..])),
+            _ => {}
+        }
+    }
+
+    return None;
+}
+
+impl Predicate {
+    fn parse(predicate: &str) -> Option<Self> {
+        let (attribute, value) = match predicate.split_once('=') {
+            Some((attribute, quoted_value)) => {
+                let value = quoted_value
+                    .strip_prefix('\'')
+                    .and_then(|value| value.strip_suffix('\''))
+                    .or_else(|| {
+                        quoted_value
+                            .strip_prefix('"')
+                            .and_then(|value| value.strip_suffix('"'))
+                    })?;
+                (attribute, Some(value.to_string()))
+            }
+            None => (predicate, None),
+        };
+
+        if attribute.is_empty() {
+            return None;
+        }
+
+        return Some(Predicate {
+            attribute: attribute.to_string(),
+            value: value,
+        });
+    }
+
+    fn matches(&self, element: &BytesStart, decoder: Decoder) -> Result<bool, quick_xml::Error> {
+        let attribute = match element.try_get_attribute(self.attribute.as_bytes())? {
+            Some(attribute) => attribute,
+            None => return Ok(false),
+        };
+
+        return match &self.value {
+            None => Ok(true),
+            Some(value) => {
+                let decoded = decoder.decode(&attribute.value)?;
+                Ok(escape::unescape(&decoded)? == value.as_str())
+            }
+        };
+    }
+}
+
+impl Step {
+    fn matches(
+        &self,
+        element: &BytesStart,
+        namespace: &ResolveResult,
+        decoder: Decoder,
+    ) -> Result<bool, quick_xml::Error> {
+        let namespace_matches = match (&self.namespace, namespace) {
+            (NamespaceTest::Any, _) => true,
+            (NamespaceTest::Unbound, ResolveResult::Unbound) => true,
+            (NamespaceTest::Uri(uri), ResolveResult::Bound(namespace)) => {
+                namespace.as_ref() == uri.as_bytes()
+            }
+            _ => false,
+        };
+
+        let name_matches = match &self.name {
+            NameTest::Any => true,
+            NameTest::Local(name) => {
+                decoder.decode(element.local_name().into_inner())? == name.as_str()
+            }
+            NameTest::Qualified(name) => {
+                decoder.decode(element.name().into_inner())? == name.as_str()
+            }
+        };
+        if !(namespace_matches && name_matches) {
+            return Ok(false);
+        }
+
+        for predicate in &self.predicates {
+            if !predicate.matches(element, decoder)? {
+                return Ok(false);
+            }
+        }
+
+        return Ok(true);
+    }
+
+    // steps that only compare the local name are looked up in a map
+    fn as_local_name(&self) -> Option<&str> {
+        match (&self.namespace, &self.name) {
+            (NamespaceTest::Any, NameTest::Local(name)) if self.predicates.is_empty() => Some(name),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct TrieNode {
+    /// Index of the first route ending on this node
+    route: Option<usize>,
+    by_local_name: HashMap<String, usize>,
+    /// Steps that need more than a local name, tested one by one
+    steps: Vec<(Step, usize)>,
+    descendants: Option<usize>,
+    /// The node of a `**`, that stays active on every child element
+    is_descendants: bool,
+}
+
+/// The routes of a router compiled in a trie, every element of the document is matched against
+/// all the routes at once.
+///
+/// The matching follows the document: the nodes reached by an element are computed from the
+/// nodes reached by its parent.
+pub struct RouteTrie {
+    nodes: Vec<TrieNode>,
+}
+
+/// Nodes of the trie reached by an element, sorted
+pub type TrieState = Vec<usize>;
+
+impl RouteTrie {
+    pub fn new<'r, I>(routes: I) -> Result<Self, RoutePatternError>
+    where
+        I: IntoIterator<Item = &'r [&'static str]>,
+    {
+        let mut trie = RouteTrie {
+            nodes: vec![TrieNode::default()],
+        };
+
+        for (index, route) in routes.into_iter().enumerate() {
+            let mut node = 0;
+            for segment in route {
+                node = trie.child(node, Segment::parse(segment)?);
+            }
+            // the first registered route wins
+            trie.nodes[node].route.get_or_insert(index);
+        }
+
+        return Ok(trie);
+    }
+
+    fn child(&mut self, node: usize, segment: Segment) -> usize {
+        let existing = match &segment {
+            Segment::Descendants => self.nodes[node].descendants,
+            Segment::Step(step) => match step.as_local_name() {
+                Some(name) => self.nodes[node].by_local_name.get(name).copied(),
+                None => self.nodes[node]
+                    .steps
+                    .iter()
+                    .find(|(s, _)| s == step)
+                    .map(|(_, child)| *child),
+            },
+        };
+        if let Some(child) = existing {
+            return child;
+        }
+
+        let child = self.nodes.len();
+        self.nodes.push(TrieNode {
+            is_descendants: segment == Segment::Descendants,
+            ..TrieNode::default()
+        });
+        match segment {
+            Segment::Descendants => self.nodes[node].descendants = Some(child),
+            Segment::Step(step) => match step.as_local_name() {
+                Some(name) => {
+                    self.nodes[node]
+                        .by_local_name
+                        .insert(name.to_string(), child);
+                }
+                None => self.nodes[node].steps.push((step, child)),
+            },
+        }
+
+        return child;
+    }
+
+    /// State before the root element
+    pub fn root(&self) -> TrieState {
+        let mut state = vec![0];
+        self.close(&mut state);
+
+        return state;
+    }
+
+    /// State reached by an element whose parent reached `parent`, the names and the attributes
+    /// of the element are decoded with `decoder`. Fails on a name or a tested attribute that can
+    /// not be decoded or unescaped.
+    pub fn step(
+        &self,
+        parent: &TrieState,
+        element: &BytesStart,
+        namespace: &ResolveResult,
+        decoder: Decoder,
+    ) -> Result<TrieState, quick_xml::Error> {
+        let mut state = Vec::new();
+        if parent.is_empty() {
+            return Ok(state);
+        }
+
+        let local_name = decoder.decode(element.local_name().into_inner())?;
+        for node in parent.iter().map(|node| &self.nodes[*node]) {
+            if let Some(child) = node.by_local_name.get(local_name.as_ref()) {
+                state.push(*child);
+            }
+            for (step, child) in &node.steps {
+                if step.matches(element, namespace, decoder)? {
+                    state.push(*child);
+                }
+            }
+        }
+        // a `**` can match this element too
+        state.extend(
+            parent
+                .iter()
+                .filter(|node| self.nodes[**node].is_descendants),
+        );
+        self.close(&mut state);
+
+        return Ok(state);
+    }
+
+    /// Index of the first route matching the element that reached this state
+    pub fn route(&self, state: &TrieState) -> Option<usize> {
+        state
+            .iter()
+            .filter_map(|node| self.nodes[*node].route)
+            .min()
+    }
+
+    // adds the `**` that can match no element
+    fn close(&self, state: &mut TrieState) {
+        let mut index = 0;
+        while index < state.len() {
+            if let Some(descendants) = self.nodes[state[index]].descendants {
+                state.push(descendants);
+            }
+            index += 1;
+        }
+        state.sort_unstable();
+        state.dedup();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NameTest, NamespaceTest, Predicate, RoutePatternError, Segment, Step};
+
+    #[test]
+    fn should_parse_segments() {
+        assert_eq!(Segment::parse("**"), Ok(Segment::Descendants));
+        assert_eq!(
+            Segment::parse("{http://uri}constraint[@type='hard'][@name]"),
+            Ok(Segment::Step(Step {
+                namespace: NamespaceTest::Uri("http://uri".to_string()),
+                name: NameTest::Local("constraint".to_string()),
+                predicates: vec![
+                    Predicate {
+                        attribute: "type".to_string(),
+                        value: Some("hard".to_string()),
+                    },
+                    Predicate {
+                        attribute: "name".to_string(),
+                        value: None,
+                    },
+                ],
+            }))
+        );
+        assert_eq!(
+            Segment::parse("tt:rule"),
+            Ok(Segment::Step(Step {
+                namespace: NamespaceTest::Any,
+                name: NameTest::Qualified("tt:rule".to_string()),
+                predicates: Vec::new(),
+            }))
+        );
+    }
+
+    #[test]
+    fn should_parse_brackets_in_quoted_values() {
+        assert_eq!(
+            Segment::parse("class[@id='a]b'][@label=\"[x]\"]"),
+            Ok(Segment::Step(Step {
+                namespace: NamespaceTest::Any,
+                name: NameTest::Local("class".to_string()),
+                predicates: vec![
+                    Predicate {
+                        attribute: "id".to_string(),
+                        value: Some("a]b".to_string()),
+                    },
+                    Predicate {
+                        attribute: "label".to_string(),
+                        value: Some("[x]".to_string()),
+                    },
+                ],
+            }))
+        );
+        assert!(Segment::parse("class[@id='a]b]").is_err());
+    }
+
+    #[test]
+    fn should_reject_invalid_segments() {
+        assert!(matches!(
+            Segment::parse("{http://uri"),
+            Err(RoutePatternError { .. })
+        ));
+        assert!(Segment::parse("[@type]").is_err());
+        assert!(Segment::parse("rule[@type='hard'").is_err());
+        assert!(Segment::parse("rule[type='hard']").is_err());
+        assert!(Segment::parse("{}tt:rule").is_err());
+    }
+}
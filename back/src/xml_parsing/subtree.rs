@@ -0,0 +1,829 @@
+use std::{borrow::Cow, collections::VecDeque, io::BufRead, mem, ops::Range, str};
+
+use quick_xml::{
+    encoding::Decoder,
+    escape,
+    events::{BytesStart, Event},
+    DeError, Error, NsReader,
+};
+use serde::de::{
+    self, value::SeqDeserializer, DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess,
+    SeqAccess, Visitor,
+};
+
+use super::limits::{Limit, LimitError, XmlLimits};
+
+/// Deserializes the element that starts with `start` from the events that follow it in the
+/// reader, nothing is copied to be parsed again. The reader is left after the end of the element.
+///
+/// The conventions of `quick_xml::de` are followed: attributes are the `@name` fields, children
+/// are the fields named after their local name, repeated children make a sequence and the text
+/// of an element is its `$text` field.
+///
+/// The tags and the texts are decoded with the encoding declared by the document, `offset` is
+/// the position of `start` in the document and `depth` the number of elements it is in, itself
+/// included.
+pub fn deserialize_element<T: DeserializeOwned, R: BufRead>(
+    reader: &mut NsReader<R>,
+    buffer: &mut Vec<u8>,
+    start: BytesStart,
+    is_empty: bool,
+    offset: usize,
+    depth: usize,
+    limits: XmlLimits,
+) -> Result<T, ElementError> {
+    let mut events = SubtreeEvents {
+        decoder: reader.decoder(),
+        reader: reader,
+        buffer: buffer,
+        lookahead: VecDeque::new(),
+        tags: Vec::new(),
+        attributes: Vec::new(),
+        key: String::new(),
+        limits: limits,
+        element_offset: offset,
+        depth: depth,
+        failure: None,
+    };
+    let tag = push_tag(&mut events.tags, events.decoder, &start, offset).map_err(|error| {
+        ElementError::Decoding {
+            offset: offset,
+            error: DeError::InvalidXml(error),
+        }
+    })?;
+    if is_empty {
+        events.lookahead.push_back(SubtreeEvent::End);
+    }
+
+    return T::deserialize(ElementDeserializer {
+        events: &mut events,
+        tag: tag,
+    })
+    .map_err(|error| match events.failure {
+        Some(Failure::Decoding(offset)) => ElementError::Decoding {
+            offset: offset,
+            error: error,
+        },
+        Some(Failure::Limit(limit)) => ElementError::LimitExceeded(limit),
+        None => ElementError::Deserialization(error),
+    });
+}
+
+/// Why an element could not be deserialized
+#[derive(Debug)]
+pub enum ElementError {
+    Deserialization(DeError),
+    /// Bytes that are not valid in the encoding of the document, `offset` is the position of the
+    /// tag or the text that holds them
+    Decoding {
+        offset: usize,
+        error: DeError,
+    },
+    LimitExceeded(LimitError),
+}
+
+/// What stopped the deserialization, besides the deserialization itself
+enum Failure {
+    /// Where the decoding failed
+    Decoding(usize),
+    Limit(LimitError),
+}
+
+/// A start tag kept in `SubtreeEvents::tags`
+#[derive(Clone, Copy)]
+struct Tag {
+    // where the tag is in the document
+    position: usize,
+    offset: usize,
+    len: usize,
+    name_len: usize,
+}
+
+/// Keeps the start tag in UTF-8, until its element ends the tags of all its parents are before it
+fn push_tag(
+    tags: &mut Vec<u8>,
+    decoder: Decoder,
+    start: &BytesStart,
+    position: usize,
+) -> Result<Tag, Error> {
+    let name = decoder.decode(start.name().into_inner())?;
+    let content = decoder.decode(start)?;
+    let tag = Tag {
+        position: position,
+        offset: tags.len(),
+        len: content.len(),
+        name_len: name.len(),
+    };
+    tags.extend_from_slice(content.as_bytes());
+
+    return Ok(tag);
+}
+
+/// Where the key and the value of an attribute are in `SubtreeEvents::tags`
+struct AttributeSpan {
+    key: Range<usize>,
+    value: Range<usize>,
+}
+
+/// Parses the attributes of the tag once, namespace declarations are not data
+fn push_attributes(
+    tags: &[u8],
+    tag: Tag,
+    attributes: &mut Vec<AttributeSpan>,
+) -> Result<(), DeError> {
+    let content = &tags[tag.offset..tag.offset + tag.len];
+    let start = BytesStart::from_content(str::from_utf8(content)?, tag.name_len);
+    // the spans are found back from the slices borrowed in the tag
+    let span = |slice: &[u8]| {
+        let offset = tag.offset + (slice.as_ptr() as usize - content.as_ptr() as usize);
+        offset..offset + slice.len()
+    };
+
+    for attribute in start.attributes().with_checks(false) {
+        let attribute = attribute?;
+        let key = attribute.key.as_ref();
+        if key == b"xmlns" || key.starts_with(b"xmlns:") {
+            continue;
+        }
+        attributes.push(AttributeSpan {
+            key: span(key),
+            value: span(&attribute.value),
+        });
+    }
+
+    return Ok(());
+}
+
+fn tag_local_name(tags: &[u8], tag: Tag) -> &[u8] {
+    let name = &tags[tag.offset..tag.offset + tag.name_len];
+
+    return match name.iter().position(|byte| *byte == b':') {
+        Some(colon) => &name[colon + 1..],
+        None => name,
+    };
+}
+
+/// What the deserializer needs from the events of the reader
+enum SubtreeEvent {
+    Start(Tag),
+    End,
+    /// Unescaped text or CDATA
+    Text(String),
+    Eof,
+}
+
+/// The events of the subtree, empty elements are given as a start followed by an end.
+///
+/// Nothing is allocated for the tags, the open ones are kept one after the other in a buffer.
+struct SubtreeEvents<'r, R: BufRead> {
+    reader: &'r mut NsReader<R>,
+    // the encoding declared by the document
+    decoder: Decoder,
+    buffer: &'r mut Vec<u8>,
+    lookahead: VecDeque<SubtreeEvent>,
+    tags: Vec<u8>,
+    // the attributes of the element being deserialized
+    attributes: Vec<AttributeSpan>,
+    // reused to build the keys of the attributes
+    key: String,
+    limits: XmlLimits,
+    // where the deserialized element starts
+    element_offset: usize,
+    // elements open in the document
+    depth: usize,
+    failure: Option<Failure>,
+}
+
+/// Remembers where the decoding failed, the error goes through the deserializer as any other
+fn decoding_error(failure: &mut Option<Failure>, offset: usize, error: Error) -> DeError {
+    if let Error::NonDecodable(_) = error {
+        *failure = Some(Failure::Decoding(offset));
+    }
+
+    return DeError::InvalidXml(error);
+}
+
+fn limit_error(failure: &mut Option<Failure>, limit: Limit, offset: usize) -> DeError {
+    let error = LimitError {
+        limit: limit,
+        offset: offset,
+    };
+    *failure = Some(Failure::Limit(error));
+
+    return DeError::Custom(error.to_string());
+}
+
+impl<'r, R: BufRead> SubtreeEvents<'r, R> {
+    fn next(&mut self) -> Result<SubtreeEvent, DeError> {
+        if let Some(event) = self.lookahead.pop_front() {
+            return Ok(event);
+        }
+
+        loop {
+            self.buffer.clear();
+            let offset = self.reader.buffer_position();
+            let event = self.reader.read_event_into(self.buffer)?;
+            let failure = &mut self.failure;
+
+            let max_bytes = self.limits.max_element_bytes;
+            if self.reader.buffer_position() - self.element_offset > max_bytes {
+                return Err(limit_error(
+                    failure,
+                    Limit::ElementBytes(max_bytes),
+                    self.element_offset,
+                ));
+            }
+            let max_depth = self.limits.max_depth;
+            if let Event::Start(_) | Event::Empty(_) = event {
+                if self.depth >= max_depth {
+                    return Err(limit_error(failure, Limit::Depth(max_depth), offset));
+                }
+            }
+
+            match event {
+                Event::Start(e) => {
+                    self.depth += 1;
+                    return push_tag(&mut self.tags, self.decoder, &e, offset)
+                        .map(SubtreeEvent::Start)
+                        .map_err(|error| decoding_error(failure, offset, error));
+                }
+                Event::Empty(e) => {
+                    let tag = push_tag(&mut self.tags, self.decoder, &e, offset)
+                        .map_err(|error| decoding_error(failure, offset, error))?;
+                    self.lookahead.push_back(SubtreeEvent::End);
+                    return Ok(SubtreeEvent::Start(tag));
+                }
+                Event::End(_) => {
+                    self.depth -= 1;
+                    return Ok(SubtreeEvent::End);
+                }
+                Event::Text(mut text) => {
+                    // blank texts are only indentation
+                    if text.inplace_trim_start() {
+                        continue;
+                    }
+                    text.inplace_trim_end();
+                    return text
+                        .unescape()
+                        .map(|text| SubtreeEvent::Text(text.into_owned()))
+                        .map_err(|error| decoding_error(failure, offset, error));
+                }
+                Event::CData(cdata) => {
+                    return self
+                        .decoder
+                        .decode(&cdata)
+                        .map(|text| SubtreeEvent::Text(text.into_owned()))
+                        .map_err(|error| decoding_error(failure, offset, error))
+                }
+                Event::Eof => return Ok(SubtreeEvent::Eof),
+                // comments, processing instructions...
+                _ => {}
+            }
+        }
+    }
+
+    fn peek(&mut self) -> Result<&SubtreeEvent, DeError> {
+        if self.lookahead.is_empty() {
+            let event = self.next()?;
+            self.lookahead.push_front(event);
+        }
+
+        return Ok(&self.lookahead[0]);
+    }
+
+    /// Reads the end of the element of `tag`, which has no more children
+    fn end(&mut self, tag: Tag) -> Result<(), DeError> {
+        match self.next()? {
+            SubtreeEvent::End => {
+                self.tags.truncate(tag.offset);
+                return Ok(());
+            }
+            SubtreeEvent::Start(child) => {
+                let name = &self.tags[child.offset..child.offset + child.name_len];
+                return Err(DeError::UnexpectedStart(name.to_vec()));
+            }
+            SubtreeEvent::Text(_) => return Err(DeError::Custom(String::from("unexpected text"))),
+            SubtreeEvent::Eof => return Err(DeError::UnexpectedEof),
+        }
+    }
+
+    /// Skips the rest of the element of `tag`
+    fn skip_element(&mut self, tag: Tag) -> Result<(), DeError> {
+        let mut depth: usize = 0;
+
+        loop {
+            match self.next()? {
+                SubtreeEvent::Start(_) => depth += 1,
+                SubtreeEvent::End if depth == 0 => {
+                    self.tags.truncate(tag.offset);
+                    return Ok(());
+                }
+                SubtreeEvent::End => depth -= 1,
+                SubtreeEvent::Eof => return Err(DeError::UnexpectedEof),
+                SubtreeEvent::Text(_) => {}
+            }
+        }
+    }
+
+    /// The texts that follow, up to the next tag
+    fn read_text(&mut self) -> Result<String, DeError> {
+        let mut text = String::new();
+
+        while let SubtreeEvent::Text(_) = self.peek()? {
+            if let SubtreeEvent::Text(t) = self.next()? {
+                text.push_str(&t);
+            }
+        }
+
+        return Ok(text);
+    }
+
+    /// The text of the element of `tag`, with its end
+    fn read_element_text(&mut self, tag: Tag) -> Result<String, DeError> {
+        let text = self.read_text()?;
+        self.end(tag)?;
+
+        return Ok(text);
+    }
+}
+
+/// An element whose start was read
+struct ElementDeserializer<'a, 'r, R: BufRead> {
+    events: &'a mut SubtreeEvents<'r, R>,
+    tag: Tag,
+}
+
+macro_rules! deserialize_element_text {
+    ($($method:ident),*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+                TextDeserializer(Cow::Owned(self.events.read_element_text(self.tag)?)).$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de, 'a, 'r, R: BufRead> de::Deserializer<'de> for ElementDeserializer<'a, 'r, R> {
+    type Error = DeError;
+
+    deserialize_element_text!(
+        deserialize_bool,
+        deserialize_i8,
+        deserialize_i16,
+        deserialize_i32,
+        deserialize_i64,
+        deserialize_u8,
+        deserialize_u16,
+        deserialize_u32,
+        deserialize_u64,
+        deserialize_f32,
+        deserialize_f64,
+        deserialize_char,
+        deserialize_str,
+        deserialize_string,
+        deserialize_bytes,
+        deserialize_byte_buf,
+        deserialize_identifier,
+        deserialize_seq
+    );
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.events.skip_element(self.tag)?;
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        let first_attribute = self.events.attributes.len();
+        push_attributes(&self.events.tags, self.tag, &mut self.events.attributes)?;
+
+        let max_attributes = self.events.limits.max_attributes;
+        if self.events.attributes.len() - first_attribute > max_attributes {
+            return Err(limit_error(
+                &mut self.events.failure,
+                Limit::Attributes(max_attributes),
+                self.tag.position,
+            ));
+        }
+
+        visitor.visit_map(ElementMap {
+            attributes: first_attribute..self.events.attributes.len(),
+            next_attribute: first_attribute,
+            events: self.events,
+            tag: self.tag,
+            value: MapValue::None,
+        })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        TextDeserializer(Cow::Owned(self.events.read_element_text(self.tag)?))
+            .deserialize_enum(name, variants, visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_unit(visitor)
+    }
+}
+
+enum MapValue {
+    None,
+    Attribute(usize),
+    Child,
+    Text,
+}
+
+/// The attributes, then the children of an element
+struct ElementMap<'a, 'r, R: BufRead> {
+    events: &'a mut SubtreeEvents<'r, R>,
+    tag: Tag,
+    // in `SubtreeEvents::attributes`, they are all read before the first child
+    attributes: Range<usize>,
+    next_attribute: usize,
+    value: MapValue,
+}
+
+impl<'de, 'a, 'r, R: BufRead> MapAccess<'de> for ElementMap<'a, 'r, R> {
+    type Error = DeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, DeError> {
+        if self.next_attribute < self.attributes.end {
+            let SubtreeEvents {
+                tags,
+                attributes,
+                key,
+                ..
+            } = &mut *self.events;
+            self.value = MapValue::Attribute(self.next_attribute);
+            let attribute_key = &tags[attributes[self.next_attribute].key.clone()];
+            self.next_attribute += 1;
+
+            key.clear();
+            key.push('@');
+            key.push_str(str::from_utf8(attribute_key)?);
+            return seed.deserialize(key.as_str().into_deserializer()).map(Some);
+        }
+        if !self.attributes.is_empty() {
+            // the children can reuse the place of the attributes
+            self.events.attributes.truncate(self.attributes.start);
+            self.attributes = self.attributes.start..self.attributes.start;
+        }
+
+        match self.events.peek()? {
+            SubtreeEvent::Start(child) => {
+                let child = *child;
+                self.value = MapValue::Child;
+                let name = str::from_utf8(tag_local_name(&self.events.tags, child))?;
+                return seed.deserialize(name.into_deserializer()).map(Some);
+            }
+            SubtreeEvent::Text(_) => {
+                self.value = MapValue::Text;
+                return seed.deserialize("$text".into_deserializer()).map(Some);
+            }
+            SubtreeEvent::End => {
+                self.events.end(self.tag)?;
+                return Ok(None);
+            }
+            SubtreeEvent::Eof => return Err(DeError::UnexpectedEof),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, DeError> {
+        match mem::replace(&mut self.value, MapValue::None) {
+            MapValue::Attribute(index) => {
+                // the tags are kept in UTF-8
+                let value = &self.events.tags[self.events.attributes[index].value.clone()];
+                let value = escape::unescape(str::from_utf8(value)?).map_err(Error::from)?;
+                seed.deserialize(TextDeserializer(value))
+            }
+            MapValue::Child => seed.deserialize(ChildDeserializer {
+                events: &mut *self.events,
+            }),
+            MapValue::Text => {
+                seed.deserialize(TextDeserializer(Cow::Owned(self.events.read_text()?)))
+            }
+            MapValue::None => Err(DeError::KeyNotRead),
+        }
+    }
+}
+
+/// The next children of an element, a sequence if they have the same name
+struct ChildDeserializer<'a, 'r, R: BufRead> {
+    events: &'a mut SubtreeEvents<'r, R>,
+}
+
+impl<'a, 'r, R: BufRead> ChildDeserializer<'a, 'r, R> {
+    fn into_element(self) -> Result<ElementDeserializer<'a, 'r, R>, DeError> {
+        match self.events.next()? {
+            SubtreeEvent::Start(tag) => Ok(ElementDeserializer {
+                events: self.events,
+                tag: tag,
+            }),
+            _ => Err(DeError::ExpectedStart),
+        }
+    }
+}
+
+macro_rules! deserialize_child {
+    ($($method:ident),*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+                self.into_element()?.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de, 'a, 'r, R: BufRead> de::Deserializer<'de> for ChildDeserializer<'a, 'r, R> {
+    type Error = DeError;
+
+    deserialize_child!(
+        deserialize_any,
+        deserialize_bool,
+        deserialize_i8,
+        deserialize_i16,
+        deserialize_i32,
+        deserialize_i64,
+        deserialize_u8,
+        deserialize_u16,
+        deserialize_u32,
+        deserialize_u64,
+        deserialize_f32,
+        deserialize_f64,
+        deserialize_char,
+        deserialize_str,
+        deserialize_string,
+        deserialize_bytes,
+        deserialize_byte_buf,
+        deserialize_identifier,
+        deserialize_option,
+        deserialize_unit,
+        deserialize_map,
+        deserialize_ignored_any
+    );
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_seq(ChildrenSeq {
+            events: self.events,
+            name: None,
+        })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.into_element()?.deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.into_element()?
+            .deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.into_element()?
+            .deserialize_enum(name, variants, visitor)
+    }
+}
+
+/// The following children that have the name of the first one
+struct ChildrenSeq<'a, 'r, R: BufRead> {
+    events: &'a mut SubtreeEvents<'r, R>,
+    name: Option<Vec<u8>>,
+}
+
+impl<'de, 'a, 'r, R: BufRead> SeqAccess<'de> for ChildrenSeq<'a, 'r, R> {
+    type Error = DeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, DeError> {
+        let child = match self.events.peek()? {
+            SubtreeEvent::Start(child) => *child,
+            _ => return Ok(None),
+        };
+        let child_name = tag_local_name(&self.events.tags, child);
+        match &self.name {
+            Some(name) if name != child_name => return Ok(None),
+            Some(_) => {}
+            None => self.name = Some(child_name.to_vec()),
+        }
+
+        return ChildDeserializer {
+            events: &mut *self.events,
+        }
+        .into_element()
+        .and_then(|element| seed.deserialize(element))
+        .map(Some);
+    }
+}
+
+/// The value of an attribute, or the text of an element
+struct TextDeserializer<'t>(Cow<'t, str>);
+
+impl<'de, 't> IntoDeserializer<'de, DeError> for TextDeserializer<'t> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident),*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+                visitor.$visit(self.0.trim().parse()?)
+            }
+        )*
+    };
+}
+
+impl<'de, 't> de::Deserializer<'de> for TextDeserializer<'t> {
+    type Error = DeError;
+
+    deserialize_parsed!(
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64
+    );
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self.0 {
+            Cow::Borrowed(text) => visitor.visit_str(text),
+            Cow::Owned(text) => visitor.visit_string(text),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self.0.trim() {
+            "true" | "1" => visitor.visit_bool(true),
+            "false" | "0" => visitor.visit_bool(false),
+            _ => Err(DeError::InvalidBoolean(self.0.into_owned())),
+        }
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        let mut chars = self.0.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => visitor.visit_char(c),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// A list separated by whitespaces
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_seq(SeqDeserializer::new(
+            self.0
+                .split_whitespace()
+                .map(|item| TextDeserializer(Cow::Borrowed(item))),
+        ))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        visitor.visit_enum(IntoDeserializer::<DeError>::into_deserializer(
+            self.0.into_owned(),
+        ))
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, DeError> {
+        Err(DeError::Unsupported(
+            "a text can not be deserialized as a map".into(),
+        ))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.deserialize_map(visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        str string bytes byte_buf identifier ignored_any unit_struct tuple tuple_struct
+    }
+}
@@ -12,7 +12,7 @@ import Calendar from "./CalendarPageBody/Calendar";
 import { Box, Typography } from "@mui/material";
 import FullCalendar from "@fullcalendar/react";
 import { useNavigate, useSearchParams } from "react-router-dom";
-import sdk from "../../utils/sdk";
+import sdk, { ImportJobError } from "../../utils/sdk";
 import {
   SolutionFiltersInfo,
   ReadSolutionBody,
@@ -33,6 +33,7 @@ import { toast } from "react-toastify";
 import { VisuallyHiddenInput } from "../VisuallyHiddenInput";
 import { timestampStrToDateTime } from "../../utils/dates";
 import ImportErrorDialog from "./ImportErrorDialog";
+import { importSolutionWithToast } from "../../utils/importSolution";
 import { isAxiosError } from "axios";
 
 interface Props {
@@ -264,11 +265,7 @@ const CalendarPage: FC<Props> = ({ solutionId }) => {
       }
       try {
         const file = e.target.files[0];
-        const data = await toast.promise(sdk.importSolution(file), {
-          pending: "Import de la solution...",
-          error: "Echec de l'import de la solution",
-          success: "🚀 Solution importée avec succès !",
-        });
+        const data = await importSolutionWithToast(file);
 
         navigate(`/calendar/${data.id}`);
       } catch (err) {
@@ -276,6 +273,9 @@ const CalendarPage: FC<Props> = ({ solutionId }) => {
         if (isAxiosError(err)) {
           setImportError(err.response?.data);
           setImportErrorDialogOpen(true);
+        } else if (err instanceof ImportJobError) {
+          setImportError(err.message);
+          setImportErrorDialogOpen(true);
         }
       }
     },
@@ -1,8 +1,8 @@
 import { ChangeEvent, FC, useCallback, useRef, useState } from "react";
 import { Alert, Box, Button, Divider } from "@mui/material";
 import { VisuallyHiddenInput } from "../VisuallyHiddenInput";
-import { toast } from "react-toastify";
-import sdk from "../../utils/sdk";
+import { ImportJobError } from "../../utils/sdk";
+import { importSolutionWithToast } from "../../utils/importSolution";
 import { useNavigate } from "react-router-dom";
 import { CalendarMonth } from "@mui/icons-material";
 import CalendarSearchButton from "./CalendarSearch/CalendarSearchButton";
@@ -32,11 +32,7 @@ const NewCalendarPage: FC = () => {
       }
       try {
         const file = e.target.files[0];
-        const data = await toast.promise(sdk.importSolution(file), {
-          pending: "Import de la solution...",
-          error: "Echec de l'import de la solution",
-          success: "🚀 Solution importée avec succès !",
-        });
+        const data = await importSolutionWithToast(file);
 
         navigate(`/calendar/${data.id}`);
       } catch (err) {
@@ -44,6 +40,9 @@ const NewCalendarPage: FC = () => {
         if (isAxiosError(err)) {
           setImportError(err.response?.data);
           setImportErrorDialogOpen(true);
+        } else if (err instanceof ImportJobError) {
+          setImportError(err.message);
+          setImportErrorDialogOpen(true);
         }
       }
     },
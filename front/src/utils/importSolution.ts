@@ -0,0 +1,37 @@
+import { toast } from "react-toastify";
+import sdk from "./sdk";
+import { ImportSolutionResponse } from "../types/api";
+
+// the toast closes itself once its progress reaches 1
+const MAX_PENDING_PROGRESS = 0.99;
+
+/** Imports a solution, following its progress in a toast */
+export async function importSolutionWithToast(
+  file: File
+): Promise<ImportSolutionResponse> {
+  const toastId = toast.loading("Import de la solution...");
+  try {
+    const data = await sdk.importSolution(file, (progress) =>
+      toast.update(toastId, {
+        progress: Math.min(progress, MAX_PENDING_PROGRESS),
+      })
+    );
+    toast.update(toastId, {
+      render: "🚀 Solution importée avec succès !",
+      type: "success",
+      isLoading: false,
+      progress: undefined,
+      autoClose: 5000,
+    });
+    return data;
+  } catch (err) {
+    toast.update(toastId, {
+      render: "Echec de l'import de la solution",
+      type: "error",
+      isLoading: false,
+      progress: undefined,
+      autoClose: 5000,
+    });
+    throw err;
+  }
+}
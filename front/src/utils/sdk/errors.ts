@@ -0,0 +1,2 @@
+/** The import job of a solution failed on the server */
+export class ImportJobError extends Error {}
@@ -6,6 +6,7 @@ import {
 } from "../../types/api";
 import { ShortSessionInfo } from "../../types/core";
 import { SDK } from "./sdk";
+export { ImportJobError } from "./errors";
 // import { SDKMock } from "./sdkMock";
 
 export interface ISDK {
@@ -15,7 +16,11 @@ export interface ISDK {
     solutionId: string,
     body: ReadSolutionBody
   ): Promise<ShortSessionInfo[]>;
-  importSolution(file: File): Promise<ImportSolutionResponse>;
+  /** `onProgress` receives the part of the file read, between 0 and 1 */
+  importSolution(
+    file: File,
+    onProgress?: (progress: number) => void
+  ): Promise<ImportSolutionResponse>;
   getSolution(solutionId: string): Promise<ShortSolutionInfo>;
 }
 
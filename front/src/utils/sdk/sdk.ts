@@ -7,6 +7,22 @@ import {
 } from "../../types/api";
 import { ISDK } from ".";
 import { ShortSessionInfo } from "../../types/core";
+import { ImportJobError } from "./errors";
+
+const IMPORT_POLLING_DELAY_MS = 1000;
+
+interface ImportJob {
+  id: string;
+  phase: "queued" | "parsing" | "inserting" | "done" | "failed";
+  rows_inserted: number;
+  solution_id: number | null;
+  error: { message: string } | null;
+}
+
+interface ImportProgress {
+  bytes_read: number;
+  bytes_total: number;
+}
 
 export class SDK implements ISDK {
   private client: Axios;
@@ -54,12 +70,42 @@ export class SDK implements ISDK {
     }));
   }
 
-  public async importSolution(file: File): Promise<ImportSolutionResponse> {
-    const { data } = await this.client.postForm<{
-      id: number;
-      row_inserted: number;
-    }>("/solutions", { solution: file });
-    return { id: data.id.toString(), rowsInserted: data.row_inserted };
+  public async importSolution(
+    file: File,
+    onProgress?: (progress: number) => void
+  ): Promise<ImportSolutionResponse> {
+    const { data } = await this.client.postForm<ImportJob>("/solutions", {
+      solution: file,
+    });
+    const events =
+      onProgress && new EventSource(`/api/imports/${data.id}/events`);
+    events?.addEventListener("progress", (e: MessageEvent<string>) => {
+      const progress = JSON.parse(e.data) as ImportProgress;
+      onProgress?.(progress.bytes_read / Math.max(progress.bytes_total, 1));
+    });
+    let job: ImportJob;
+    try {
+      job = await this.waitForImport(data.id);
+    } finally {
+      events?.close();
+    }
+    if (job.solution_id === null) {
+      throw new ImportJobError(job.error?.message ?? "The import failed");
+    }
+    return { id: job.solution_id.toString(), rowsInserted: job.rows_inserted };
+  }
+
+  // the import runs in the background, its job is polled until it ends
+  private async waitForImport(jobId: string): Promise<ImportJob> {
+    for (;;) {
+      const { data } = await this.client.get<ImportJob>(`/imports/${jobId}`);
+      if (data.phase === "done" || data.phase === "failed") {
+        return data;
+      }
+      await new Promise((resolve) =>
+        setTimeout(resolve, IMPORT_POLLING_DELAY_MS)
+      );
+    }
   }
 
   public async getSolution(solutionId: string): Promise<ShortSolutionInfo> {
@@ -71,7 +71,10 @@ export class SDKMock implements ISDK {
     return solutions;
   }
 
-  public async importSolution(_file: File): Promise<ImportSolutionResponse> {
+  public async importSolution(
+    _file: File,
+    _onProgress?: (progress: number) => void
+  ): Promise<ImportSolutionResponse> {
     return {
       id: "35",
       rowsInserted: 2,